@@ -0,0 +1,53 @@
+// Headless instruction throughput, run with `cargo bench --bench dispatch`
+
+use std::time::Instant;
+
+use rust_nes::{cpu::Cpu, rom::Rom};
+
+const INSTRUCTIONS: u64 = 10_000_000;
+
+// Mix of addressing modes looping forever over zero page
+fn program() -> Vec<u8>
+{
+    vec![
+        0xA2, 0x00,       // LDX #$00
+        0xA9, 0x01,       // loop: LDA #$01
+        0x75, 0x10,       // ADC $10,X
+        0x95, 0x10,       // STA $10,X
+        0xBD, 0x00, 0x80, // LDA $8000,X
+        0x49, 0xFF,       // EOR #$FF
+        0x0A,             // ASL A
+        0xE6, 0x20,       // INC $20
+        0xE8,             // INX
+        0xE0, 0x40,       // CPX #$40
+        0xD0, 0xED,       // BNE loop
+        0x4C, 0x00, 0x80, // JMP $8000
+    ]
+}
+
+fn main()
+{
+    let mut prg = vec![0xEA; 0x8000];
+    let code = program();
+
+    prg[..code.len()].copy_from_slice(&code);
+    prg[0x7FFC] = 0x00;
+    prg[0x7FFD] = 0x80;
+
+    let mut cpu = Cpu::new();
+
+    cpu.load_rom(Rom { prg, ..Rom::empty() }).unwrap();
+    cpu.reset();
+
+    let start = Instant::now();
+
+    for _ in 0..INSTRUCTIONS
+    {
+        cpu.step().unwrap();
+    }
+
+    let elapsed = start.elapsed();
+
+    println!("{} instructions in {:.3}s", INSTRUCTIONS, elapsed.as_secs_f64());
+    println!("{:.2} M instructions/s", INSTRUCTIONS as f64 / elapsed.as_secs_f64() / 1_000_000.0);
+}
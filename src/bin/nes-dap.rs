@@ -0,0 +1,16 @@
+use std::io;
+use std::process::exit;
+
+use rust_nes::dap::DapServer;
+
+// Editors start the adapter and talk to it over stdio, the ROM comes with the launch request
+fn main()
+{
+    let mut server = DapServer::new(io::stdout());
+
+    if let Err(err) = server.serve(io::stdin())
+    {
+        eprintln!("{}", err);
+        exit(1);
+    }
+}
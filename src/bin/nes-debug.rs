@@ -0,0 +1,268 @@
+use std::io::{self, BufRead, Write};
+use std::process::exit;
+
+use rust_nes::cpu::{Cpu, Access, asm};
+use rust_nes::debugger::{Debugger, StopReason};
+use rust_nes::rom::Rom;
+
+const HELP: &str = "\
+Commands (numbers are decimal, or hexadecimal with a $ or 0x prefix):
+  s, step               execute one instruction
+  n, next               step over a JSR
+  o, out                run until the current subroutine returns
+  c, continue           run until a breakpoint, a watchpoint or an error
+  limit [count]         show or set the most instructions next, out and continue execute
+  b, break <addr>       add an execution breakpoint
+  rw <addr>             add a read watchpoint
+  ww <addr>             add a write watchpoint
+  d, delete <addr>      remove the breakpoint and watchpoints at an address
+  l, list               list breakpoints and watchpoints
+  r, regs               show the registers
+  set <reg> <value>     change a register (a, x, y, sp, pc, p)
+  x <addr> [len]        hex dump of the memory
+  poke <addr> <value>   write a byte into memory
+  u [addr] [count]      disassemble around an address, PC by default
+  h, help               show this help
+  q, quit               exit";
+
+// Instructions shown before and after the address by default
+const DISASM_BEFORE: usize = 4;
+const DISASM_AFTER: usize = 8;
+
+const HEX_DUMP_LENGTH: u16 = 64;
+
+// Keeps a program stuck in a loop from hanging the debugger, a few seconds of emulation
+const DEFAULT_LIMIT: u64 = 10_000_000;
+
+fn main()
+{
+    let args: Vec<String> = std::env::args().collect();
+
+    let cpu = match &args[1..]
+    {
+        [path] => load_rom(path),
+        [flag, path] if flag == "--asm" => load_source(path),
+        _ => {
+            eprintln!("Usage: {0} <rom.nes>\n       {0} --asm <source.s>", args[0]);
+            exit(1);
+        }
+    };
+
+    let mut debugger = Debugger::new(cpu);
+    let mut limit = DEFAULT_LIMIT;
+
+    println!("Type 'help' for the list of commands");
+    print_location(&debugger);
+
+    let stdin = io::stdin();
+
+    loop
+    {
+        print!("({:04X}) ", debugger.pc());
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+
+        // Stop on end of input
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 { break }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        if words.is_empty() { continue }
+
+        if let Err(err) = execute(&mut debugger, &mut limit, &words)
+        {
+            println!("{}", err);
+        }
+    }
+}
+
+fn load_rom(path: &str) -> Cpu
+{
+    let rom = Rom::from_file(path).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+
+    let mut cpu = Cpu::new();
+    cpu.load_rom(rom).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+    cpu.reset();
+
+    cpu
+}
+
+// The program is loaded in a flat RAM and starts at its origin
+fn load_source(path: &str) -> Cpu
+{
+    let source = std::fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Unable to read file '{}', ({})", path, err);
+        exit(1);
+    });
+
+    let assembly = asm::assemble(&source).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        exit(1);
+    });
+
+    let mut cpu = Cpu::flat();
+
+    if let Err(err) = cpu.load_at(assembly.origin, assembly.bytes)
+    {
+        eprintln!("{}: {}", path, err);
+        exit(1);
+    }
+    cpu.reset();
+
+    cpu
+}
+
+fn execute(debugger: &mut Debugger, limit: &mut u64, words: &[&str]) -> Result<(), String>
+{
+    match words
+    {
+        ["s" | "step"] => {
+            let stop = debugger.step();
+            report(debugger, stop);
+        },
+        ["n" | "next"] => {
+            let stop = debugger.step_over(*limit);
+            report(debugger, stop);
+        },
+        ["o" | "out"] => {
+            let stop = debugger.step_out(*limit);
+            report(debugger, stop);
+        },
+        ["c" | "continue"] => {
+            let stop = debugger.resume(*limit);
+            report(debugger, stop);
+        },
+        ["limit"] => println!("{} instructions", limit),
+        ["limit", count] => *limit = count.parse().map_err(|_| format!("Invalid number '{}'", count))?,
+
+        ["b" | "break", addr] => debugger.add_breakpoint(parse(addr)?),
+        ["rw", addr] => debugger.add_watchpoint(parse(addr)?, Access::Read),
+        ["ww", addr] => debugger.add_watchpoint(parse(addr)?, Access::Write),
+        ["d" | "delete", addr] => {
+            let addr = parse(addr)?;
+
+            debugger.remove_breakpoint(addr);
+            debugger.remove_watchpoint(addr, Access::Read);
+            debugger.remove_watchpoint(addr, Access::Write);
+        },
+        ["l" | "list"] => {
+            for addr in debugger.breakpoints()
+            {
+                println!("break  ${:04X}", addr);
+            }
+
+            for (addr, access) in debugger.watchpoints()
+            {
+                println!("{:<5}  ${:04X}", if *access == Access::Read { "rw" } else { "ww" }, addr);
+            }
+        },
+
+        ["r" | "regs"] => println!("{}", debugger.cpu.registers()),
+        ["set", register, value] => {
+            let value = parse(value)?;
+            let mut registers = debugger.cpu.registers();
+
+            match register.to_lowercase().as_str()
+            {
+                "a"  => registers.a = byte(value)?,
+                "x"  => registers.x = byte(value)?,
+                "y"  => registers.y = byte(value)?,
+                "sp" => registers.sp = byte(value)?,
+                "p"  => registers.p = byte(value)?,
+                "pc" => registers.pc = value,
+                _ => return Err(format!("Unknown register '{}'", register)),
+            }
+
+            debugger.cpu.set_registers(registers);
+            println!("{}", debugger.cpu.registers());
+        },
+
+        ["x", addr] => hex_dump(debugger, parse(addr)?, HEX_DUMP_LENGTH),
+        ["x", addr, length] => hex_dump(debugger, parse(addr)?, parse(length)?),
+        ["poke", addr, value] => {
+            debugger.cpu.memory.try_write(parse(addr)?, byte(parse(value)?)?).map_err(|err| err.to_string())?;
+        },
+
+        ["u"] => disassemble(debugger, debugger.pc(), DISASM_BEFORE, DISASM_AFTER),
+        ["u", addr] => disassemble(debugger, parse(addr)?, DISASM_BEFORE, DISASM_AFTER),
+        ["u", addr, count] => disassemble(debugger, parse(addr)?, 0, parse(count)? as usize),
+
+        ["h" | "help"] => println!("{}", HELP),
+        ["q" | "quit"] => exit(0),
+
+        _ => return Err(format!("Unknown command '{}', type 'help' for the list of commands", words.join(" "))),
+    }
+
+    Ok(())
+}
+
+fn report(debugger: &Debugger, stop: StopReason)
+{
+    match stop
+    {
+        StopReason::Step => {},
+        StopReason::Limit => println!("Instruction limit reached, see 'limit'"),
+        StopReason::Breakpoint(addr) => println!("Breakpoint at ${:04X}", addr),
+        StopReason::Watchpoint(hit) => {
+            let access = if hit.access == Access::Read { "Read" } else { "Write" };
+            println!("{} of ${:02X} at ${:04X}", access, hit.value, hit.addr);
+        },
+        StopReason::Error(err) => println!("{}", err),
+    }
+
+    print_location(debugger);
+}
+
+fn print_location(debugger: &Debugger)
+{
+    println!("{}", debugger.cpu.registers());
+    disassemble(debugger, debugger.pc(), 0, 1);
+}
+
+fn disassemble(debugger: &Debugger, addr: u16, before: usize, after: usize)
+{
+    for instruction in debugger.disassemble_around(addr, before, after)
+    {
+        let marker = if instruction.addr == debugger.pc() { '>' } else { ' ' };
+        let bytes: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+
+        println!("{} {:04X}  {:<9} {}", marker, instruction.addr, bytes.join(" "), instruction);
+    }
+}
+
+fn hex_dump(debugger: &Debugger, addr: u16, length: u16)
+{
+    for row in (0..length).step_by(16)
+    {
+        let start = addr.wrapping_add(row);
+        let bytes: Vec<u8> = (0..16.min(length - row)).map(|i| debugger.cpu.memory.peek(start.wrapping_add(i))).collect();
+
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        let text: String = bytes.iter().map(|&byte| if byte.is_ascii_graphic() { byte as char } else { '.' }).collect();
+
+        println!("{:04X}  {:<47}  {}", start, hex.join(" "), text);
+    }
+}
+
+fn parse(text: &str) -> Result<u16, String>
+{
+    let (digits, radix) = match text.strip_prefix('$').or_else(|| text.strip_prefix("0x"))
+    {
+        Some(hex) => (hex, 16),
+        None => (text, 10),
+    };
+
+    u16::from_str_radix(digits, radix).map_err(|_| format!("Invalid number '{}'", text))
+}
+
+fn byte(value: u16) -> Result<u8, String>
+{
+    u8::try_from(value).map_err(|_| format!("{} does not fit in a byte", value))
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeSet;
+use std::process::exit;
+
+use rust_nes::cpu::disasm::{self, Instruction};
+use rust_nes::rom::{Rom, PRG_BANK_SIZE};
+
+const NMI_VECTOR: u16   = 0xFFFA;
+const RESET_VECTOR: u16 = 0xFFFC;
+const BRK_VECTOR: u16   = 0xFFFE;
+
+const SWITCHABLE_ORIGIN: u16 = 0x8000;
+const PRG_BANK_SIZE_8K: usize = 0x2000;
+
+fn main()
+{
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.len() != 2
+    {
+        eprintln!("Usage: {} <rom.nes>", args[0]);
+        exit(1);
+    }
+
+    let rom = match Rom::from_file(&args[1])
+    {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(1);
+        }
+    };
+
+    if rom.prg.is_empty()
+    {
+        eprintln!("No PRG ROM in '{}'", args[1]);
+        exit(1);
+    }
+
+    let layout = match Layout::from_mapper(rom.mapper, rom.prg.len())
+    {
+        Some(layout) => layout,
+        None => {
+            eprintln!("Unsupported mapper {}", rom.mapper);
+            exit(1);
+        }
+    };
+
+    let (switchable, fixed) = rom.prg.split_at(rom.prg.len() - layout.fixed_size);
+
+    // Without knowing which banks are selected, the switchable ones are decoded linearly
+    for (index, bank) in switchable.chunks(layout.bank_size).enumerate()
+    {
+        if layout.fixed_size == 0
+        {
+            // The whole window switches, every bank has its own vectors
+            let origin = (0x10000 - bank.len()) as u16;
+
+            println!("; Bank {} (${:04X}-$FFFF)", index, origin);
+            print_reachable(bank, origin);
+        }
+        else
+        {
+            println!("; Bank {} (${:04X}-${:04X}), switchable", index, SWITCHABLE_ORIGIN, SWITCHABLE_ORIGIN as usize + bank.len() - 1);
+
+            let code: BTreeSet<u16> = disasm::disassemble(bank, SWITCHABLE_ORIGIN).iter().map(|i| i.addr).collect();
+            print_listing(bank, SWITCHABLE_ORIGIN, &code, &[]);
+        }
+
+        println!();
+    }
+
+    if !fixed.is_empty()
+    {
+        let origin = (0x10000 - fixed.len()) as u16;
+
+        println!("; Bank {} (${:04X}-$FFFF), fixed", switchable.len() / layout.bank_size, origin);
+        print_reachable(fixed, origin);
+    }
+}
+
+// How the PRG ROM is mapped at power up, the end of the PRG holds the vectors at the top of the address space
+struct Layout
+{
+    bank_size:  usize,
+    fixed_size: usize, // 0 when the whole $8000-$FFFF window switches
+}
+
+impl Layout
+{
+    fn from_mapper(mapper: u8, prg_size: usize) -> Option<Layout>
+    {
+        let (bank_size, fixed_size) = match mapper
+        {
+            0 | 3            => (PRG_BANK_SIZE, 2 * PRG_BANK_SIZE), // No PRG banking
+            1 | 2 | 10       => (PRG_BANK_SIZE, PRG_BANK_SIZE),
+            4                => (PRG_BANK_SIZE_8K, 2 * PRG_BANK_SIZE_8K),
+            5                => (PRG_BANK_SIZE_8K, PRG_BANK_SIZE_8K),
+            9                => (PRG_BANK_SIZE_8K, 3 * PRG_BANK_SIZE_8K),
+            7 | 11 | 34 | 66 => (2 * PRG_BANK_SIZE, 0),
+            _ => return None,
+        };
+
+        Some(Layout { bank_size, fixed_size: fixed_size.min(prg_size) })
+    }
+}
+
+// Decodes what the vectors of a bank mapped at the top of the address space lead to
+fn print_reachable(bank: &[u8], origin: u16)
+{
+    let read_vector = |vector: u16| {
+        let offset = (vector - origin) as usize;
+        u16::from_le_bytes([bank[offset], bank[offset + 1]])
+    };
+
+    let entry_points = [
+        ("reset", read_vector(RESET_VECTOR)),
+        ("nmi",   read_vector(NMI_VECTOR)),
+        ("brk",   read_vector(BRK_VECTOR)),
+    ];
+
+    let addrs: Vec<u16> = entry_points.iter().map(|(_, addr)| *addr).collect();
+    let code = disasm::reachable(bank, origin, &addrs);
+    print_listing(bank, origin, &code, &entry_points);
+}
+
+// Prints the instructions starting at the `code` addresses, and every other byte as data
+fn print_listing(bytes: &[u8], origin: u16, code: &BTreeSet<u16>, labels: &[(&str, u16)])
+{
+    let mut offset = 0;
+
+    while offset < bytes.len()
+    {
+        let addr = origin.wrapping_add(offset as u16);
+
+        for (label, _) in labels.iter().filter(|(_, label_addr)| *label_addr == addr)
+        {
+            println!("{}:", label);
+        }
+
+        let instruction = if code.contains(&addr) { decode(bytes, offset, addr) } else { None };
+
+        match instruction
+        {
+            Some(instruction) => {
+                let hex: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                let marker = if instruction.official { ' ' } else { '*' };
+
+                println!("{:04X}  {:<9}{}{}", addr, hex.join(" "), marker, instruction);
+
+                offset += instruction.bytes.len();
+            },
+            None => {
+                println!("{:04X}  {:02X}        .byte ${:02X}", addr, bytes[offset], bytes[offset]);
+
+                offset += 1;
+            }
+        }
+    }
+}
+
+// Only an instruction fully inside of the bank is decoded
+fn decode(bytes: &[u8], offset: usize, addr: u16) -> Option<Instruction>
+{
+    disasm::disassemble(&bytes[offset..bytes.len().min(offset + 3)], addr).into_iter().next()
+}
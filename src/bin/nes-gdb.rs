@@ -0,0 +1,67 @@
+use std::net::TcpListener;
+use std::process::exit;
+
+use rust_nes::cpu::Cpu;
+use rust_nes::debugger::Debugger;
+use rust_nes::gdb::GdbStub;
+use rust_nes::rom::Rom;
+
+const DEFAULT_PORT: u16 = 1234;
+
+fn main()
+{
+    let args: Vec<String> = std::env::args().collect();
+
+    let (path, port) = match &args[1..]
+    {
+        [path] => (path, DEFAULT_PORT),
+        [path, port] => match port.parse()
+        {
+            Ok(port) => (path, port),
+            Err(_) => {
+                eprintln!("Invalid port '{}'", port);
+                exit(1);
+            }
+        },
+        _ => {
+            eprintln!("Usage: {} <rom.nes> [port]", args[0]);
+            exit(1);
+        }
+    };
+
+    let rom = Rom::from_file(path).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+
+    let mut cpu = Cpu::new();
+    cpu.load_rom(rom).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+    cpu.reset();
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|err| {
+        eprintln!("Unable to listen on port {}, ({})", port, err);
+        exit(1);
+    });
+
+    println!("Waiting for GDB on 127.0.0.1:{}", port);
+
+    // The CPU keeps its state from one session to the next
+    let mut stub = GdbStub::new(Debugger::new(cpu));
+
+    for stream in listener.incoming()
+    {
+        let result = stream.and_then(|stream| {
+            println!("Client connected");
+            stub.serve(stream)
+        });
+
+        match result
+        {
+            Ok(()) => println!("Client disconnected"),
+            Err(err) => eprintln!("Connection error: {}", err),
+        }
+    }
+}
@@ -1,33 +1,271 @@
+use std::{cell::RefCell, rc::Rc};
+
 use rand::Rng;
-use rust_nes::cpu::Cpu;
+use rust_nes::cpu::{Cpu, asm, trace::RingBufferTracer};
 use sdl2::{pixels::{PixelFormatEnum, Color}, EventPump, event::Event, keyboard::Keycode};
 
 const SCALE: u32 = 10;
 
+// Snake from the easy6502 tutorial, assembled at $0600
+const GAME_CODE: &str = "
+    .org $0600
+
+    appleL         = $00 ; screen location of apple, low byte
+    appleH         = $01 ; screen location of apple, high byte
+    snakeDirection = $02 ; direction (see below)
+    snakeLength    = $03 ; snake length, in bytes
+    snakeHeadL     = $10 ; screen location of snake head, low byte
+    snakeHeadH     = $11 ; screen location of snake head, high byte
+    snakeBodyStart = $12 ; start of snake body byte pairs
+
+    movingUp      = 1
+    movingRight   = 2
+    movingDown    = 4
+    movingLeft    = 8
+
+    sysRandom     = $FE
+    sysLastKey    = $FF
+
+    ASCII_w       = $77
+    ASCII_a       = $61
+    ASCII_s       = $73
+    ASCII_d       = $64
+
+        jsr init
+        jsr loop
+
+    init:
+        jsr initSnake
+        jsr generateApplePosition
+        rts
+
+    initSnake:
+        lda #movingRight
+        sta snakeDirection
+
+        lda #4              ; start length (2 segments)
+        sta snakeLength
+
+        lda #$11
+        sta snakeHeadL
+
+        lda #$10
+        sta snakeBodyStart
+
+        lda #$0F
+        sta $14             ; body segment 1
+
+        lda #$04
+        sta snakeHeadH
+        sta $13             ; body segment 1
+        sta $15             ; body segment 2
+        rts
+
+    generateApplePosition:
+        lda sysRandom       ; new random byte for the low byte
+        sta appleL
+
+        lda sysRandom       ; new random number from 2 to 5 for the high byte
+        and #$03
+        clc
+        adc #2
+        sta appleH
+        rts
+
+    loop:
+        jsr readKeys
+        jsr checkCollision
+        jsr updateSnake
+        jsr drawApple
+        jsr drawSnake
+        jsr spinWheels
+        jmp loop
+
+    readKeys:
+        lda sysLastKey
+        cmp #ASCII_w
+        beq upKey
+        cmp #ASCII_d
+        beq rightKey
+        cmp #ASCII_s
+        beq downKey
+        cmp #ASCII_a
+        beq leftKey
+        rts
+    upKey:
+        lda #movingDown
+        bit snakeDirection
+        bne illegalMove
+
+        lda #movingUp
+        sta snakeDirection
+        rts
+    rightKey:
+        lda #movingLeft
+        bit snakeDirection
+        bne illegalMove
+
+        lda #movingRight
+        sta snakeDirection
+        rts
+    downKey:
+        lda #movingUp
+        bit snakeDirection
+        bne illegalMove
+
+        lda #movingDown
+        sta snakeDirection
+        rts
+    leftKey:
+        lda #movingRight
+        bit snakeDirection
+        bne illegalMove
+
+        lda #movingLeft
+        sta snakeDirection
+        rts
+    illegalMove:
+        rts
+
+    checkCollision:
+        jsr checkAppleCollision
+        jsr checkSnakeCollision
+        rts
+
+    checkAppleCollision:
+        lda appleL
+        cmp snakeHeadL
+        bne doneCheckingAppleCollision
+        lda appleH
+        cmp snakeHeadH
+        bne doneCheckingAppleCollision
+
+        inc snakeLength     ; eat apple
+        inc snakeLength     ; increase length
+        jsr generateApplePosition
+    doneCheckingAppleCollision:
+        rts
+
+    checkSnakeCollision:
+        ldx #2              ; start with second segment
+    snakeCollisionLoop:
+        lda snakeHeadL,x
+        cmp snakeHeadL
+        bne continueCollisionLoop
+
+    maybeCollided:
+        lda snakeHeadH,x
+        cmp snakeHeadH
+        beq didCollide
+
+    continueCollisionLoop:
+        inx
+        inx
+        cpx snakeLength     ; got to last section with no collision
+        beq didntCollide
+        jmp snakeCollisionLoop
+
+    didCollide:
+        jmp gameOver
+    didntCollide:
+        rts
+
+    updateSnake:
+        ldx snakeLength
+        dex
+        txa
+    updateloop:
+        lda snakeHeadL,x
+        sta snakeBodyStart,x
+        dex
+        bpl updateloop
+
+        lda snakeDirection
+        lsr a
+        bcs up
+        lsr a
+        bcs right
+        lsr a
+        bcs down
+        lsr a
+        bcs left
+    up:
+        lda snakeHeadL
+        sec
+        sbc #$20
+        sta snakeHeadL
+        bcc upup
+        rts
+    upup:
+        dec snakeHeadH
+        lda #$01
+        cmp snakeHeadH
+        beq collision
+        rts
+    right:
+        inc snakeHeadL
+        lda #$1F
+        bit snakeHeadL
+        beq collision
+        rts
+    down:
+        lda snakeHeadL
+        clc
+        adc #$20
+        sta snakeHeadL
+        bcs downdown
+        rts
+    downdown:
+        inc snakeHeadH
+        lda #$06
+        cmp snakeHeadH
+        beq collision
+        rts
+    left:
+        dec snakeHeadL
+        lda snakeHeadL
+        and #$1F
+        cmp #$1F
+        beq collision
+        rts
+    collision:
+        jmp gameOver
+
+    drawApple:
+        ldy #0
+        lda sysRandom
+        sta (appleL),y
+        rts
+
+    drawSnake:
+        ldx snakeLength
+        lda #0
+        sta (snakeHeadL,x)  ; erase end of tail
+
+        ldx #0
+        lda #1
+        sta (snakeHeadL,x)  ; paint head
+        rts
+
+    spinWheels:
+        ldx #0
+    spinloop:
+        nop
+        nop
+        dex
+        bne spinloop
+        rts
+
+    gameOver:               ; falls into the BRK following the program
+";
+
 fn game_code() -> Vec<u8>
 {
-    vec![
-        0x20, 0x06, 0x06, 0x20, 0x38, 0x06, 0x20, 0x0d, 0x06, 0x20, 0x2a, 0x06, 0x60, 0xa9, 0x02, 0x85,
-        0x02, 0xa9, 0x04, 0x85, 0x03, 0xa9, 0x11, 0x85, 0x10, 0xa9, 0x10, 0x85, 0x12, 0xa9, 0x0f, 0x85,
-        0x14, 0xa9, 0x04, 0x85, 0x11, 0x85, 0x13, 0x85, 0x15, 0x60, 0xa5, 0xfe, 0x85, 0x00, 0xa5, 0xfe,
-        0x29, 0x03, 0x18, 0x69, 0x02, 0x85, 0x01, 0x60, 0x20, 0x4d, 0x06, 0x20, 0x8d, 0x06, 0x20, 0xc3,
-        0x06, 0x20, 0x19, 0x07, 0x20, 0x20, 0x07, 0x20, 0x2d, 0x07, 0x4c, 0x38, 0x06, 0xa5, 0xff, 0xc9,
-        0x77, 0xf0, 0x0d, 0xc9, 0x64, 0xf0, 0x14, 0xc9, 0x73, 0xf0, 0x1b, 0xc9, 0x61, 0xf0, 0x22, 0x60,
-        0xa9, 0x04, 0x24, 0x02, 0xd0, 0x26, 0xa9, 0x01, 0x85, 0x02, 0x60, 0xa9, 0x08, 0x24, 0x02, 0xd0,
-        0x1b, 0xa9, 0x02, 0x85, 0x02, 0x60, 0xa9, 0x01, 0x24, 0x02, 0xd0, 0x10, 0xa9, 0x04, 0x85, 0x02,
-        0x60, 0xa9, 0x02, 0x24, 0x02, 0xd0, 0x05, 0xa9, 0x08, 0x85, 0x02, 0x60, 0x60, 0x20, 0x94, 0x06,
-        0x20, 0xa8, 0x06, 0x60, 0xa5, 0x00, 0xc5, 0x10, 0xd0, 0x0d, 0xa5, 0x01, 0xc5, 0x11, 0xd0, 0x07,
-        0xe6, 0x03, 0xe6, 0x03, 0x20, 0x2a, 0x06, 0x60, 0xa2, 0x02, 0xb5, 0x10, 0xc5, 0x10, 0xd0, 0x06,
-        0xb5, 0x11, 0xc5, 0x11, 0xf0, 0x09, 0xe8, 0xe8, 0xe4, 0x03, 0xf0, 0x06, 0x4c, 0xaa, 0x06, 0x4c,
-        0x35, 0x07, 0x60, 0xa6, 0x03, 0xca, 0x8a, 0xb5, 0x10, 0x95, 0x12, 0xca, 0x10, 0xf9, 0xa5, 0x02,
-        0x4a, 0xb0, 0x09, 0x4a, 0xb0, 0x19, 0x4a, 0xb0, 0x1f, 0x4a, 0xb0, 0x2f, 0xa5, 0x10, 0x38, 0xe9,
-        0x20, 0x85, 0x10, 0x90, 0x01, 0x60, 0xc6, 0x11, 0xa9, 0x01, 0xc5, 0x11, 0xf0, 0x28, 0x60, 0xe6,
-        0x10, 0xa9, 0x1f, 0x24, 0x10, 0xf0, 0x1f, 0x60, 0xa5, 0x10, 0x18, 0x69, 0x20, 0x85, 0x10, 0xb0,
-        0x01, 0x60, 0xe6, 0x11, 0xa9, 0x06, 0xc5, 0x11, 0xf0, 0x0c, 0x60, 0xc6, 0x10, 0xa5, 0x10, 0x29,
-        0x1f, 0xc9, 0x1f, 0xf0, 0x01, 0x60, 0x4c, 0x35, 0x07, 0xa0, 0x00, 0xa5, 0xfe, 0x91, 0x00, 0x60,
-        0xa6, 0x03, 0xa9, 0x00, 0x81, 0x10, 0xa2, 0x00, 0xa9, 0x01, 0x81, 0x10, 0x60, 0xa2, 0x00, 0xea,
-        0xea, 0xca, 0xd0, 0xfb, 0x60
-    ]
+    match asm::assemble(GAME_CODE)
+    {
+        Ok(assembly) => assembly.bytes,
+        Err(err) => panic!("Invalid game code, {}", err),
+    }
 }
 
 fn handle_user_input(cpu: &mut Cpu, event_pump: &mut EventPump)
@@ -94,7 +332,7 @@ fn main()
     let video_subsystem = sdl_context.video().unwrap();
 
     let window = video_subsystem
-        .window("Snake", 32 as u32 * SCALE, 32 as u32 * SCALE)
+        .window("Snake", 32 * SCALE, 32 * SCALE)
         .position_centered()
         .build()
         .unwrap();
@@ -110,22 +348,24 @@ fn main()
     let creator = canvas.texture_creator();
     let mut texture = creator.create_texture_target(PixelFormatEnum::RGB24, 32, 32).unwrap();
 
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::flat();
     let mut rng = rand::thread_rng();
 
-    let mut screen_state = [0 as u8; 32 * 3 * 32];
+    let mut screen_state = [0_u8; 32 * 3 * 32];
 
     canvas.set_scale(SCALE as f32, SCALE as f32).unwrap();
 
-    cpu.load_at(0x0600, game_code());
+    cpu.load_at(0x0600, game_code()).unwrap();
     cpu.reset();
+    let tracer = Rc::new(RefCell::new(RingBufferTracer::new(32)));
+    cpu.set_tracer(Box::new(tracer.clone()));
 
-    cpu.run(|cpu: &mut Cpu|{
+    let result = cpu.run(|cpu: &mut Cpu|{
         handle_user_input(cpu, &mut event_pump);
 
         cpu.memory.write(0xFE, rng.gen_range(1..16));
 
-        if screen_changed(&cpu, &mut screen_state)
+        if screen_changed(cpu, &mut screen_state)
         {
             texture.update(None, &screen_state, 32 * 3).unwrap();
             canvas.copy(&texture, None, None).unwrap();
@@ -134,4 +374,11 @@ fn main()
 
         ::std::thread::sleep(std::time::Duration::new(0, 70_000));
     });
+
+    if let Err(err) = result
+    {
+        eprintln!("{}, last instructions:", err);
+        let _ = tracer.borrow().dump(&mut std::io::stderr());
+        std::process::exit(1);
+    }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
+use std::{cell::RefCell, rc::Rc};
+
 use rand::Rng;
-use rust_nes::{cpu::Cpu, rom::Rom};
+use rust_nes::{cpu::{Cpu, trace::RingBufferTracer}, rom::Rom};
 use sdl2::{pixels::{PixelFormatEnum, Color}, EventPump, event::Event, keyboard::Keycode};
 
 const SCALE: u32 = 10;
@@ -68,7 +70,7 @@ fn main()
     let video_subsystem = sdl_context.video().unwrap();
 
     let window = video_subsystem
-        .window("Snake", 32 as u32 * SCALE, 32 as u32 * SCALE)
+        .window("Snake", 32 * SCALE, 32 * SCALE)
         .position_centered()
         .build()
         .unwrap();
@@ -87,21 +89,23 @@ fn main()
     let mut cpu = Cpu::new();
     let mut rng = rand::thread_rng();
 
-    let mut screen_state = [0 as u8; 32 * 3 * 32];
+    let mut screen_state = [0_u8; 32 * 3 * 32];
 
     canvas.set_scale(SCALE as f32, SCALE as f32).unwrap();
 
     let rom = Rom::from_file("./resources/snake.nes").unwrap();
 
-    cpu.load_rom(rom);
+    cpu.load_rom(rom).unwrap();
     cpu.reset();
+    let tracer = Rc::new(RefCell::new(RingBufferTracer::new(32)));
+    cpu.set_tracer(Box::new(tracer.clone()));
 
-    cpu.run(|cpu: &mut Cpu|{
+    let result = cpu.run(|cpu: &mut Cpu|{
         handle_user_input(cpu, &mut event_pump);
 
         cpu.memory.write(0xFE, rng.gen_range(1..16));
 
-        if screen_changed(&cpu, &mut screen_state)
+        if screen_changed(cpu, &mut screen_state)
         {
             texture.update(None, &screen_state, 32 * 3).unwrap();
             canvas.copy(&texture, None, None).unwrap();
@@ -110,4 +114,11 @@ fn main()
 
         ::std::thread::sleep(std::time::Duration::new(0, 70_000));
     });
+
+    if let Err(err) = result
+    {
+        eprintln!("{}, last instructions:", err);
+        let _ = tracer.borrow().dump(&mut std::io::stderr());
+        std::process::exit(1);
+    }
 }
\ No newline at end of file
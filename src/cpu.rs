@@ -1,40 +1,131 @@
 mod register;
+mod bus;
+mod device;
 mod memory;
 mod ops;
+mod error;
+pub mod trace;
+pub mod disasm;
+pub mod asm;
 
 use std::fmt::{Debug};
 use register::CpuRegisters;
-use memory::Memory;
-use ops::OpcodeMap;
+use ops::{OPCODES, Op, Nmi, Irq};
+use trace::{Tracer, NoopTracer};
 
 use crate::rom::Rom;
 
-use self::ops::{opcode_length, Opcode};
+use self::ops::opcode_length;
+
+pub use self::ops::AddressingMode;
+pub use self::error::{CpuError, ErrorKind};
+pub use self::register::{RegisterSnapshot, StatusRegister};
+pub use self::bus::Bus;
+pub use self::device::Device;
+pub use self::memory::{Memory, Access, WatchHit};
 
 const ROM_START: u16          = 0x8000;
 const STACK_START: u16        = 0x0100;
+#[cfg(test)]
 const STACK_END: u16          = 0x01FF;
 const STACK_POINTER_START: u8 = 0xFF;
+const STACK_POINTER_RESET: u8 = 0xFD; // Reset goes through the interrupt sequence without writing
 
 const NMI_VECTOR: u16   = 0xFFFA;
 const RESET_VECTOR: u16 = 0xFFFC;
 const BRK_VECTOR: u16   = 0xFFFE;
+const IRQ_VECTOR: u16   = BRK_VECTOR; // Shared with BRK
+
+const BRK_OPCODE: u8 = 0x00;
+
+// CLI, SEI and PLP change the I flag after the interrupt poll of the next instruction
+const DELAYED_MASK_OPCODES: [u8; 3] = [0x58, 0x78, 0x28];
+
+const INTERRUPT_CYCLES: u8 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt
+{
+    Brk,
+    Nmi,
+    Irq
+}
 
-pub struct Cpu
+/// Outcome of a single `Cpu::step`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepResult
+{
+    pub opcode:    u8,
+    pub mode:      AddressingMode,
+    pub cycles:    u8, // Including page crossing and taken branch penalties
+    pub interrupt: Option<Interrupt>,
+}
+
+pub struct Cpu<B: Bus = Memory>
 {
     registers:  CpuRegisters,
-    pub memory: Memory, // TODO: remove pub
-    opcodes:    OpcodeMap,
+    pub memory: B, // TODO: remove pub
+    cycles:     u64,
+
+    nmi_line:    bool,
+    nmi_pending: bool,
+    irq_line:    bool,
+
+    delayed_irq_mask: Option<bool>, // I flag as seen by the next interrupt poll
+
+    tracer: Box<dyn Tracer<B>>,
 }
 
 impl Cpu
 {
     pub fn new() -> Cpu
+    {
+        Self::with_bus(Memory::new())
+    }
+
+    /// Bare 6502 with 64K of RAM, see `Memory::flat`
+    pub fn flat() -> Cpu
+    {
+        Self::with_bus(Memory::flat())
+    }
+
+    pub fn load(&mut self, program: Vec<u8>) -> Result<(), ErrorKind>
+    {
+        self.load_at(ROM_START, program)
+    }
+
+    /// Fails when the mapper of the ROM is not supported
+    pub fn load_rom(&mut self, rom: Rom) -> Result<(), String>
+    {
+        self.memory.load_rom(rom)
+    }
+
+    /// Copies a program and points the reset vector to it, nothing is written unless both fit in writable memory
+    pub fn load_at(&mut self, start_addr: u16, program: Vec<u8>) -> Result<(), ErrorKind>
+    {
+        self.memory.check_writable(RESET_VECTOR, 2)?;
+        self.memory.write_slice(start_addr, &program[..])?;
+        self.memory.write_u16(RESET_VECTOR, start_addr)
+    }
+}
+
+impl<B: Bus> Cpu<B>
+{
+    /// CPU on a custom address space
+    pub fn with_bus(memory: B) -> Cpu<B>
     {
         Cpu {
             registers:  CpuRegisters::new(),
-            memory:     Memory::new(),
-            opcodes:    ops::opcodes(),
+            memory,
+            cycles:     0,
+
+            nmi_line:    false,
+            nmi_pending: false,
+            irq_line:    false,
+
+            delayed_irq_mask: None,
+
+            tracer: Box::new(NoopTracer),
         }
     }
 
@@ -43,90 +134,221 @@ impl Cpu
         self.registers.a.set(0);
         self.registers.x.set(0);
         self.registers.y.set(0);
-        self.registers.sp.set(STACK_POINTER_START);
+        self.registers.sp.set(STACK_POINTER_RESET);
         self.registers.p.reset();
+        self.registers.p.set_interrupt_disable(true);
+
+        self.nmi_pending = false;
+        self.delayed_irq_mask = None;
 
         self.registers.pc.set(
             self.memory.read_u16(RESET_VECTOR)
         );
+
+        // The reset sequence takes 7 cycles before the first instruction
+        self.cycles = 7;
     }
 
-    pub fn load(&mut self, program: Vec<u8>)
+    /// Total number of CPU cycles elapsed since reset
+    pub fn cycles(&self) -> u64
     {
-        self.load_at(ROM_START, program);
+        self.cycles
     }
 
-    pub fn load_rom(&mut self, rom: Rom)
+    pub fn registers(&self) -> RegisterSnapshot
     {
-        self.memory.load_rom(rom);
+        RegisterSnapshot::from(&self.registers)
     }
 
-    pub fn load_at(&mut self, start_addr: u16, program: Vec<u8>)
+    pub fn set_registers(&mut self, registers: RegisterSnapshot)
     {
-        self.memory.write_slice(start_addr, &program[..]);
-        self.memory.write_u16(0xFFFC, start_addr);
+        self.registers.a.set(registers.a);
+        self.registers.x.set(registers.x);
+        self.registers.y.set(registers.y);
+        self.registers.sp.set(registers.sp);
+        self.registers.pc.set(registers.pc);
+        self.registers.p = StatusRegister::from(registers.p);
+        self.delayed_irq_mask = None;
     }
 
-    pub fn run<F>(&mut self, mut callback: F)
-        where F: FnMut(&mut Cpu)
+    /// Replaces the tracer called before every instruction, `NoopTracer` by default
+    pub fn set_tracer(&mut self, tracer: Box<dyn Tracer<B>>)
     {
-        loop
+        self.tracer = tracer;
+    }
+
+    /// Sets the NMI line, the interrupt is latched when it goes from released to asserted
+    pub fn set_nmi(&mut self, asserted: bool)
+    {
+        if asserted && !self.nmi_line
         {
-            println!("{:?}", &self);
+            self.nmi_pending = true;
+        }
 
-            callback(self);
+        self.nmi_line = asserted;
+    }
+
+    /// Sets the IRQ line, the interrupt is serviced as long as it stays asserted and is not masked
+    pub fn set_irq(&mut self, asserted: bool)
+    {
+        self.irq_line = asserted;
+    }
+
+    /// Executes a single instruction at PC (or services a pending interrupt) and reports what happened
+    pub fn step(&mut self) -> Result<StepResult, CpuError>
+    {
+        let result = self.execute();
+
+        if let Err(err) = &result
+        {
+            self.tracer.error(err);
+        }
+
+        result
+    }
+
+    fn execute(&mut self) -> Result<StepResult, CpuError>
+    {
+        if let Some(step) = self.poll_interrupts()?
+        {
+            return Ok(step);
+        }
 
-            let opcode = self.memory.read(*self.registers.pc);
-            self.registers.pc += 1;
+        // The tracer needs the whole CPU, so it is moved out while it runs
+        let mut tracer = std::mem::replace(&mut self.tracer, Box::new(NoopTracer));
+        tracer.trace(self);
+        self.tracer = tracer;
 
-            let pc_state = *self.registers.pc;
+        self.memory.set_cycle(self.cycles);
 
-            let op_metadata = self.opcodes.get(&opcode);
+        let pc = *self.registers.pc;
+        let opcode = self.memory.read(pc);
+        self.registers.pc.set(pc.wrapping_add(1));
 
-            match op_metadata
-            {
-                Some(metadata) =>
-                {
-                    self.print_opcode_debug(&metadata);
+        let pc_state = *self.registers.pc;
 
-                    let op = &metadata.op;
-                    // let args = &self.memory.read_slice(*self.registers.pc, op.args_len());
-                    // self.registers.pc += op.args_len() as u16;
+        let entry = match &OPCODES[opcode as usize]
+        {
+            Some(entry) => entry,
+            None => return Err(self.error(ErrorKind::UnsupportedOpcode, pc, opcode)),
+        };
 
-                    op.call(metadata.mode, &mut self.registers, &mut self.memory);
+        let cycles = entry.cycles + (entry.extra_cycles)(entry.mode, &self.registers, &self.memory);
+        let interrupt_disabled = self.registers.p.interrupt_disabled();
 
-                    // If the PC has not moved, we progress over the operand
-                    if pc_state == *self.registers.pc
-                    {
-                        self.registers.pc += (opcode_length(metadata.mode) - 1) as u16; // Remove the opcode byte as we already moved over it
-                    }
+        // Instructions without operand still read the byte after the opcode
+        if let AddressingMode::Implicit | AddressingMode::Accumulator = entry.mode
+        {
+            self.memory.read(pc_state);
+        }
+
+        if let Err(kind) = (entry.call)(entry.mode, &mut self.registers, &mut self.memory)
+        {
+            return Err(self.error(kind, pc, opcode));
+        }
 
-                },
-                None => panic!("Unsupported opcode 0x{:02X}", opcode),
-            }
+        if !(entry.sets_pc)()
+        {
+            self.registers.pc.set(pc_state.wrapping_add((opcode_length(entry.mode) - 1) as u16)); // Remove the opcode byte as we already moved over it
+        }
 
-            // Exit on BRK
-            // TODO: align with correct NES impl
-            if self.registers.p.has_broken() { break }
+        if DELAYED_MASK_OPCODES.contains(&opcode)
+        {
+            self.delayed_irq_mask = Some(interrupt_disabled);
         }
+
+        self.cycles += cycles as u64;
+
+        Ok(StepResult
+        {
+            opcode,
+            mode:      entry.mode,
+            cycles,
+            interrupt: if opcode == BRK_OPCODE { Some(Interrupt::Brk) } else { None },
+        })
     }
 
-    fn print_opcode_debug(&self, metadata: &Opcode)
+    // Interrupts are checked between instructions, NMI has priority over IRQ
+    fn poll_interrupts(&mut self) -> Result<Option<StepResult>, CpuError>
     {
-        println!("* {0:#04X} ({1:?}) - AddressingMode::{2:?}", metadata.opcode, metadata.op, metadata.mode);
-        print!("> ");
+        let pc = *self.registers.pc;
+        let irq_masked = self.delayed_irq_mask.take().unwrap_or(self.registers.p.interrupt_disabled());
+
+        let (interrupt, result) = if self.nmi_pending
+        {
+            self.nmi_pending = false;
+            (Interrupt::Nmi, Nmi.call(AddressingMode::Implicit, &mut self.registers, &mut self.memory))
+        }
+        else if (self.irq_line || self.memory.irq()) && !irq_masked
+        {
+            (Interrupt::Irq, Irq.call(AddressingMode::Implicit, &mut self.registers, &mut self.memory))
+        }
+        else
+        {
+            return Ok(None);
+        };
+
+        // The CPU runs a BRK in place of the next instruction
+        if let Err(kind) = result
+        {
+            return Err(self.error(kind, pc, BRK_OPCODE));
+        }
+
+        self.cycles += INTERRUPT_CYCLES as u64;
+
+        Ok(Some(StepResult
+        {
+            opcode:    BRK_OPCODE,
+            mode:      AddressingMode::Implicit,
+            cycles:    INTERRUPT_CYCLES,
+            interrupt: Some(interrupt),
+        }))
+    }
 
-        for operand_addr in 0..opcode_length(metadata.mode)-1
+    fn error(&self, kind: ErrorKind, pc: u16, opcode: u8) -> CpuError
+    {
+        CpuError
         {
-            print!("{:#04X} ", self.memory.read(*self.registers.pc + operand_addr as u16));
+            kind,
+            pc,
+            opcode,
+            registers: RegisterSnapshot::from(&self.registers),
         }
+    }
+
+    /// Runs until a BRK is executed
+    pub fn run<F>(&mut self, callback: F) -> Result<StepResult, CpuError>
+        where F: FnMut(&mut Cpu<B>)
+    {
+        self.run_until(callback, |_, step| step.interrupt == Some(Interrupt::Brk))
+    }
+
+    /// Runs until `stop` returns true for the last executed instruction, which is then returned
+    pub fn run_until<F, S>(&mut self, mut callback: F, mut stop: S) -> Result<StepResult, CpuError>
+        where F: FnMut(&mut Cpu<B>),
+              S: FnMut(&Cpu<B>, &StepResult) -> bool
+    {
+        loop
+        {
+            callback(self);
+
+            let step = self.step()?;
 
-        println!("\n");
+            if stop(self, &step) { return Ok(step) }
+        }
     }
 
 }
 
-impl Debug for Cpu
+impl Default for Cpu
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl<B: Bus> Debug for Cpu<B>
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
     {
@@ -146,6 +368,331 @@ impl Debug for Cpu
             write!(f, "{:#04X} ", self.memory.read(STACK_START + (sp_addr + 1) as u16))?;
         }
 
-        writeln!(f, "")
+        writeln!(f)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn cpu_with_program(program: &[u8]) -> Cpu
+    {
+        let mut cpu = Cpu::new();
+
+        cpu.memory.write_slice(0x0600, program).unwrap();
+        cpu.registers.pc.set(0x0600);
+
+        cpu
+    }
+
+    #[test]
+    fn load_flat()
+    {
+        let mut cpu = Cpu::flat();
+
+        // LDA #$42 ; STA $C000
+        cpu.load(vec![0xA9, 0x42, 0x8D, 0x00, 0xC0]).unwrap();
+        cpu.reset();
+
+        assert_eq!(0x8000, *cpu.registers.pc);
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(0x42, cpu.memory.read(0xC000));
+    }
+
+    #[test]
+    fn load_at_checks_before_writing()
+    {
+        let mut cpu = Cpu::new();
+
+        // The reset vector is in cartridge ROM
+        assert_eq!(Err(ErrorKind::RomWrite(0xFFFC)), cpu.load_at(0x0600, vec![0xEA]));
+        assert_eq!(0x00, cpu.memory.read(0x0600));
+
+        let mut cpu = Cpu::flat();
+
+        assert_eq!(Err(ErrorKind::OutOfBounds(0xFFFF)), cpu.load_at(0xFFFF, vec![0xEA, 0xEA]));
+        assert_eq!(0x0000, cpu.memory.read_u16(RESET_VECTOR));
+    }
+
+    #[test]
+    fn step_single_instruction()
+    {
+        // LDA #$42 ; INX
+        let mut cpu = cpu_with_program(&[0xA9, 0x42, 0xE8]);
+
+        let step = cpu.step().unwrap();
+
+        assert_eq!(0xA9, step.opcode);
+        assert_eq!(AddressingMode::Immediate, step.mode);
+        assert_eq!(2, step.cycles);
+        assert_eq!(None, step.interrupt);
+
+        assert_eq!(0x42, *cpu.registers.a);
+        assert_eq!(0x00, *cpu.registers.x);
+        assert_eq!(0x0602, *cpu.registers.pc);
+    }
+
+    // NMI handler at 0x0680, IRQ/BRK handler at 0x0700
+    fn load_vectors(cpu: &mut Cpu)
+    {
+        let mut prg = vec![0; 0x8000];
+        prg[0x7FFA] = 0x80;
+        prg[0x7FFB] = 0x06;
+        prg[0x7FFE] = 0x00;
+        prg[0x7FFF] = 0x07;
+
+        cpu.load_rom(Rom { prg, ..Rom::empty() }).unwrap();
+    }
+
+    #[test]
+    fn step_reports_brk()
+    {
+        let mut cpu = cpu_with_program(&[0x00]);
+        load_vectors(&mut cpu);
+
+        let step = cpu.step().unwrap();
+
+        assert_eq!(Some(Interrupt::Brk), step.interrupt);
+        assert_eq!(7, step.cycles);
+        assert_eq!(0x0700, *cpu.registers.pc);
+    }
+
+    #[test]
+    fn step_branch_onto_operand()
+    {
+        // BEQ -1 ; BNE -1
+        let mut cpu = cpu_with_program(&[0xF0, 0xFF, 0xD0, 0xFF]);
+
+        cpu.registers.p.set_zero(false);
+        cpu.step().unwrap();
+        assert_eq!(0x0602, *cpu.registers.pc);
+
+        // Lands on its own operand, the CPU must not move over it
+        cpu.step().unwrap();
+        assert_eq!(0x0603, *cpu.registers.pc);
+    }
+
+    #[test]
+    fn run_until_condition()
+    {
+        // loop: INX ; JMP loop
+        let mut cpu = cpu_with_program(&[0xE8, 0x4C, 0x00, 0x06]);
+
+        let mut callbacks = 0;
+        let step = cpu.run_until(|_| callbacks += 1, |cpu, _| *cpu.registers.x == 3).unwrap();
+
+        assert_eq!(0xE8, step.opcode);
+        assert_eq!(3, *cpu.registers.x);
+        assert_eq!(5, callbacks);
+    }
+
+    #[test]
+    fn step_page_cross_penalty()
+    {
+        // LDA $02F0,X ; LDA $02F0,X
+        let mut cpu = cpu_with_program(&[0xBD, 0xF0, 0x02, 0xBD, 0xF0, 0x02]);
+
+        cpu.registers.x.set(0x0F);
+        assert_eq!(4, cpu.step().unwrap().cycles);
+
+        cpu.registers.x.set(0x10);
+        assert_eq!(5, cpu.step().unwrap().cycles);
+
+        assert_eq!(9, cpu.cycles());
+    }
+
+    #[test]
+    fn step_store_has_no_page_cross_penalty()
+    {
+        // STA $02F0,X
+        let mut cpu = cpu_with_program(&[0x9D, 0xF0, 0x02]);
+
+        cpu.registers.x.set(0x10);
+
+        assert_eq!(5, cpu.step().unwrap().cycles);
+    }
+
+    #[test]
+    fn step_branch_penalty()
+    {
+        // BNE +2 (not taken) ; BEQ -4 (taken, back on page 0x05)
+        let mut cpu = cpu_with_program(&[0xD0, 0x02, 0xF0, 0xFA]);
+
+        cpu.registers.p.set_zero(true);
+
+        assert_eq!(2, cpu.step().unwrap().cycles);
+        assert_eq!(4, cpu.step().unwrap().cycles);
+        assert_eq!(0x05FE, *cpu.registers.pc);
+    }
+
+    #[test]
+    fn step_rom_write_error()
+    {
+        // LDA #$42 ; STA $8000
+        let mut cpu = cpu_with_program(&[0xA9, 0x42, 0x8D, 0x00, 0x80]);
+
+        cpu.step().unwrap();
+        let error = cpu.step().unwrap_err();
+
+        assert_eq!(ErrorKind::RomWrite(0x8000), error.kind);
+        assert_eq!(0x0602, error.pc);
+        assert_eq!(0x8D, error.opcode);
+        assert_eq!(0x42, error.registers.a);
+    }
+
+    #[test]
+    fn run_stops_on_error()
+    {
+        // PHA (stack is fine) ; STX $FFFF
+        let mut cpu = cpu_with_program(&[0x48, 0x8E, 0xFF, 0xFF]);
+
+        let error = cpu.run(|_| {}).unwrap_err();
+
+        assert_eq!(ErrorKind::RomWrite(0xFFFF), error.kind);
+        assert_eq!(0x0601, error.pc);
+    }
+
+    #[test]
+    fn nmi_is_edge_triggered()
+    {
+        // NOP ; NOP
+        let mut cpu = cpu_with_program(&[0xEA, 0xEA]);
+        load_vectors(&mut cpu);
+
+        cpu.set_nmi(true);
+
+        let step = cpu.step().unwrap();
+
+        assert_eq!(Some(Interrupt::Nmi), step.interrupt);
+        assert_eq!(7, step.cycles);
+        assert_eq!(0x0680, *cpu.registers.pc);
+        assert_eq!(0b0010_0000, cpu.memory.read(STACK_END - 2));
+
+        // Still asserted, but no new edge
+        cpu.memory.write(0x0680, 0xEA);
+        assert_eq!(None, cpu.step().unwrap().interrupt);
+
+        cpu.set_nmi(false);
+        cpu.set_nmi(true);
+
+        assert_eq!(Some(Interrupt::Nmi), cpu.step().unwrap().interrupt);
+    }
+
+    #[test]
+    fn nmi_ignores_interrupt_disable()
+    {
+        let mut cpu = cpu_with_program(&[0xEA]);
+        load_vectors(&mut cpu);
+
+        cpu.registers.p.set_interrupt_disable(true);
+        cpu.set_nmi(true);
+
+        assert_eq!(Some(Interrupt::Nmi), cpu.step().unwrap().interrupt);
+    }
+
+    #[test]
+    fn irq_is_level_triggered_and_masked()
+    {
+        // CLI ; NOP ; NOP
+        let mut cpu = cpu_with_program(&[0x58, 0xEA, 0xEA]);
+        load_vectors(&mut cpu);
+
+        cpu.registers.p.set_interrupt_disable(true);
+        cpu.set_irq(true);
+
+        let step = cpu.step().unwrap();
+        assert_eq!(None, step.interrupt);
+        assert_eq!(0x58, step.opcode);
+
+        // The cleared I flag is only seen after the next instruction
+        let step = cpu.step().unwrap();
+        assert_eq!(None, step.interrupt);
+        assert_eq!(0xEA, step.opcode);
+
+        let step = cpu.step().unwrap();
+        assert_eq!(Some(Interrupt::Irq), step.interrupt);
+        assert_eq!(0x0700, *cpu.registers.pc);
+        assert_eq!(0x06, cpu.memory.read(STACK_END));
+        assert_eq!(0x02, cpu.memory.read(STACK_END - 1));
+        assert_eq!(0b0010_0000, cpu.memory.read(STACK_END - 2));
+
+        // Masked by the handler until RTI
+        assert!(cpu.registers.p.interrupt_disabled());
+    }
+
+    #[test]
+    fn irq_after_cli_sei()
+    {
+        // CLI ; SEI ; NOP
+        let mut cpu = cpu_with_program(&[0x58, 0x78, 0xEA]);
+        load_vectors(&mut cpu);
+
+        cpu.registers.p.set_interrupt_disable(true);
+        cpu.set_irq(true);
+
+        assert_eq!(0x58, cpu.step().unwrap().opcode);
+        assert_eq!(0x78, cpu.step().unwrap().opcode);
+
+        // Taken after SEI, which already set I in the pushed status
+        let step = cpu.step().unwrap();
+        assert_eq!(Some(Interrupt::Irq), step.interrupt);
+        assert_eq!(0x02, cpu.memory.read(STACK_END - 1));
+        assert_eq!(0b0010_0100, cpu.memory.read(STACK_END - 2));
+
+        cpu.memory.write(0x0700, 0xEA);
+        assert_eq!(None, cpu.step().unwrap().interrupt);
+    }
+
+    #[test]
+    fn irq_after_plp_setting_interrupt_disable()
+    {
+        // PLP ; NOP
+        let mut cpu = cpu_with_program(&[0x28, 0xEA]);
+        load_vectors(&mut cpu);
+
+        cpu.memory.write(STACK_END, 0b0000_0100);
+        cpu.registers.sp.set(STACK_POINTER_START - 1);
+        cpu.registers.p.set_interrupt_disable(false);
+
+        assert_eq!(0x28, cpu.step().unwrap().opcode);
+        cpu.set_irq(true);
+
+        let step = cpu.step().unwrap();
+        assert_eq!(Some(Interrupt::Irq), step.interrupt);
+        assert_eq!(0b0010_0100, cpu.memory.read(STACK_END - 2));
+    }
+
+    #[test]
+    fn irq_after_plp_clearing_interrupt_disable()
+    {
+        // PLP ; NOP ; NOP
+        let mut cpu = cpu_with_program(&[0x28, 0xEA, 0xEA]);
+        load_vectors(&mut cpu);
+
+        cpu.memory.write(STACK_END, 0b0000_0000);
+        cpu.registers.sp.set(STACK_POINTER_START - 1);
+        cpu.registers.p.set_interrupt_disable(true);
+        cpu.set_irq(true);
+
+        assert_eq!(0x28, cpu.step().unwrap().opcode);
+        assert_eq!(0xEA, cpu.step().unwrap().opcode);
+        assert_eq!(Some(Interrupt::Irq), cpu.step().unwrap().interrupt);
+        assert_eq!(0x02, cpu.memory.read(STACK_END - 1));
+    }
+
+    #[test]
+    fn nmi_has_priority_over_irq()
+    {
+        let mut cpu = cpu_with_program(&[0xEA]);
+        load_vectors(&mut cpu);
+
+        cpu.set_irq(true);
+        cpu.set_nmi(true);
+
+        assert_eq!(Some(Interrupt::Nmi), cpu.step().unwrap().interrupt);
+    }
+}
@@ -0,0 +1,872 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use super::ops::{OPCODES, AddressingMode, opcode_length};
+
+/// Machine code produced by `assemble`, to be loaded at `origin`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly
+{
+    pub origin: u16,
+    pub bytes:  Vec<u8>,
+    pub labels: HashMap<String, u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmErrorKind
+{
+    Syntax(String),
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    InvalidOperand(String),
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    OutOfRange(i64),
+    BranchOutOfRange(i64),
+    OrgBackwards(u16),
+}
+
+/// Error raised by `assemble`, `line` starts at 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError
+{
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl Display for AsmErrorKind
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            AsmErrorKind::Syntax(text)              => write!(f, "Syntax error near '{}'", text),
+            AsmErrorKind::UnknownMnemonic(name)     => write!(f, "Unknown mnemonic '{}'", name),
+            AsmErrorKind::UnknownDirective(name)    => write!(f, "Unknown directive '{}'", name),
+            AsmErrorKind::InvalidOperand(mnemonic)  => write!(f, "Addressing mode not supported by {}", mnemonic),
+            AsmErrorKind::UndefinedLabel(name)      => write!(f, "Undefined label '{}'", name),
+            AsmErrorKind::DuplicateLabel(name)      => write!(f, "Label '{}' is already defined", name),
+            AsmErrorKind::OutOfRange(value)         => write!(f, "Value {} does not fit", value),
+            AsmErrorKind::BranchOutOfRange(offset)  => write!(f, "Branch offset {} is out of range", offset),
+            AsmErrorKind::OrgBackwards(addr)        => write!(f, ".org {:#06X} is before the current address", addr),
+        }
+    }
+}
+
+impl Display for AsmError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+#[derive(Debug, Clone)]
+enum Expr
+{
+    Number(i64),
+    Symbol(String),
+    Current, // `*`, address of the statement
+    Unary(char, Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Index
+{
+    None,
+    X,
+    Y,
+}
+
+#[derive(Debug, Clone)]
+enum Operand
+{
+    None,
+    Accumulator,
+    Immediate(Expr),
+    Direct(Expr, Index), // Zero page, absolute or relative
+    Indirect(Expr),
+    IndirectX(Expr),
+    IndirectY(Expr),
+}
+
+#[derive(Debug, Clone)]
+enum Data
+{
+    Value(Expr),
+    Text(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+enum Statement
+{
+    Label(String),
+    Constant(String, Expr),
+    Org(Expr),
+    Bytes(Vec<Data>),
+    Words(Vec<Expr>),
+    Instruction(String, Operand),
+}
+
+type Symbols = HashMap<String, i64>;
+
+/// Assembles 6502 source, starting at address 0 unless `.org` says otherwise.
+///
+/// Supports `label:`, `name = expr`, `.org`, `.byte` (values and "strings"), `.word`, comments after `;`
+/// and the usual operand syntax (`#imm`, `zp`, `abs,X`, `(ind)`, `(zp,X)`, `(zp),Y`...).
+/// Expressions accept `$hex`, `%bin`, decimal and 'c' literals, labels, `*`,
+/// `+ - * / & | ^`, parentheses and the `<` / `>` low and high byte operators.
+/// Zero page modes are picked when the operand is known to fit when the line is first seen.
+pub fn assemble(source: &str) -> Result<Assembly, AsmError>
+{
+    let mut statements = vec![];
+
+    for (index, line) in source.lines().enumerate()
+    {
+        parse_line(line)
+            .map_err(|kind| AsmError { line: index + 1, kind })?
+            .into_iter()
+            .for_each(|statement| statements.push((index + 1, statement)));
+    }
+
+    // First pass: addresses of labels and addressing modes, so sizes are known
+    let mut symbols = Symbols::new();
+    let mut modes = vec![];
+    let mut gaps = vec![];
+    let mut origin = None;
+    let mut pc: u32 = 0;
+
+    for (line, statement) in &statements
+    {
+        let error = |kind| AsmError { line: *line, kind };
+
+        match statement
+        {
+            Statement::Label(name) => {
+                if symbols.insert(name.clone(), pc as i64).is_some()
+                {
+                    return Err(error(AsmErrorKind::DuplicateLabel(name.clone())));
+                }
+            },
+            Statement::Constant(name, expr) => {
+                let value = expr.eval(&symbols, pc as u16).map_err(error)?;
+
+                if symbols.insert(name.clone(), value).is_some()
+                {
+                    return Err(error(AsmErrorKind::DuplicateLabel(name.clone())));
+                }
+            },
+            Statement::Org(expr) => {
+                let addr = to_u16(expr.eval(&symbols, pc as u16).map_err(error)?).map_err(error)?;
+
+                // Moving the start is fine until something is emitted, after that the gap is filled
+                if origin.is_some() && (addr as u32) < pc
+                {
+                    return Err(error(AsmErrorKind::OrgBackwards(addr)));
+                }
+
+                gaps.push(origin.map(|_| addr));
+                pc = addr as u32;
+            },
+            Statement::Bytes(data) => {
+                origin.get_or_insert(pc as u16);
+                pc += data.iter().map(|item| match item { Data::Value(_) => 1, Data::Text(text) => text.len() as u32 }).sum::<u32>();
+            },
+            Statement::Words(exprs) => {
+                origin.get_or_insert(pc as u16);
+                pc += 2 * exprs.len() as u32;
+            },
+            Statement::Instruction(mnemonic, operand) => {
+                origin.get_or_insert(pc as u16);
+
+                let mode = select_mode(mnemonic, operand, &symbols, pc as u16).map_err(error)?;
+                modes.push(mode);
+
+                pc += opcode_length(mode) as u32;
+            },
+        }
+
+        if pc > 0x10000
+        {
+            return Err(error(AsmErrorKind::OutOfRange(pc as i64)));
+        }
+    }
+
+    // Second pass: every label is known, emit the bytes
+    let origin = origin.unwrap_or(0);
+    let mut bytes: Vec<u8> = vec![];
+    let mut modes = modes.into_iter();
+    let mut gaps = gaps.into_iter();
+
+    for (line, statement) in &statements
+    {
+        let error = |kind| AsmError { line: *line, kind };
+        let pc = origin.wrapping_add(bytes.len() as u16);
+
+        match statement
+        {
+            Statement::Label(_) | Statement::Constant(_, _) => {},
+            Statement::Org(_) => {
+                if let Some(addr) = gaps.next().unwrap()
+                {
+                    bytes.resize((addr - origin) as usize, 0);
+                }
+            },
+            Statement::Bytes(data) => {
+                for item in data
+                {
+                    match item
+                    {
+                        Data::Value(expr) => bytes.push(to_u8(expr.eval(&symbols, pc).map_err(error)?).map_err(error)?),
+                        Data::Text(text) => bytes.extend_from_slice(text),
+                    }
+                }
+            },
+            Statement::Words(exprs) => {
+                for expr in exprs
+                {
+                    let value = to_u16(expr.eval(&symbols, pc).map_err(error)?).map_err(error)?;
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            },
+            Statement::Instruction(mnemonic, operand) => {
+                let mode = modes.next().unwrap();
+
+                bytes.push(find_opcode(mnemonic, mode).unwrap());
+                bytes.extend(encode_operand(mode, operand, &symbols, pc).map_err(error)?);
+            },
+        }
+    }
+
+    let labels = symbols.into_iter()
+        .filter(|(name, _)| statements.iter().any(|(_, statement)| matches!(statement, Statement::Label(label) if label == name)))
+        .map(|(name, value)| (name, value as u16))
+        .collect();
+
+    Ok(Assembly { origin, bytes, labels })
+}
+
+// Official opcodes are preferred when several share a mnemonic and mode (e.g. SBC #imm)
+fn find_opcode(mnemonic: &str, mode: AddressingMode) -> Option<u8>
+{
+    [true, false].iter().find_map(|&official| {
+        OPCODES.iter().position(|entry| match entry
+        {
+            Some(entry) => entry.official == official && entry.mode == mode && entry.name.eq_ignore_ascii_case(mnemonic),
+            None => false,
+        })
+    }).map(|opcode| opcode as u8)
+}
+
+fn select_mode(mnemonic: &str, operand: &Operand, symbols: &Symbols, pc: u16) -> Result<AddressingMode, AsmErrorKind>
+{
+    let supports = |mode| find_opcode(mnemonic, mode).is_some();
+
+    if !OPCODES.iter().flatten().any(|entry| entry.name.eq_ignore_ascii_case(mnemonic))
+    {
+        return Err(AsmErrorKind::UnknownMnemonic(mnemonic.to_string()));
+    }
+
+    // Zero page only when the value is already known to fit, otherwise the size could change in the second pass
+    let zero_page = |expr: &Expr| matches!(expr.eval(symbols, pc), Ok(value) if (0..=0xFF).contains(&value));
+
+    let candidates: Vec<AddressingMode> = match operand
+    {
+        Operand::None => vec![AddressingMode::Implicit, AddressingMode::Accumulator],
+        Operand::Accumulator => vec![AddressingMode::Accumulator],
+        Operand::Immediate(_) => vec![AddressingMode::Immediate],
+        Operand::Indirect(_) => vec![AddressingMode::Indirect],
+        Operand::IndirectX(_) => vec![AddressingMode::IndirectX],
+        Operand::IndirectY(_) => vec![AddressingMode::IndirectY],
+        Operand::Direct(expr, index) => {
+            let (zero_page_mode, absolute_mode) = match index
+            {
+                Index::None => (AddressingMode::ZeroPage, AddressingMode::Absolute),
+                Index::X    => (AddressingMode::ZeroPageX, AddressingMode::AbsoluteX),
+                Index::Y    => (AddressingMode::ZeroPageY, AddressingMode::AbsoluteY),
+            };
+
+            if *index == Index::None && supports(AddressingMode::Relative)
+            {
+                vec![AddressingMode::Relative]
+            }
+            else if zero_page(expr)
+            {
+                vec![zero_page_mode, absolute_mode]
+            }
+            else
+            {
+                vec![absolute_mode, zero_page_mode]
+            }
+        },
+    };
+
+    candidates.into_iter()
+        .find(|&mode| supports(mode))
+        .ok_or_else(|| AsmErrorKind::InvalidOperand(mnemonic.to_uppercase()))
+}
+
+fn encode_operand(mode: AddressingMode, operand: &Operand, symbols: &Symbols, pc: u16) -> Result<Vec<u8>, AsmErrorKind>
+{
+    let expr = match operand
+    {
+        Operand::None | Operand::Accumulator => return Ok(vec![]),
+        Operand::Immediate(expr) | Operand::Direct(expr, _) | Operand::Indirect(expr)
+            | Operand::IndirectX(expr) | Operand::IndirectY(expr) => expr,
+    };
+
+    let value = expr.eval(symbols, pc)?;
+
+    match mode
+    {
+        AddressingMode::Relative => {
+            let offset = value - (pc as i64 + 2);
+
+            if !(-128..=127).contains(&offset)
+            {
+                return Err(AsmErrorKind::BranchOutOfRange(offset));
+            }
+
+            Ok(vec![offset as u8])
+        },
+        AddressingMode::Immediate => Ok(vec![to_u8(value)?]),
+        _ if opcode_length(mode) == 2 => {
+            // Addresses cannot be negative
+            if !(0..=0xFF).contains(&value)
+            {
+                return Err(AsmErrorKind::OutOfRange(value));
+            }
+
+            Ok(vec![value as u8])
+        },
+        _ => Ok(to_u16(value)?.to_le_bytes().to_vec()),
+    }
+}
+
+// Negative values are stored as two's complement
+fn to_u8(value: i64) -> Result<u8, AsmErrorKind>
+{
+    match value
+    {
+        -0x80..=0xFF => Ok(value as u8),
+        _ => Err(AsmErrorKind::OutOfRange(value)),
+    }
+}
+
+fn to_u16(value: i64) -> Result<u16, AsmErrorKind>
+{
+    match value
+    {
+        -0x8000..=0xFFFF => Ok(value as u16),
+        _ => Err(AsmErrorKind::OutOfRange(value)),
+    }
+}
+
+impl Expr
+{
+    fn eval(&self, symbols: &Symbols, pc: u16) -> Result<i64, AsmErrorKind>
+    {
+        match self
+        {
+            Expr::Number(value) => Ok(*value),
+            Expr::Symbol(name) => symbols.get(name).copied().ok_or_else(|| AsmErrorKind::UndefinedLabel(name.clone())),
+            Expr::Current => Ok(pc as i64),
+            Expr::Unary(op, expr) => {
+                let value = expr.eval(symbols, pc)?;
+
+                Ok(match op
+                {
+                    '-' => -value,
+                    '<' => value & 0xFF,
+                    '>' => (value >> 8) & 0xFF,
+                    _   => !value,
+                })
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(symbols, pc)?;
+                let rhs = rhs.eval(symbols, pc)?;
+
+                Ok(match op
+                {
+                    '+' => lhs.wrapping_add(rhs),
+                    '-' => lhs.wrapping_sub(rhs),
+                    '*' => lhs.wrapping_mul(rhs),
+                    '/' => lhs.checked_div(rhs).ok_or_else(|| AsmErrorKind::Syntax(String::from("/ 0")))?,
+                    '&' => lhs & rhs,
+                    '|' => lhs | rhs,
+                    _   => lhs ^ rhs,
+                })
+            },
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Result<Vec<Statement>, AsmErrorKind>
+{
+    let mut line = strip_comment(line).trim();
+    let mut parsed = vec![];
+
+    // Any number of labels can precede the statement
+    while let Some(colon) = line.find(':')
+    {
+        let name = line[..colon].trim();
+
+        if !is_identifier(name) { break }
+
+        parsed.push(Statement::Label(name.to_string()));
+        line = line[colon + 1..].trim();
+    }
+
+    if line.is_empty()
+    {
+        return Ok(parsed);
+    }
+
+    if let Some(equal) = line.find('=')
+    {
+        let name = line[..equal].trim();
+
+        if is_identifier(name)
+        {
+            parsed.push(Statement::Constant(name.to_string(), parse_expr(&line[equal + 1..])?));
+            return Ok(parsed);
+        }
+    }
+
+    let (word, rest) = match line.find(char::is_whitespace)
+    {
+        Some(space) => (&line[..space], line[space..].trim()),
+        None => (line, ""),
+    };
+
+    let statement = match word.to_lowercase().as_str()
+    {
+        ".org" => Statement::Org(parse_expr(rest)?),
+        ".byte" | ".db" => Statement::Bytes(
+            split_arguments(rest).into_iter().map(parse_data).collect::<Result<_, _>>()?
+        ),
+        ".word" | ".dw" => Statement::Words(
+            split_arguments(rest).into_iter().map(parse_expr).collect::<Result<_, _>>()?
+        ),
+        directive if directive.starts_with('.') => return Err(AsmErrorKind::UnknownDirective(word.to_string())),
+        _ => {
+            if !is_identifier(word)
+            {
+                return Err(AsmErrorKind::Syntax(word.to_string()));
+            }
+
+            Statement::Instruction(word.to_string(), parse_operand(rest)?)
+        },
+    };
+
+    parsed.push(statement);
+
+    Ok(parsed)
+}
+
+fn strip_comment(line: &str) -> &str
+{
+    let mut quote = None;
+
+    for (index, c) in line.char_indices()
+    {
+        match (quote, c)
+        {
+            (None, ';') => return &line[..index],
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            _ => {},
+        }
+    }
+
+    line
+}
+
+fn is_identifier(text: &str) -> bool
+{
+    let mut chars = text.chars();
+
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Splits on the commas outside of quotes and parentheses
+fn split_arguments(text: &str) -> Vec<&str>
+{
+    let mut arguments = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+
+    for (index, c) in text.char_indices()
+    {
+        match (quote, c)
+        {
+            (Some(open), _) if open == c => quote = None,
+            (Some(_), _) => {},
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                arguments.push(text[start..index].trim());
+                start = index + 1;
+            },
+            _ => {},
+        }
+    }
+
+    arguments.push(text[start..].trim());
+    arguments
+}
+
+fn parse_data(text: &str) -> Result<Data, AsmErrorKind>
+{
+    match text.strip_prefix('"').and_then(|text| text.strip_suffix('"'))
+    {
+        Some(string) => Ok(Data::Text(string.as_bytes().to_vec())),
+        None => Ok(Data::Value(parse_expr(text)?)),
+    }
+}
+
+fn parse_operand(text: &str) -> Result<Operand, AsmErrorKind>
+{
+    if text.is_empty()
+    {
+        return Ok(Operand::None);
+    }
+
+    if text.eq_ignore_ascii_case("a")
+    {
+        return Ok(Operand::Accumulator);
+    }
+
+    if let Some(immediate) = text.strip_prefix('#')
+    {
+        return Ok(Operand::Immediate(parse_expr(immediate)?));
+    }
+
+    let arguments = split_arguments(text);
+
+    let index = match arguments[..]
+    {
+        [_] => Index::None,
+        [_, register] if register.eq_ignore_ascii_case("x") => Index::X,
+        [_, register] if register.eq_ignore_ascii_case("y") => Index::Y,
+        _ => return Err(AsmErrorKind::Syntax(text.to_string())),
+    };
+
+    let base = arguments[0];
+
+    // Parentheses wrapping the whole base are an indirection, not a sub expression
+    if let Some(inner) = base.strip_prefix('(').and_then(|base| base.strip_suffix(')'))
+    {
+        if matching_parenthesis(base) == Some(base.len() - 1)
+        {
+            let inner_arguments = split_arguments(inner);
+
+            return match (&inner_arguments[..], index)
+            {
+                ([address], Index::None) => Ok(Operand::Indirect(parse_expr(address)?)),
+                ([address], Index::Y) => Ok(Operand::IndirectY(parse_expr(address)?)),
+                ([address, register], Index::None) if register.eq_ignore_ascii_case("x") => Ok(Operand::IndirectX(parse_expr(address)?)),
+                _ => Err(AsmErrorKind::Syntax(text.to_string())),
+            };
+        }
+    }
+
+    Ok(Operand::Direct(parse_expr(base)?, index))
+}
+
+fn matching_parenthesis(text: &str) -> Option<usize>
+{
+    let mut depth = 0;
+
+    for (index, c) in text.char_indices()
+    {
+        match c
+        {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+
+                if depth == 0 { return Some(index) }
+            },
+            _ => {},
+        }
+    }
+
+    None
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token
+{
+    Number(i64),
+    Identifier(String),
+    Symbol(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, AsmErrorKind>
+{
+    let syntax = || AsmErrorKind::Syntax(text.to_string());
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    // Consumes the characters matching `accept` starting at `index`
+    let take = |index: &mut usize, accept: &dyn Fn(char) -> bool| {
+        let start = *index;
+        while *index < chars.len() && accept(chars[*index]) { *index += 1 }
+        chars[start..*index].iter().collect::<String>()
+    };
+
+    while index < chars.len()
+    {
+        let c = chars[index];
+
+        match c
+        {
+            _ if c.is_whitespace() => index += 1,
+            '$' | '%' => {
+                index += 1;
+                let radix = if c == '$' { 16 } else { 2 };
+                let digits = take(&mut index, &|c| c.is_ascii_alphanumeric());
+                tokens.push(Token::Number(i64::from_str_radix(&digits, radix).map_err(|_| syntax())?));
+            },
+            '0'..='9' => {
+                let digits = take(&mut index, &|c| c.is_ascii_alphanumeric());
+                tokens.push(Token::Number(digits.parse().map_err(|_| syntax())?));
+            },
+            '\'' => {
+                match (chars.get(index + 1), chars.get(index + 2))
+                {
+                    (Some(&value), Some('\'')) => tokens.push(Token::Number(value as i64)),
+                    _ => return Err(syntax()),
+                }
+                index += 3;
+            },
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                tokens.push(Token::Identifier(take(&mut index, &|c| c.is_ascii_alphanumeric() || c == '_')));
+            },
+            '+' | '-' | '*' | '/' | '&' | '|' | '^' | '~' | '<' | '>' | '(' | ')' => {
+                tokens.push(Token::Symbol(c));
+                index += 1;
+            },
+            _ => return Err(syntax()),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_expr(text: &str) -> Result<Expr, AsmErrorKind>
+{
+    let tokens = tokenize(text)?;
+    let mut position = 0;
+
+    let expr = parse_binary(&tokens, &mut position, 0).ok_or_else(|| AsmErrorKind::Syntax(text.to_string()))?;
+
+    if position != tokens.len()
+    {
+        return Err(AsmErrorKind::Syntax(text.to_string()));
+    }
+
+    Ok(expr)
+}
+
+// Lowest precedence first
+const PRECEDENCE: [&[char]; 5] = [&['|'], &['^'], &['&'], &['+', '-'], &['*', '/']];
+
+fn parse_binary(tokens: &[Token], position: &mut usize, level: usize) -> Option<Expr>
+{
+    if level == PRECEDENCE.len()
+    {
+        return parse_unary(tokens, position);
+    }
+
+    let mut lhs = parse_binary(tokens, position, level + 1)?;
+
+    while let Some(Token::Symbol(op)) = tokens.get(*position)
+    {
+        if !PRECEDENCE[level].contains(op) { break }
+
+        *position += 1;
+        let rhs = parse_binary(tokens, position, level + 1)?;
+        lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+    }
+
+    Some(lhs)
+}
+
+fn parse_unary(tokens: &[Token], position: &mut usize) -> Option<Expr>
+{
+    let token = tokens.get(*position)?;
+    *position += 1;
+
+    match token
+    {
+        Token::Number(value) => Some(Expr::Number(*value)),
+        Token::Identifier(name) => Some(Expr::Symbol(name.clone())),
+        Token::Symbol('*') => Some(Expr::Current),
+        Token::Symbol(op @ ('-' | '<' | '>' | '~')) => Some(Expr::Unary(*op, Box::new(parse_unary(tokens, position)?))),
+        Token::Symbol('(') => {
+            let expr = parse_binary(tokens, position, 0)?;
+
+            match tokens.get(*position)
+            {
+                Some(Token::Symbol(')')) => {
+                    *position += 1;
+                    Some(expr)
+                },
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::cpu::disasm;
+
+    fn bytes(source: &str) -> Vec<u8>
+    {
+        assemble(source).unwrap().bytes
+    }
+
+    fn error(source: &str) -> AsmErrorKind
+    {
+        assemble(source).unwrap_err().kind
+    }
+
+    #[test]
+    fn every_mode()
+    {
+        let source = "
+            clc
+            asl a
+            lsr
+            lda #$01
+            lda $10
+            lda $10,x
+            ldx $10,Y
+            lda $1234
+            lda $1234,X
+            lda $1234,y
+            jmp ($1234)
+            lda ($10,X)
+            lda ($10),Y
+        ";
+
+        assert_eq!(vec![
+            0x18, 0x0A, 0x4A, 0xA9, 0x01, 0xA5, 0x10, 0xB5, 0x10, 0xB6, 0x10,
+            0xAD, 0x34, 0x12, 0xBD, 0x34, 0x12, 0xB9, 0x34, 0x12,
+            0x6C, 0x34, 0x12, 0xA1, 0x10, 0xB1, 0x10,
+        ], bytes(source));
+    }
+
+    #[test]
+    fn labels_and_branches()
+    {
+        let source = "
+            .org $0600
+            start:  ldx #5
+            loop:   dex
+                    bne loop
+                    jmp end
+                    nop
+            end:    rts
+        ";
+
+        let assembly = assemble(source).unwrap();
+
+        assert_eq!(0x0600, assembly.origin);
+        assert_eq!(vec![0xA2, 0x05, 0xCA, 0xD0, 0xFD, 0x4C, 0x09, 0x06, 0xEA, 0x60], assembly.bytes);
+        assert_eq!(Some(&0x0602), assembly.labels.get("loop"));
+    }
+
+    #[test]
+    fn forward_reference_uses_absolute()
+    {
+        assert_eq!(vec![0xAD, 0x03, 0x00, 0x00], bytes("lda data\ndata: .byte 0"));
+        assert_eq!(vec![0xA5, 0x10], bytes("value = $10\nlda value"));
+    }
+
+    #[test]
+    fn expressions()
+    {
+        let source = "
+            base = $1234
+            .byte <base, >base, base & $0F | 1, (2 + 3) * 4, %1010, 'A', -1
+            .word base + 1, * - 2
+        ";
+
+        assert_eq!(vec![0x34, 0x12, 0x05, 20, 0x0A, 0x41, 0xFF, 0x35, 0x12, 0x05, 0x00], bytes(source));
+    }
+
+    #[test]
+    fn parenthesized_expression_is_not_indirect()
+    {
+        assert_eq!(vec![0xAD, 0x02, 0x02], bytes("lda ($100 + 1) * 2"));
+        assert_eq!(vec![0xB5, 0x06], bytes("lda (2 + 1) * 2,X"));
+    }
+
+    #[test]
+    fn org_fills_gaps()
+    {
+        assert_eq!(vec![0xEA, 0x00, 0x00, 0xE8], bytes(".org $10\nnop\n.org $13\ninx"));
+    }
+
+    #[test]
+    fn org_moves_freely_until_something_is_emitted()
+    {
+        let assembly = assemble(".org $1000\nstart:\n.org $0600\nnop\n.org $0602\ninx").unwrap();
+
+        assert_eq!(0x0600, assembly.origin);
+        assert_eq!(vec![0xEA, 0x00, 0xE8], assembly.bytes);
+        assert_eq!(Some(&0x1000), assembly.labels.get("start"));
+    }
+
+    #[test]
+    fn strings_and_comments()
+    {
+        assert_eq!(vec![b'a', b';', 0x00], bytes(".byte \"a;\", 0 ; comment"));
+    }
+
+    #[test]
+    fn errors()
+    {
+        assert_eq!(AsmErrorKind::UnknownMnemonic(String::from("foo")), error("foo"));
+        assert_eq!(AsmErrorKind::UndefinedLabel(String::from("nowhere")), error("jmp nowhere"));
+        assert_eq!(AsmErrorKind::DuplicateLabel(String::from("a1")), error("a1:\na1:"));
+        assert_eq!(AsmErrorKind::InvalidOperand(String::from("STA")), error("sta #1"));
+        assert_eq!(AsmErrorKind::OutOfRange(256), error("lda #256"));
+        assert_eq!(AsmErrorKind::OrgBackwards(0), error("nop\n.org 0"));
+        assert_eq!(AsmErrorKind::UnknownDirective(String::from(".foo")), error(".foo"));
+
+        assert_eq!(AsmErrorKind::BranchOutOfRange(254), error("bne far\n.org $100\nfar: rts"));
+
+        assert_eq!(3, assemble("nop\n\nlda (1").unwrap_err().line);
+    }
+
+    #[test]
+    fn disassembly_round_trip()
+    {
+        for (opcode, entry) in OPCODES.iter().enumerate()
+        {
+            let entry = entry.unwrap();
+            let program = [opcode as u8, 0x44, 0x33];
+            let instruction = &disasm::disassemble(&program, 0x1000)[0];
+
+            let mut expected = vec![find_opcode(entry.name, entry.mode).unwrap()];
+            expected.extend_from_slice(&program[1..instruction.bytes.len()]);
+
+            assert_eq!(expected, bytes(&format!(".org $1000\n{}", instruction)), "{:#04X} {}", opcode, instruction);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use super::error::ErrorKind;
+
+/// Address space seen by the CPU, the NES memory map or any other machine
+pub trait Bus
+{
+    fn read(&self, addr: u16) -> u8;
+
+    /// Fails for addresses that cannot be written, the CPU reports it as an error
+    fn write(&mut self, addr: u16, value: u8) -> Result<(), ErrorKind>;
+
+    /// Reads without side effects, for debugging tools
+    fn peek(&self, addr: u16) -> u8
+    {
+        self.read(addr)
+    }
+
+    /// State of the IRQ line driven by the devices on the bus, such as a cartridge
+    fn irq(&self) -> bool
+    {
+        false
+    }
+
+    /// CPU cycle at the start of the current instruction, for devices sensitive to the timing of accesses
+    fn set_cycle(&mut self, _cycle: u64)
+    {
+    }
+
+    /// ADC and SBC follow the D flag like on an NMOS 6502 when true, the NES CPU has no decimal mode
+    fn decimal_mode(&self) -> bool
+    {
+        false
+    }
+
+    fn read_u16(&self, addr: u16) -> u16
+    {
+        u16::from_le_bytes([self.read(addr), self.read(addr.wrapping_add(1))])
+    }
+
+    fn peek_u16(&self, addr: u16) -> u16
+    {
+        u16::from_le_bytes([self.peek(addr), self.peek(addr.wrapping_add(1))])
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::cell::RefCell;
+
+    use crate::cpu::{Cpu, RegisterSnapshot};
+    use super::*;
+
+    // 4K of RAM mirrored over the whole address space, keeping a log of the accesses
+    struct MirroredBus
+    {
+        ram: [u8; 0x1000],
+        log: RefCell<Vec<(u16, Option<u8>)>>,
+    }
+
+    impl Bus for MirroredBus
+    {
+        fn read(&self, addr: u16) -> u8
+        {
+            self.log.borrow_mut().push((addr, None));
+            self.peek(addr)
+        }
+
+        fn write(&mut self, addr: u16, value: u8) -> Result<(), ErrorKind>
+        {
+            self.log.borrow_mut().push((addr, Some(value)));
+            self.ram[addr as usize & 0x0FFF] = value;
+
+            Ok(())
+        }
+
+        fn peek(&self, addr: u16) -> u8
+        {
+            self.ram[addr as usize & 0x0FFF]
+        }
+    }
+
+    #[test]
+    fn custom_bus()
+    {
+        let mut bus = MirroredBus { ram: [0; 0x1000], log: RefCell::new(vec![]) };
+
+        // Reset vector at $FFFC, mirrored from $0FFC, then LDA #$42 ; STA $F010
+        bus.ram[0x0FFC] = 0x00;
+        bus.ram[0x0FFD] = 0x06;
+        bus.ram[0x0600..0x0605].copy_from_slice(&[0xA9, 0x42, 0x8D, 0x10, 0xF0]);
+
+        let mut cpu = Cpu::with_bus(bus);
+        cpu.reset();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(0x42, cpu.memory.peek(0x0010));
+        assert_eq!(Some(&(0xF010, Some(0x42))), cpu.memory.log.borrow().last());
+    }
+
+    #[test]
+    fn read_modify_write()
+    {
+        let mut bus = MirroredBus { ram: [0; 0x1000], log: RefCell::new(vec![]) };
+
+        // INC $F010, the unmodified value is written back before the result
+        bus.ram[0x0FFD] = 0x06;
+        bus.ram[0x0600..0x0603].copy_from_slice(&[0xEE, 0x10, 0xF0]);
+        bus.ram[0x0010] = 0x41;
+
+        let mut cpu = Cpu::with_bus(bus);
+        cpu.reset();
+        cpu.step().unwrap();
+
+        let log = cpu.memory.log.borrow();
+        let writes: Vec<_> = log.iter().filter(|(_, value)| value.is_some()).collect();
+
+        assert_eq!(vec![&(0xF010, Some(0x41)), &(0xF010, Some(0x42))], writes);
+    }
+
+    #[test]
+    fn page_crossing_accesses()
+    {
+        let mut bus = MirroredBus { ram: [0; 0x1000], log: RefCell::new(vec![]) };
+
+        // LDA $F0FF,X with a page crossing, then a taken BNE. Their penalties are only peeked,
+        // the bus sees the read from the wrong page and the opcode fetched while branching
+        bus.ram[0x0FFD] = 0x06;
+        bus.ram[0x0600..0x0605].copy_from_slice(&[0xBD, 0xFF, 0xF0, 0xD0, 0x7E]);
+        bus.ram[0x0100] = 0x42;
+
+        let mut cpu = Cpu::with_bus(bus);
+        cpu.reset();
+        let registers = cpu.registers();
+        cpu.set_registers(RegisterSnapshot { x: 1, ..registers });
+        cpu.memory.log.borrow_mut().clear();
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        let reads: Vec<_> = cpu.memory.log.borrow().iter().map(|(addr, _)| *addr).collect();
+
+        assert_eq!(vec![0x0600, 0x0601, 0x0602, 0xF000, 0xF100, 0x0603, 0x0604, 0x0605], reads);
+        assert_eq!(0x42, cpu.registers().a);
+    }
+
+    #[test]
+    fn interrupt_accesses()
+    {
+        let mut bus = MirroredBus { ram: [0; 0x1000], log: RefCell::new(vec![]) };
+
+        // Reset to $0600 and NMI to $0700, the interrupt takes the place of the opcode fetch
+        bus.ram[0x0FFB] = 0x07;
+        bus.ram[0x0FFD] = 0x06;
+
+        let mut cpu = Cpu::with_bus(bus);
+        cpu.reset();
+        cpu.memory.log.borrow_mut().clear();
+
+        cpu.set_nmi(true);
+        cpu.step().unwrap();
+
+        assert_eq!(
+            vec![
+                (0x0600, None), (0x0600, None),
+                (0x01FD, Some(0x06)), (0x01FC, Some(0x00)), (0x01FB, Some(0x24)),
+                (0xFFFA, None), (0xFFFB, None),
+            ],
+            *cpu.memory.log.borrow()
+        );
+        assert_eq!(0x0700, cpu.registers().pc);
+    }
+}
@@ -0,0 +1,34 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Memory mapped device, such as the PPU or APU registers, see `Memory::map`.
+/// Addresses are relative to the start of the mapped range, after mirroring
+pub trait Device
+{
+    /// None when the device does not drive the data bus (write-only register), the read returns open bus
+    fn read(&mut self, addr: u16) -> Option<u8>;
+
+    fn write(&mut self, addr: u16, value: u8);
+
+    /// Reads without side effects, for debugging tools
+    fn peek(&self, addr: u16) -> Option<u8>;
+}
+
+// Lets the host keep a handle on a device mapped in memory
+impl<T: Device> Device for Rc<RefCell<T>>
+{
+    fn read(&mut self, addr: u16) -> Option<u8>
+    {
+        self.borrow_mut().read(addr)
+    }
+
+    fn write(&mut self, addr: u16, value: u8)
+    {
+        self.borrow_mut().write(addr, value);
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8>
+    {
+        self.borrow().peek(addr)
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use super::Bus;
+use super::ops::{OPCODES, AddressingMode, opcode_length};
+
+/// A decoded instruction, displayed as its mnemonic and operand, e.g. `LDA ($80),Y`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction
+{
+    pub addr:     u16,
+    pub opcode:   u8,
+    pub name:     &'static str,
+    pub mode:     AddressingMode,
+    pub official: bool,
+    pub bytes:    Vec<u8>, // Opcode followed by the operand bytes
+}
+
+impl Instruction
+{
+    pub fn length(&self) -> u16
+    {
+        self.bytes.len() as u16
+    }
+
+    pub fn mnemonic(&self) -> String
+    {
+        self.name.to_uppercase()
+    }
+
+    /// Operand as a little endian value, 0 without any
+    pub fn operand(&self) -> u16
+    {
+        match self.bytes[1..]
+        {
+            [lsb]      => lsb as u16,
+            [lsb, msb] => u16::from_le_bytes([lsb, msb]),
+            _          => 0,
+        }
+    }
+
+    /// Address of the instruction following this one
+    pub fn next_addr(&self) -> u16
+    {
+        self.addr.wrapping_add(self.length())
+    }
+
+    /// Where the instruction can transfer control to, besides the next instruction
+    pub fn jump_target(&self) -> Option<u16>
+    {
+        match (self.name, self.mode)
+        {
+            (_, AddressingMode::Relative) => Some(self.next_addr().wrapping_add(self.operand() as u8 as i8 as u16)),
+            ("Jmp" | "Jsr", AddressingMode::Absolute) => Some(self.operand()),
+            _ => None,
+        }
+    }
+
+    /// Whether the execution never continues with the next instruction
+    pub fn ends_flow(&self) -> bool
+    {
+        matches!(self.name, "Jmp" | "Rts" | "Rti" | "Brk" | "Jam")
+    }
+
+    pub fn operand_text(&self) -> String
+    {
+        let operand = self.operand();
+
+        match self.mode
+        {
+            AddressingMode::Implicit    => String::new(),
+            AddressingMode::Accumulator => String::from("A"),
+            AddressingMode::Immediate   => format!("#${:02X}", operand),
+            AddressingMode::ZeroPage    => format!("${:02X}", operand),
+            AddressingMode::ZeroPageX   => format!("${:02X},X", operand),
+            AddressingMode::ZeroPageY   => format!("${:02X},Y", operand),
+            AddressingMode::Absolute    => format!("${:04X}", operand),
+            AddressingMode::AbsoluteX   => format!("${:04X},X", operand),
+            AddressingMode::AbsoluteY   => format!("${:04X},Y", operand),
+            AddressingMode::Indirect    => format!("(${:04X})", operand),
+            AddressingMode::IndirectX   => format!("(${:02X},X)", operand),
+            AddressingMode::IndirectY   => format!("(${:02X}),Y", operand),
+            AddressingMode::Relative    => format!("${:04X}", self.jump_target().unwrap_or_default()),
+        }
+    }
+}
+
+impl Display for Instruction
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self.mode
+        {
+            AddressingMode::Implicit => write!(f, "{}", self.mnemonic()),
+            _ => write!(f, "{} {}", self.mnemonic(), self.operand_text()),
+        }
+    }
+}
+
+/// Decodes the instruction at `addr`, `read` giving the byte at any address
+pub fn decode<F>(read: F, addr: u16) -> Option<Instruction>
+    where F: Fn(u16) -> u8
+{
+    let opcode = read(addr);
+    let entry = OPCODES[opcode as usize].as_ref()?;
+
+    let bytes = (0..opcode_length(entry.mode) as u16)
+        .map(|i| read(addr.wrapping_add(i)))
+        .collect();
+
+    Some(Instruction
+    {
+        addr,
+        opcode,
+        name:     entry.name,
+        mode:     entry.mode,
+        official: entry.official,
+        bytes,
+    })
+}
+
+/// Decodes `bytes` in sequence as if they were loaded at `origin`.
+/// An instruction cut by the end of the slice is left out.
+pub fn disassemble(bytes: &[u8], origin: u16) -> Vec<Instruction>
+{
+    let mut instructions = vec![];
+    let mut offset = 0;
+
+    while offset < bytes.len()
+    {
+        let instruction = match decode(|addr| slice_read(bytes, origin, addr), origin.wrapping_add(offset as u16))
+        {
+            Some(instruction) => instruction,
+            None => break,
+        };
+
+        offset += instruction.bytes.len();
+
+        if offset > bytes.len() { break }
+
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
+/// Decodes the memory in sequence from `start` until an instruction goes past `end` (included)
+pub fn disassemble_memory(memory: &dyn Bus, start: u16, end: u16) -> Vec<Instruction>
+{
+    let mut instructions = vec![];
+    let mut addr = start as u32;
+
+    while addr <= end as u32
+    {
+        let instruction = match decode(|pos| memory.peek(pos), addr as u16)
+        {
+            Some(instruction) => instruction,
+            None => break,
+        };
+
+        addr += instruction.length() as u32;
+
+        if addr > end as u32 + 1 { break }
+
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
+/// Follows the control flow from `entry_points` and returns the address of every instruction reached.
+/// Only the code inside `bytes` (loaded at `origin`) is followed, indirect jumps are not.
+pub fn reachable(bytes: &[u8], origin: u16, entry_points: &[u16]) -> BTreeSet<u16>
+{
+    let contains = |addr: u16| (addr.wrapping_sub(origin) as usize) < bytes.len();
+
+    let mut visited = BTreeSet::new();
+    let mut pending: Vec<u16> = entry_points.iter().copied().filter(|&addr| contains(addr)).collect();
+
+    while let Some(addr) = pending.pop()
+    {
+        if !visited.insert(addr) { continue }
+
+        let instruction = match decode(|pos| slice_read(bytes, origin, pos), addr)
+        {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+
+        if let Some(target) = instruction.jump_target()
+        {
+            if contains(target) { pending.push(target) }
+        }
+
+        if !instruction.ends_flow() && contains(instruction.next_addr())
+        {
+            pending.push(instruction.next_addr());
+        }
+    }
+
+    visited
+}
+
+// Outside of the slice reads as 0
+fn slice_read(bytes: &[u8], origin: u16, addr: u16) -> u8
+{
+    *bytes.get(addr.wrapping_sub(origin) as usize).unwrap_or(&0)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::cpu::Memory;
+    use super::*;
+
+    fn text(instructions: &[Instruction]) -> Vec<String>
+    {
+        instructions.iter().map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn every_mode()
+    {
+        let program = [
+            0x18,               // CLC
+            0x0A,               // ASL A
+            0xA9, 0x01,         // LDA #$01
+            0xA5, 0x10,         // LDA $10
+            0xB5, 0x10,         // LDA $10,X
+            0xB6, 0x10,         // LDX $10,Y
+            0xAD, 0x34, 0x12,   // LDA $1234
+            0xBD, 0x34, 0x12,   // LDA $1234,X
+            0xB9, 0x34, 0x12,   // LDA $1234,Y
+            0x6C, 0x34, 0x12,   // JMP ($1234)
+            0xA1, 0x10,         // LDA ($10,X)
+            0xB1, 0x10,         // LDA ($10),Y
+        ];
+
+        assert_eq!(
+            vec![
+                "CLC", "ASL A", "LDA #$01", "LDA $10", "LDA $10,X", "LDX $10,Y",
+                "LDA $1234", "LDA $1234,X", "LDA $1234,Y", "JMP ($1234)", "LDA ($10,X)", "LDA ($10),Y",
+            ],
+            text(&disassemble(&program, 0x8000))
+        );
+    }
+
+    #[test]
+    fn relative_target_is_resolved()
+    {
+        let instructions = disassemble(&[0xEA, 0xD0, 0xFD, 0xF0, 0x02], 0xC000);
+
+        assert_eq!(vec!["NOP", "BNE $C000", "BEQ $C007"], text(&instructions));
+        assert_eq!(0xC003, instructions[2].addr);
+    }
+
+    #[test]
+    fn truncated_instruction_is_left_out()
+    {
+        assert_eq!(vec!["INX"], text(&disassemble(&[0xE8, 0xAD, 0x34], 0x8000)));
+    }
+
+    #[test]
+    fn memory_range()
+    {
+        let mut memory = Memory::new();
+        memory.write_slice(0x0600, &[0xA2, 0x05, 0xCA, 0xD0, 0xFD, 0x60]).unwrap();
+
+        let instructions = disassemble_memory(&memory, 0x0600, 0x0605);
+
+        assert_eq!(vec!["LDX #$05", "DEX", "BNE $0602", "RTS"], text(&instructions));
+        assert_eq!(0x0605, instructions[3].addr);
+    }
+
+    #[test]
+    fn reachable_follows_control_flow()
+    {
+        let program = [
+            0x20, 0x09, 0x80,   // 8000: JSR $8009
+            0xF0, 0x01,         // 8003: BEQ $8006
+            0x60,               // 8005: RTS
+            0x4C, 0x00, 0x80,   // 8006: JMP $8000
+            0xE8,               // 8009: INX
+            0x40,               // 800A: RTI
+            0xFF,               // 800B: data
+        ];
+
+        let code = reachable(&program, 0x8000, &[0x8000]);
+
+        assert_eq!(vec![0x8000, 0x8003, 0x8005, 0x8006, 0x8009, 0x800A], code.into_iter().collect::<Vec<_>>());
+    }
+}
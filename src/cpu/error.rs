@@ -0,0 +1,47 @@
+use std::fmt::{Display, Formatter};
+
+use super::AddressingMode;
+use super::register::RegisterSnapshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind
+{
+    UnsupportedOpcode,
+    RomWrite(u16),
+    OutOfBounds(u16), // Start of a slice running past the end of the address space
+    InvalidOperand(AddressingMode),
+}
+
+/// Error raised while executing an instruction, with the CPU state at the time of the failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuError
+{
+    pub kind:      ErrorKind,
+    pub pc:        u16, // Address of the failing instruction
+    pub opcode:    u8,
+    pub registers: RegisterSnapshot,
+}
+
+impl Display for ErrorKind
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            ErrorKind::UnsupportedOpcode    => write!(f, "Unsupported opcode"),
+            ErrorKind::RomWrite(addr)       => write!(f, "Unable to write into cartridge ROM space at {:#06X}", addr),
+            ErrorKind::OutOfBounds(addr)    => write!(f, "Data from {:#06X} runs past the end of the address space", addr),
+            ErrorKind::InvalidOperand(mode) => write!(f, "You cannot get operand address for {:?} addressing mode", mode),
+        }
+    }
+}
+
+impl Display for CpuError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{0} (opcode {1:#04X} at {2:#06X}) - {3}", self.kind, self.opcode, self.pc, self.registers)
+    }
+}
+
+impl std::error::Error for CpuError {}
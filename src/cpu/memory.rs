@@ -1,16 +1,67 @@
+use std::cell::{Cell, RefCell};
+use std::ops::RangeInclusive;
+
+use crate::mapper::{self, Mapper, CARTRIDGE_START, PRG_ROM_START};
 use crate::rom::Rom;
 
+use super::bus::Bus;
+use super::device::Device;
+use super::error::ErrorKind;
+
 pub const RAM_START:      u16 = 0x0000;
 pub const RAM_END:        u16 = 0xFFFF;
 pub const RAM_MIRROR_END: u16 = 0x1FFF;
 pub const PPU_START:      u16 = 0x2000;
 pub const PPU_MIRROR_END: u16 = 0x3FFF;
-pub const PRG_ROM_START:  u16 = 0x8000; // TODO: align with mapper number
+pub const APU_IO_START:   u16 = 0x4000;
+pub const APU_IO_END:     u16 = 0x401F;
+
+// PPUCTRL, PPUMASK, OAMADDR, PPUSCROLL and PPUADDR cannot be read back
+const WRITE_ONLY_PPU_REGISTERS: [u16; 5] = [0, 1, 3, 5, 6];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access
+{
+    Read,
+    Write
+}
+
+/// Access matching a watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit
+{
+    pub addr:   u16,
+    pub access: Access,
+    pub value:  u8, // Value read, or written
+}
+
+/// Device mapped on a range of addresses
+struct Mapping
+{
+    range:  RangeInclusive<u16>,
+    mask:   u16,
+    device: RefCell<Box<dyn Device>>, // Reads are not mutable
+}
+
+impl Mapping
+{
+    fn offset(&self, pos: u16) -> u16
+    {
+        (pos - self.range.start()) & self.mask
+    }
+}
 
 pub struct Memory
 {
     memory: [u8; 0x10000],
-    rom: Rom
+    cartridge: Option<RefCell<Box<dyn Mapper>>>, // Reads are not mutable
+    flat: bool, // Plain 64K of RAM, no mirroring and no cartridge
+    decimal_mode: bool,
+    devices: Vec<Mapping>,
+    bus_latch: Cell<u8>, // Last value on the data bus, what reads nothing drives return (open bus)
+
+    watchpoints: Vec<(u16, Access)>,
+    watch_hit:   Cell<Option<WatchHit>>, // Reads are not mutable
 }
 
 impl Memory
@@ -19,13 +70,103 @@ impl Memory
     {
         Memory {
             memory: [0; 0x10000],
-            rom: Rom::empty()
+            cartridge: None,
+            flat: false,
+            decimal_mode: false,
+            devices: vec![],
+            bus_latch: Cell::new(0),
+
+            watchpoints: vec![],
+            watch_hit:   Cell::new(None),
+        }
+    }
+
+    /// The whole address space is writable RAM, for bare 6502 programs
+    pub fn flat() -> Memory
+    {
+        Memory {
+            flat: true,
+            ..Memory::new()
+        }
+    }
+
+    /// See `Bus::decimal_mode`
+    pub fn set_decimal_mode(&mut self, enabled: bool)
+    {
+        self.decimal_mode = enabled;
+    }
+
+    /// Plugs a cartridge with the mapper of the ROM, fails for unsupported mappers
+    pub fn load_rom(&mut self, rom: Rom) -> Result<(), String>
+    {
+        self.cartridge = Some(RefCell::new(mapper::create(rom)?));
+
+        Ok(())
+    }
+
+    /// Content of the battery backed RAM of the cartridge, None if it has none
+    pub fn battery_ram(&self) -> Option<Vec<u8>>
+    {
+        self.cartridge.as_ref()?.borrow().battery_ram().map(|ram| ram.to_vec())
+    }
+
+    /// Restores a save made from `battery_ram`
+    pub fn load_battery_ram(&mut self, data: &[u8])
+    {
+        if let Some(cartridge) = self.cartridge.as_mut()
+        {
+            cartridge.get_mut().load_battery_ram(data);
         }
     }
 
-    pub fn load_rom(&mut self, rom: Rom)
+    /// Pattern table access by the PPU, the cartridge sees every address for mappers that watch them
+    pub fn ppu_read(&self, addr: u16) -> u8
     {
-        self.rom = rom;
+        self.cartridge.as_ref().map_or(0, |cartridge| cartridge.borrow_mut().ppu_read(addr))
+    }
+
+    pub fn ppu_write(&mut self, addr: u16, value: u8)
+    {
+        if let Some(cartridge) = self.cartridge.as_mut()
+        {
+            cartridge.get_mut().ppu_write(addr, value);
+        }
+    }
+
+    /// Nametable read by the PPU, None when it comes from the console VRAM
+    pub fn nametable_read(&self, addr: u16) -> Option<u8>
+    {
+        self.cartridge.as_ref()?.borrow_mut().nametable_read(addr)
+    }
+
+    /// False when the write goes to the console VRAM
+    pub fn nametable_write(&mut self, addr: u16, value: u8) -> bool
+    {
+        self.cartridge.as_mut().is_some_and(|cartridge| cartridge.get_mut().nametable_write(addr, value))
+    }
+
+    // The cartridge sees everything from $4020, in the NES memory map
+    fn in_cartridge(&self, pos: u16) -> bool
+    {
+        !self.flat && pos >= CARTRIDGE_START
+    }
+
+    fn cartridge(&self, pos: u16) -> Option<&RefCell<Box<dyn Mapper>>>
+    {
+        self.cartridge.as_ref().filter(|_| self.in_cartridge(pos))
+    }
+
+    /// Maps a device over `range`, taking precedence over what was there before.
+    /// The device gets `(addr - start) & mask`, e.g. `0x2000..=0x3FFF` with a mask of 7 for the PPU registers
+    pub fn map(&mut self, range: RangeInclusive<u16>, mask: u16, device: Box<dyn Device>)
+    {
+        self.devices.push(Mapping { range, mask, device: RefCell::new(device) });
+    }
+
+    // Last mapped first
+    fn device(&self, pos: u16) -> Option<&Mapping>
+    {
+        self.devices.iter().rev().find(|mapping| mapping.range.contains(&pos))
     }
 
     fn unmirrored_addr(&self, pos: u16) -> usize
@@ -42,68 +183,254 @@ impl Memory
 
     pub fn read(&self, pos: u16) -> u8
     {
+        let value = match (self.device(pos), self.cartridge(pos))
+        {
+            (Some(mapping), _) => mapping.device.borrow_mut().read(mapping.offset(pos)),
+            (None, Some(cartridge)) => cartridge.borrow_mut().cpu_read(pos),
+            (None, None) => self.driven(pos),
+        };
+        let value = value.unwrap_or(self.bus_latch.get());
+
+        self.bus_latch.set(value);
+
+        if !self.watchpoints.is_empty()
+        {
+            self.check_watchpoints(pos, Access::Read, value);
+        }
+
+        value
+    }
+
+    /// Reads without triggering watchpoints, for debugging tools
+    pub fn peek(&self, pos: u16) -> u8
+    {
+        let value = match (self.device(pos), self.cartridge(pos))
+        {
+            (Some(mapping), _) => mapping.device.borrow().peek(mapping.offset(pos)),
+            (None, Some(cartridge)) => cartridge.borrow().cpu_peek(pos),
+            (None, None) => self.driven(pos),
+        };
+
+        value.unwrap_or(self.bus_latch.get())
+    }
+
+    // Value put on the data bus by the RAM, None for open bus
+    fn driven(&self, pos: u16) -> Option<u8>
+    {
+        if self.flat
+        {
+            return Some(self.memory[pos as usize]);
+        }
+
         match pos
         {
-            PRG_ROM_START..=RAM_END => self.rom_read(pos),
-            _ => self.memory[self.unmirrored_addr(pos)]
+            PPU_START..=PPU_MIRROR_END if WRITE_ONLY_PPU_REGISTERS.contains(&(pos & 0x0007)) => None,
+            APU_IO_START..=APU_IO_END => None,
+            CARTRIDGE_START..=RAM_END => None, // No cartridge
+            _ => Some(self.memory[self.unmirrored_addr(pos)])
         }
     }
 
+    /// Panics on writes `try_write` refuses, for setting up memory in tests and tools
     pub fn write(&mut self, pos: u16, data: u8)
     {
-        match pos
+        if let Err(err) = self.try_write(pos, data)
+        {
+            panic!("{}", err);
+        }
+    }
+
+    // Used by the CPU so a bad write is reported instead of panicking
+    pub fn try_write(&mut self, pos: u16, data: u8) -> Result<(), ErrorKind>
+    {
+        self.bus_latch.set(data);
+
+        let in_cartridge = self.in_cartridge(pos);
+
+        if let Some(index) = self.devices.iter().rposition(|mapping| mapping.range.contains(&pos))
+        {
+            let mapping = &mut self.devices[index];
+            let offset = mapping.offset(pos);
+            mapping.device.get_mut().write(offset, data);
+        }
+        else if let Some(cartridge) = self.cartridge.as_mut().filter(|_| in_cartridge)
+        {
+            cartridge.get_mut().cpu_write(pos, data);
+        }
+        else
+        {
+            let addr = self.writable_addr(pos)?;
+
+            self.memory[addr] = data;
+        }
+
+        // Some mappers watch the PPU configuration
+        if let (false, PPU_START..=PPU_MIRROR_END, Some(cartridge)) = (self.flat, pos, self.cartridge.as_mut())
+        {
+            cartridge.get_mut().ppu_register_write(pos & 0x2007, data);
+        }
+
+        if !self.watchpoints.is_empty()
+        {
+            self.check_watchpoints(pos, Access::Write, data);
+        }
+
+        Ok(())
+    }
+
+    pub fn add_watchpoint(&mut self, pos: u16, access: Access)
+    {
+        if !self.watchpoints.contains(&(pos, access))
         {
-            PRG_ROM_START..=RAM_END => panic!("Unable to write into cartridge ROM space"),
-            _ => self.memory[self.unmirrored_addr(pos)] = data
+            self.watchpoints.push((pos, access));
+        }
+    }
+
+    pub fn remove_watchpoint(&mut self, pos: u16, access: Access)
+    {
+        self.watchpoints.retain(|&watchpoint| watchpoint != (pos, access));
+    }
+
+    pub fn watchpoints(&self) -> &[(u16, Access)]
+    {
+        &self.watchpoints
+    }
+
+    /// First watchpoint hit since the last call
+    pub fn take_watch_hit(&mut self) -> Option<WatchHit>
+    {
+        self.watch_hit.take()
+    }
+
+    fn check_watchpoints(&self, pos: u16, access: Access, value: u8)
+    {
+        if self.watch_hit.get().is_none() && self.watchpoints.contains(&(pos, access))
+        {
+            self.watch_hit.set(Some(WatchHit { addr: pos, access, value }));
         }
     }
 
     pub fn read_u16(&self, pos: u16) -> u16
     {
         let lsb = self.read(pos);
-        let msb = self.read(pos + 1);
+        let msb = self.read(pos.wrapping_add(1));
 
         u16::from_le_bytes([lsb, msb])
     }
 
-    pub fn write_u16(&mut self, pos: u16, data: u16)
+    pub fn write_u16(&mut self, pos: u16, data: u16) -> Result<(), ErrorKind>
     {
         let bytes = data.to_le_bytes();
 
-        self.write(pos, bytes[0]);
-        self.write(pos + 1, bytes[1]);
+        self.try_write(pos, bytes[0])?;
+        self.try_write(pos.wrapping_add(1), bytes[1])
+    }
+
+    /// Reads `length` bytes from `pos` without triggering watchpoints
+    pub fn read_slice(&self, pos: u16, length: usize) -> Result<Vec<u8>, ErrorKind>
+    {
+        Self::check_bounds(pos, length)?;
+
+        Ok((0..length).map(|offset| self.peek(pos + offset as u16)).collect())
+    }
+
+    /// Writes `data` from `pos` without triggering watchpoints, nothing is written unless it all fits in RAM
+    pub fn write_slice(&mut self, pos: u16, data: &[u8]) -> Result<(), ErrorKind>
+    {
+        let addrs = self.writable_addrs(pos, data.len())?;
+
+        for (addr, byte) in addrs.into_iter().zip(data)
+        {
+            self.memory[addr] = *byte;
+        }
+
+        Ok(())
+    }
+
+    /// Fails like `write_slice` would, without writing anything
+    pub fn check_writable(&self, pos: u16, length: usize) -> Result<(), ErrorKind>
+    {
+        self.writable_addrs(pos, length).map(|_| ())
+    }
+
+    fn writable_addrs(&self, pos: u16, length: usize) -> Result<Vec<usize>, ErrorKind>
+    {
+        Self::check_bounds(pos, length)?;
+
+        (0..length).map(|offset| self.writable_addr(pos + offset as u16)).collect()
     }
 
-    // TODO: handle mapping and prevent writting to rom
-    pub fn read_slice(&self, pos: u16, length: usize) -> &[u8]
+    fn writable_addr(&self, pos: u16) -> Result<usize, ErrorKind>
     {
-        &self.memory[(pos as usize)..(pos as usize) + length]
+        match pos
+        {
+            _ if self.flat => Ok(pos as usize),
+            _ if self.cartridge(pos).is_some() => Err(ErrorKind::RomWrite(pos)),
+            PRG_ROM_START..=RAM_END => Err(ErrorKind::RomWrite(pos)),
+            _ => Ok(self.unmirrored_addr(pos)),
+        }
     }
 
-    pub fn write_slice(&mut self, pos: u16, data: &[u8])
+    // Slices do not wrap around the end of the address space
+    fn check_bounds(pos: u16, length: usize) -> Result<(), ErrorKind>
     {
-        self.memory[(pos as usize)..(pos as usize) + data.len()].copy_from_slice(data);
+        match pos as usize + length > RAM_END as usize + 1
+        {
+            true => Err(ErrorKind::OutOfBounds(pos)),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Bus for Memory
+{
+    fn read(&self, addr: u16) -> u8
+    {
+        Memory::read(self, addr)
     }
 
-    fn rom_read(&self, pos: u16) -> u8
+    fn write(&mut self, addr: u16, value: u8) -> Result<(), ErrorKind>
     {
-        // Reframe only on PRG space
-        let mut addr = pos - PRG_ROM_START;
+        self.try_write(addr, value)
+    }
 
-        // Only 1 bank of 16KB PRG so we mirror
-        if self.rom.prg.len() == 0x4000 && addr > 0x4000
+    fn peek(&self, addr: u16) -> u8
+    {
+        Memory::peek(self, addr)
+    }
+
+    fn set_cycle(&mut self, cycle: u64)
+    {
+        if let Some(cartridge) = self.cartridge.as_mut()
         {
-            addr %= 0x4000;
+            cartridge.get_mut().set_cycle(cycle);
         }
+    }
+
+    fn irq(&self) -> bool
+    {
+        self.cartridge(CARTRIDGE_START).is_some_and(|cartridge| cartridge.borrow().irq())
+    }
 
-        self.rom.prg[addr as usize]
+    fn decimal_mode(&self) -> bool
+    {
+        self.decimal_mode
     }
+}
 
+impl Default for Memory
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
 mod tests
 {
+    use std::rc::Rc;
+
     use super::*;
 
     #[test]
@@ -142,10 +469,23 @@ mod tests
     {
         let mut m = Memory::new();
 
-        m.memory[0xFE] = 0x34;
-        m.memory[0xFF] = 0x12;
+        m.write_u16(0xFE, 0x1234).unwrap();
 
-        assert_eq!(0x1234, m.read_u16(0xFE));
+        assert_eq!(0x34, m.memory[0xFE]);
+        assert_eq!(0x12, m.memory[0xFF]);
+        assert_eq!(Err(ErrorKind::RomWrite(0xFFFC)), m.write_u16(0xFFFC, 0x1234));
+    }
+
+    #[test]
+    fn u16_wraps_around()
+    {
+        let mut m = Memory::flat();
+
+        m.write_u16(0xFFFF, 0x1234).unwrap();
+
+        assert_eq!(0x34, m.read(0xFFFF));
+        assert_eq!(0x12, m.read(0x0000));
+        assert_eq!(0x1234, m.read_u16(0xFFFF));
     }
 
     #[test]
@@ -157,7 +497,28 @@ mod tests
         m.memory[0xA1] = 0x2;
         m.memory[0xA2] = 0x3;
 
-        assert_eq!(&[0x1, 0x2, 0x3], m.read_slice(0xA0, 3));
+        assert_eq!(Ok(vec![0x1, 0x2, 0x3]), m.read_slice(0xA0, 3));
+        assert_eq!(Ok(vec![0x1]), m.read_slice(0x08A0, 1)); // Mirrored
+        assert_eq!(Err(ErrorKind::OutOfBounds(0xFFFF)), m.read_slice(0xFFFF, 2));
+    }
+
+    #[test]
+    fn write_slice()
+    {
+        let mut m = Memory::new();
+
+        m.write_slice(0x07FF, &[0x1, 0x2]).unwrap();
+
+        assert_eq!(0x1, m.memory[0x07FF]);
+        assert_eq!(0x2, m.memory[0x0000]); // Mirrored
+
+        assert_eq!(Err(ErrorKind::RomWrite(0x8000)), m.write_slice(0x7FFF, &[0x3, 0x4]));
+        assert_eq!(0x00, m.memory[0x7FFF]);
+
+        let mut m = Memory::flat();
+
+        assert_eq!(Err(ErrorKind::OutOfBounds(0xFFFF)), m.write_slice(0xFFFF, &[0x5, 0x6]));
+        assert_eq!(0x00, m.memory[0xFFFF]);
     }
 
     #[test]
@@ -192,10 +553,10 @@ mod tests
     {
         let mut m = Memory::new();
 
-        m.memory[0x2000] = 0xFF;
+        m.memory[0x2002] = 0xFF;
 
-        assert_eq!(0xFF, m.read(0x2008));
-        assert_eq!(0xFF, m.read(0x2010));
+        assert_eq!(0xFF, m.read(0x200A));
+        assert_eq!(0xFF, m.read(0x2012));
     }
 
     #[test]
@@ -213,4 +574,180 @@ mod tests
         assert_eq!(0xFD, m.memory[0x2000]);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn try_write_rom()
+    {
+        let mut m = Memory::new();
+
+        assert_eq!(Err(ErrorKind::RomWrite(0x8000)), m.try_write(0x8000, 0xFF));
+        assert_eq!(Ok(()), m.try_write(0x07FF, 0xFF));
+    }
+
+    #[test]
+    fn flat()
+    {
+        let mut m = Memory::flat();
+
+        m.write(0x0800, 0x01);
+        m.write(0x2008, 0x02);
+        m.write(0xFFFC, 0x03);
+
+        assert_eq!(0x00, m.read(0x0000));
+        assert_eq!(0x01, m.read(0x0800));
+        assert_eq!(0x00, m.read(0x2000));
+        assert_eq!(0x02, m.read(0x2008));
+        assert_eq!(0x03, m.read(0xFFFC));
+    }
+
+    #[test]
+    fn watchpoints()
+    {
+        let mut m = Memory::new();
+
+        m.add_watchpoint(0x10, Access::Write);
+        m.add_watchpoint(0x20, Access::Read);
+
+        m.read(0x10);
+        m.write(0x20, 0x01);
+        assert_eq!(None, m.take_watch_hit());
+
+        m.write(0x10, 0x42);
+        m.read(0x20);
+        assert_eq!(Some(WatchHit { addr: 0x10, access: Access::Write, value: 0x42 }), m.take_watch_hit());
+        assert_eq!(None, m.take_watch_hit());
+
+        m.peek(0x20);
+        assert_eq!(None, m.take_watch_hit());
+
+        m.remove_watchpoint(0x20, Access::Read);
+        m.read(0x20);
+        assert_eq!(None, m.take_watch_hit());
+    }
+
+    // Status register at 0 cleared by reads, data register at 1 with an auto incremented address
+    #[derive(Default)]
+    struct Registers
+    {
+        status:  u8,
+        data:    Vec<(u8, u8)>,
+        address: u8,
+    }
+
+    impl Device for Registers
+    {
+        fn read(&mut self, addr: u16) -> Option<u8>
+        {
+            let value = self.peek(addr);
+
+            if addr == 0 { self.status = 0 }
+
+            value
+        }
+
+        fn write(&mut self, addr: u16, value: u8)
+        {
+            if addr == 1
+            {
+                self.data.push((self.address, value));
+                self.address += 1;
+            }
+        }
+
+        fn peek(&self, addr: u16) -> Option<u8>
+        {
+            if addr == 0 { Some(self.status) } else { None }
+        }
+    }
+
+    #[test]
+    fn mapped_device()
+    {
+        let registers = Rc::new(RefCell::new(Registers { status: 0x80, ..Registers::default() }));
+
+        let mut m = Memory::new();
+        m.map(0x2000..=0x3FFF, 0x0007, Box::new(registers.clone()));
+
+        assert_eq!(0x80, m.peek(0x2008));
+        assert_eq!(0x80, m.read(0x3FF8));
+        assert_eq!(0x00, m.read(0x2000));
+
+        m.write(0x2001, 0x11);
+        m.write(0x2009, 0x22);
+
+        assert_eq!(vec![(0, 0x11), (1, 0x22)], registers.borrow().data);
+        assert_eq!(0x00, m.memory[0x2001]);
+
+        // The data register is write-only
+        assert_eq!(0x22, m.read(0x2001));
+    }
+
+    #[test]
+    fn mapped_device_precedence()
+    {
+        let mut m = Memory::flat();
+        m.write(0x8000, 0x01);
+
+        m.map(0x8000..=0xFFFF, 0xFFFF, Box::new(Registers { status: 0x42, ..Registers::default() }));
+
+        assert_eq!(0x42, m.read(0x8000));
+        assert_eq!(Ok(()), m.try_write(0x8001, 0x00));
+
+        m.add_watchpoint(0x8000, Access::Read);
+        m.read(0x8000);
+
+        assert_eq!(Some(WatchHit { addr: 0x8000, access: Access::Read, value: 0x00 }), m.take_watch_hit());
+    }
+
+    #[test]
+    fn open_bus()
+    {
+        let mut m = Memory::new();
+
+        m.write(0x0010, 0x42);
+        assert_eq!(0x42, m.read(0x4018));
+        assert_eq!(0x42, m.read(0x5FFF));
+
+        m.memory[0x0020] = 0x24;
+        m.read(0x0020);
+        assert_eq!(0x24, m.peek(0x4000));
+
+        // Without a cartridge
+        assert_eq!(0x24, m.read(0x8000));
+    }
+
+    #[test]
+    fn open_bus_ppu_registers()
+    {
+        let mut m = Memory::new();
+
+        m.write(0x2000, 0x80);
+        m.memory[0x2002] = 0x1F;
+
+        assert_eq!(0x1F, m.read(0x2002));
+        assert_eq!(0x1F, m.read(0x2000));
+        assert_eq!(0x1F, m.read(0x2006));
+    }
+
+    #[test]
+    fn peek_keeps_bus_latch()
+    {
+        let mut m = Memory::new();
+
+        m.write(0x0010, 0x42);
+        m.memory[0x0020] = 0x24;
+
+        assert_eq!(0x24, m.peek(0x0020));
+        assert_eq!(0x42, m.read(0x4018));
+    }
+
+    #[test]
+    fn flat_has_no_open_bus()
+    {
+        let mut m = Memory::flat();
+
+        m.write(0x0010, 0x42);
+
+        assert_eq!(0x00, m.read(0x4018));
+        assert_eq!(0x00, m.read(0x2000));
+    }
+}
@@ -6,8 +6,13 @@ mod test_helpers;
 
 // Import Ops
 mod adc;
+mod alr;
+mod anc;
 mod and;
+mod ane;
+mod arr;
 mod asl;
+mod axs;
 mod branch;
 mod bit;
 mod brk;
@@ -15,30 +20,43 @@ mod flags;
 mod cmp;
 mod cpx;
 mod cpy;
+mod dcp;
 mod dec;
 mod dex;
 mod dey;
 mod eor;
 mod inc;
+mod interrupt;
 mod inx;
 mod iny;
+mod isb;
+mod jam;
 mod jmp;
 mod jsr;
+mod las;
+mod lax;
 mod lda;
 mod ldx;
 mod ldy;
 mod lsr;
+mod lxa;
 mod nop;
 mod ora;
 mod pha;
 mod php;
 mod pla;
 mod plp;
+mod rla;
 mod rol;
 mod ror;
+mod rra;
 mod rti;
 mod rts;
+mod sax;
 mod sbc;
+mod sha;
+mod slo;
+mod sre;
 mod sta;
 mod stx;
 mod sty;
@@ -49,11 +67,13 @@ mod txa;
 mod txs;
 mod tya;
 
-use std::{collections::HashMap};
 use std::fmt::Debug;
-use super::{CpuRegisters, Memory, STACK_START};
+use super::{CpuRegisters, Bus, STACK_START};
+use super::error::ErrorKind;
 
-#[derive(Debug, Clone, Copy)]
+pub use self::interrupt::{Nmi, Irq};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AddressingMode
 {
     Implicit,
@@ -71,37 +91,107 @@ pub enum AddressingMode
     IndirectY
 }
 
-pub type OpcodeMap = HashMap<u8, Opcode>;
+pub type OpcodeTable = [Option<OpcodeEntry>; 256];
 
-#[derive(Debug)]
-pub struct Opcode
+#[derive(Debug, Clone, Copy)]
+pub struct OpcodeEntry
 {
-    pub opcode: u8,
-    pub mode:   AddressingMode,
-    pub op:     Box<dyn Op>
+    pub name:         &'static str,
+    pub mode:         AddressingMode,
+    pub cycles:       u8, // Base cycle count, without any penalty
+    pub official:     bool,
+    pub call:         fn(AddressingMode, &mut CpuRegisters, &mut dyn Bus) -> Result<(), ErrorKind>,
+    pub extra_cycles: fn(AddressingMode, &CpuRegisters, &dyn Bus) -> u8,
+    pub sets_pc:      fn() -> bool,
 }
 
 pub trait Op : Debug
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory);
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>;
+
+    // Cycles on top of the opcode base count (page crossing, taken branch), evaluated before `call`.
+    // Only peeks at memory, the bus accesses are the ones of `call`
+    fn extra_cycles(&self, _: AddressingMode, _: &CpuRegisters, _: &dyn Bus) -> u8
+    {
+        0
+    }
 
-    fn operand_addr(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &Memory) -> u16
+    // Jumps, branches and returns set the PC themselves, even when it lands on the operand,
+    // the CPU moves over the operand of the others
+    fn sets_pc(&self) -> bool
     {
-        match mode
+        false
+    }
+
+    fn page_crossed(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> bool
+    {
+        let (base, index) = match mode
+        {
+            AddressingMode::AbsoluteX => (memory.peek_u16(*registers.pc), *registers.x),
+            AddressingMode::AbsoluteY => (memory.peek_u16(*registers.pc), *registers.y),
+            AddressingMode::IndirectY => {
+                let lsb_addr = memory.peek(*registers.pc);
+
+                let lsb = memory.peek(lsb_addr as u16);
+                let msb = memory.peek(lsb_addr.wrapping_add(1) as u16);
+
+                (u16::from_le_bytes([lsb, msb]), *registers.y)
+            },
+            _ => return false
+        };
+
+        base & 0xFF00 != base.wrapping_add(index as u16) & 0xFF00
+    }
+
+    // Address read by the instruction, with the bus accesses of the addressing mode
+    fn operand_addr(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> Result<u16, ErrorKind>
+    {
+        self.effective_addr(mode, registers, memory, false)
+    }
+
+    // Address written by stores and read-modify-write instructions, indexed modes always take the fix-up cycle
+    fn write_addr(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> Result<u16, ErrorKind>
+    {
+        self.effective_addr(mode, registers, memory, true)
+    }
+
+    fn effective_addr(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus, write: bool) -> Result<u16, ErrorKind>
+    {
+        // Indexing a zero page address takes a cycle, spent reading the unindexed address
+        let zero_page_indexed = |index: u8| {
+            let base = memory.read(*registers.pc);
+            memory.read(base as u16);
+
+            base.wrapping_add(index) as u16
+        };
+
+        // The high byte is fixed a cycle after the indexed low byte, reading from the wrong page in between
+        let indexed = |base: u16, index: u8| {
+            let addr = base.wrapping_add(index as u16);
+
+            if write || base & 0xFF00 != addr & 0xFF00
+            {
+                memory.read(base & 0xFF00 | addr & 0x00FF);
+            }
+
+            addr
+        };
+
+        let addr = match mode
         {
             AddressingMode::Immediate => *registers.pc,
             AddressingMode::ZeroPage  => memory.read(*registers.pc) as u16,
-            AddressingMode::ZeroPageX => memory.read(*registers.pc).wrapping_add(*registers.x) as u16,
-            AddressingMode::ZeroPageY => memory.read(*registers.pc).wrapping_add(*registers.y) as u16,
+            AddressingMode::ZeroPageX => zero_page_indexed(*registers.x),
+            AddressingMode::ZeroPageY => zero_page_indexed(*registers.y),
 
             AddressingMode::Absolute  => memory.read_u16(*registers.pc),
-            AddressingMode::AbsoluteX => memory.read_u16(*registers.pc) + *registers.x as u16,
-            AddressingMode::AbsoluteY => memory.read_u16(*registers.pc) + *registers.y as u16,
+            AddressingMode::AbsoluteX => indexed(memory.read_u16(*registers.pc), *registers.x),
+            AddressingMode::AbsoluteY => indexed(memory.read_u16(*registers.pc), *registers.y),
 
             AddressingMode::Indirect => memory.read_u16(memory.read_u16(*registers.pc)),
 
             AddressingMode::IndirectX => {
-                let lsb_addr = memory.read(*registers.pc).wrapping_add(*registers.x);
+                let lsb_addr = zero_page_indexed(*registers.x) as u8;
 
                 let lsb = memory.read(lsb_addr as u16);
                 let msb = memory.read(lsb_addr.wrapping_add(1) as u16);
@@ -115,7 +205,7 @@ pub trait Op : Debug
                 let lsb = memory.read(lsb_addr as u16);
                 let msb = memory.read(lsb_addr.wrapping_add(1) as u16);
 
-                u16::from_le_bytes([lsb, msb]) + *registers.y as u16
+                indexed(u16::from_le_bytes([lsb, msb]), *registers.y)
             },
 
             AddressingMode::Relative => {
@@ -126,250 +216,430 @@ pub trait Op : Debug
                     .wrapping_add(jump_size as u16)
             }
 
-            AddressingMode::Accumulator => panic!("You cannot get operand address for Accumulator addressing mode"),
-            AddressingMode::Implicit => panic!("You cannot get operand address for Implicit addressing mode")
-        }
+            AddressingMode::Accumulator | AddressingMode::Implicit => return Err(ErrorKind::InvalidOperand(mode))
+        };
+
+        Ok(addr)
     }
 
-    fn operand(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &Memory) -> u8
+    fn operand(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> Result<u8, ErrorKind>
     {
-        if let AddressingMode::Accumulator = mode { return *registers.a; }
+        if let AddressingMode::Accumulator = mode { return Ok(*registers.a); }
 
-        memory.read(self.operand_addr(mode, registers, memory))
+        Ok(memory.read(self.operand_addr(mode, registers, memory)?))
     }
 
-    fn stack_push(&self, registers: &mut CpuRegisters, memory: &mut Memory, value: u8)
+    // Read-modify-write instructions read their operand, then write the unmodified value back on the cycle before the result
+    fn read_modify_write(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &mut dyn Bus, modify: &dyn Fn(u8) -> u8) -> Result<(u8, u8), ErrorKind>
     {
-        memory.write(STACK_START + registers.sp.decrement() as u16, value);
+        let addr = self.write_addr(mode, registers, memory)?;
+        let value = memory.read(addr);
+        let result = modify(value);
+
+        self.write_back(memory, addr, value, result)?;
+
+        Ok((value, result))
     }
 
-    fn stack_push_u16(&self, registers: &mut CpuRegisters, memory: &mut Memory, value: u16)
+    fn write_back(&self, memory: &mut dyn Bus, addr: u16, value: u8, result: u8) -> Result<(), ErrorKind>
+    {
+        memory.write(addr, value)?;
+        memory.write(addr, result)
+    }
+
+    fn stack_push(&self, registers: &mut CpuRegisters, memory: &mut dyn Bus, value: u8) -> Result<(), ErrorKind>
+    {
+        memory.write(STACK_START + registers.sp.decrement() as u16, value)
+    }
+
+    fn stack_push_u16(&self, registers: &mut CpuRegisters, memory: &mut dyn Bus, value: u16) -> Result<(), ErrorKind>
     {
         let msb = (value >> 8) as u8;
         let lsb = (value & 0xFF) as u8;
 
         // Push msb first, then lsb
-        self.stack_push(registers, memory, msb);
-        self.stack_push(registers, memory, lsb);
+        self.stack_push(registers, memory, msb)?;
+        self.stack_push(registers, memory, lsb)
     }
 
-    fn stack_pop(&self, registers: &mut CpuRegisters, memory: &Memory) -> u8
+    // Pulls and JSR spend a cycle reading the stack before moving SP
+    fn stack_dummy_read(&self, registers: &CpuRegisters, memory: &dyn Bus)
+    {
+        memory.read(STACK_START + *registers.sp as u16);
+    }
+
+    fn stack_pop(&self, registers: &mut CpuRegisters, memory: &dyn Bus) -> u8
     {
         memory.read(STACK_START + registers.sp.increment() as u16)
     }
 
-    fn stack_pop_u16(&self, registers: &mut CpuRegisters, memory: &Memory) -> u16
+    fn stack_pop_u16(&self, registers: &mut CpuRegisters, memory: &dyn Bus) -> u16
     {
         let lsb = memory.read(STACK_START + registers.sp.increment() as u16);
         let msb = memory.read(STACK_START + registers.sp.increment() as u16);
 
-        ((msb as u16) << 8) as u16 | lsb as u16
+        (msb as u16) << 8 | lsb as u16
     }
 
-    // Mainly used for testing
-    fn stack_peek(&self, registers: &CpuRegisters, memory: &Memory) -> u8
+    // Sequence shared by BRK and hardware interrupts: push PC and P (with `flags`), then jump through `vector`
+    fn interrupt(&self, registers: &mut CpuRegisters, memory: &mut dyn Bus, vector: u16, flags: u8) -> Result<(), ErrorKind>
+    {
+        self.stack_push_u16(registers, memory, *registers.pc)?;
+        self.stack_push(registers, memory, Into::<u8>::into(&registers.p) | flags)?;
+
+        registers.p.set_interrupt_disable(true);
+        registers.pc.set(memory.read_u16(vector));
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn stack_peek(&self, registers: &CpuRegisters, memory: &dyn Bus) -> u8
     {
         memory.read(STACK_START + registers.sp.wrapping_add(1) as u16)
     }
 
-    fn stack_peek_u16(&self, registers: &CpuRegisters, memory: &Memory) -> u16
+    #[cfg(test)]
+    fn stack_peek_u16(&self, registers: &CpuRegisters, memory: &dyn Bus) -> u16
     {
         let lsb = memory.read(STACK_START + registers.sp.wrapping_add(1) as u16);
         let msb = memory.read(STACK_START + registers.sp.wrapping_add(2) as u16);
 
-        ((msb as u16) << 8) as u16 | lsb as u16
+        (msb as u16) << 8 | lsb as u16
     }
-
 }
 
-pub fn opcodes() -> OpcodeMap
+// The second half of a combined unofficial instruction works on the value left by the first half,
+// which the CPU already has: its reads only peek, so the bus sees the accesses once
+struct Peeking<'a>(&'a mut dyn Bus);
+
+impl Bus for Peeking<'_>
 {
-    opcodes!(
-        (0x69, AddressingMode::Immediate, adc::Adc),
-        (0x65, AddressingMode::ZeroPage,  adc::Adc),
-        (0x75, AddressingMode::ZeroPageX, adc::Adc),
-        (0x6D, AddressingMode::Absolute,  adc::Adc),
-        (0x7D, AddressingMode::AbsoluteX, adc::Adc),
-        (0x79, AddressingMode::AbsoluteY, adc::Adc),
-        (0x61, AddressingMode::IndirectX, adc::Adc),
-        (0x71, AddressingMode::IndirectY, adc::Adc),
-
-        (0x29, AddressingMode::Immediate, and::And),
-        (0x25, AddressingMode::ZeroPage,  and::And),
-        (0x35, AddressingMode::ZeroPageX, and::And),
-        (0x2D, AddressingMode::Absolute,  and::And),
-        (0x3D, AddressingMode::AbsoluteX, and::And),
-        (0x39, AddressingMode::AbsoluteY, and::And),
-        (0x21, AddressingMode::IndirectX, and::And),
-        (0x31, AddressingMode::IndirectY, and::And),
-
-        (0x0A, AddressingMode::Accumulator, asl::Asl),
-        (0x06, AddressingMode::ZeroPage,    asl::Asl),
-        (0x16, AddressingMode::ZeroPageX,   asl::Asl),
-        (0x0E, AddressingMode::Absolute,    asl::Asl),
-        (0x1E, AddressingMode::AbsoluteX,   asl::Asl),
-
-        (0x90, AddressingMode::Relative, branch::Bcc),
-        (0xB0, AddressingMode::Relative, branch::Bcs),
-        (0xF0, AddressingMode::Relative, branch::Beq),
-        (0x30, AddressingMode::Relative, branch::Bmi),
-        (0xD0, AddressingMode::Relative, branch::Bne),
-        (0x10, AddressingMode::Relative, branch::Bpl),
-        (0x50, AddressingMode::Relative, branch::Bvc),
-        (0x70, AddressingMode::Relative, branch::Bvs),
-
-        (0x24, AddressingMode::ZeroPage, bit::Bit),
-        (0x2C, AddressingMode::Absolute, bit::Bit),
-
-        (0x00, AddressingMode::Implicit, brk::Brk),
-
-        (0x18, AddressingMode::Implicit, flags::Clc),
-        (0xD8, AddressingMode::Implicit, flags::Cld),
-        (0x58, AddressingMode::Implicit, flags::Cli),
-        (0xB8, AddressingMode::Implicit, flags::Clv),
-        (0x38, AddressingMode::Implicit, flags::Sec),
-        (0xF8, AddressingMode::Implicit, flags::Sed),
-        (0x78, AddressingMode::Implicit, flags::Sei),
-
-        (0xC9, AddressingMode::Immediate, cmp::Cmp),
-        (0xC5, AddressingMode::ZeroPage,  cmp::Cmp),
-        (0xD5, AddressingMode::ZeroPageX, cmp::Cmp),
-        (0xCD, AddressingMode::Absolute,  cmp::Cmp),
-        (0xDD, AddressingMode::AbsoluteX, cmp::Cmp),
-        (0xD9, AddressingMode::AbsoluteY, cmp::Cmp),
-        (0xC1, AddressingMode::IndirectX, cmp::Cmp),
-        (0xD1, AddressingMode::IndirectY, cmp::Cmp),
-
-        (0xE0, AddressingMode::Immediate, cpx::Cpx),
-        (0xE4, AddressingMode::ZeroPage,  cpx::Cpx),
-        (0xEC, AddressingMode::Absolute,  cpx::Cpx),
-
-        (0xC0, AddressingMode::Immediate, cpy::Cpy),
-        (0xC4, AddressingMode::ZeroPage,  cpy::Cpy),
-        (0xCC, AddressingMode::Absolute,  cpy::Cpy),
-
-        (0xC6, AddressingMode::ZeroPage,  dec::Dec),
-        (0xD6, AddressingMode::ZeroPageX, dec::Dec),
-        (0xCE, AddressingMode::Absolute,  dec::Dec),
-        (0xDE, AddressingMode::AbsoluteX, dec::Dec),
-
-        (0xCA, AddressingMode::Implicit, dex::Dex),
-        (0x88, AddressingMode::Implicit, dey::Dey),
-
-        (0x49, AddressingMode::Immediate, eor::Eor),
-        (0x45, AddressingMode::ZeroPage,  eor::Eor),
-        (0x55, AddressingMode::ZeroPageX, eor::Eor),
-        (0x4D, AddressingMode::Absolute,  eor::Eor),
-        (0x5D, AddressingMode::AbsoluteX, eor::Eor),
-        (0x59, AddressingMode::AbsoluteY, eor::Eor),
-        (0x41, AddressingMode::IndirectX, eor::Eor),
-        (0x51, AddressingMode::IndirectY, eor::Eor),
-
-        (0xE6, AddressingMode::ZeroPage,  inc::Inc),
-        (0xF6, AddressingMode::ZeroPageX, inc::Inc),
-        (0xEE, AddressingMode::Absolute,  inc::Inc),
-        (0xFE, AddressingMode::AbsoluteX, inc::Inc),
-
-        (0xE8, AddressingMode::Implicit, inx::Inx),
-        (0xC8, AddressingMode::Implicit, iny::Iny),
-
-        (0x4C, AddressingMode::Absolute, jmp::Jmp),
-        (0x6C, AddressingMode::Indirect, jmp::Jmp),
-
-        (0x20, AddressingMode::Absolute, jsr::Jsr),
-
-        (0xA9, AddressingMode::Immediate, lda::Lda),
-        (0xA5, AddressingMode::ZeroPage,  lda::Lda),
-        (0xB5, AddressingMode::ZeroPageX, lda::Lda),
-        (0xAD, AddressingMode::Absolute,  lda::Lda),
-        (0xBD, AddressingMode::AbsoluteX, lda::Lda),
-        (0xB9, AddressingMode::AbsoluteY, lda::Lda),
-        (0xA1, AddressingMode::IndirectX, lda::Lda),
-        (0xB1, AddressingMode::IndirectY, lda::Lda),
-
-        (0xA2, AddressingMode::Immediate, ldx::Ldx),
-        (0xA6, AddressingMode::ZeroPage,  ldx::Ldx),
-        (0xB6, AddressingMode::ZeroPageY, ldx::Ldx),
-        (0xAE, AddressingMode::Absolute,  ldx::Ldx),
-        (0xBE, AddressingMode::AbsoluteY, ldx::Ldx),
-
-        (0xA0, AddressingMode::Immediate, ldy::Ldy),
-        (0xA4, AddressingMode::ZeroPage,  ldy::Ldy),
-        (0xB4, AddressingMode::ZeroPageX, ldy::Ldy),
-        (0xAC, AddressingMode::Absolute,  ldy::Ldy),
-        (0xBC, AddressingMode::AbsoluteX, ldy::Ldy),
-
-        (0x4A, AddressingMode::Accumulator, lsr::Lsr),
-        (0x46, AddressingMode::ZeroPage,    lsr::Lsr),
-        (0x56, AddressingMode::ZeroPageX,   lsr::Lsr),
-        (0x4E, AddressingMode::Absolute,    lsr::Lsr),
-        (0x5E, AddressingMode::AbsoluteX,   lsr::Lsr),
-
-        (0xEA, AddressingMode::Implicit, nop::Nop),
-
-        (0x09, AddressingMode::Immediate, ora::Ora),
-        (0x05, AddressingMode::ZeroPage,  ora::Ora),
-        (0x15, AddressingMode::ZeroPageX, ora::Ora),
-        (0x0D, AddressingMode::Absolute,  ora::Ora),
-        (0x1D, AddressingMode::AbsoluteX, ora::Ora),
-        (0x19, AddressingMode::AbsoluteY, ora::Ora),
-        (0x01, AddressingMode::IndirectX, ora::Ora),
-        (0x11, AddressingMode::IndirectY, ora::Ora),
-
-        (0x48, AddressingMode::Implicit, pha::Pha),
-        (0x08, AddressingMode::Implicit, php::Php),
-        (0x68, AddressingMode::Implicit, pla::Pla),
-        (0x28, AddressingMode::Implicit, plp::Plp),
-
-        (0x2A, AddressingMode::Accumulator, rol::Rol),
-        (0x26, AddressingMode::ZeroPage,    rol::Rol),
-        (0x36, AddressingMode::ZeroPageX,   rol::Rol),
-        (0x2E, AddressingMode::Absolute,    rol::Rol),
-        (0x3E, AddressingMode::AbsoluteX,   rol::Rol),
-
-        (0x6A, AddressingMode::Accumulator, ror::Ror),
-        (0x66, AddressingMode::ZeroPage,    ror::Ror),
-        (0x76, AddressingMode::ZeroPageX,   ror::Ror),
-        (0x6E, AddressingMode::Absolute,    ror::Ror),
-        (0x7E, AddressingMode::AbsoluteX,   ror::Ror),
-
-        (0x40, AddressingMode::Implicit, rti::Rti),
-        (0x60, AddressingMode::Implicit, rts::Rts),
-
-        (0xE9, AddressingMode::Immediate, sbc::Sbc),
-        (0xE5, AddressingMode::ZeroPage,  sbc::Sbc),
-        (0xF5, AddressingMode::ZeroPageX, sbc::Sbc),
-        (0xED, AddressingMode::Absolute,  sbc::Sbc),
-        (0xFD, AddressingMode::AbsoluteX, sbc::Sbc),
-        (0xF9, AddressingMode::AbsoluteY, sbc::Sbc),
-        (0xE1, AddressingMode::IndirectX, sbc::Sbc),
-        (0xF1, AddressingMode::IndirectY, sbc::Sbc),
-
-        (0x85, AddressingMode::ZeroPage,  sta::Sta),
-        (0x95, AddressingMode::ZeroPageX, sta::Sta),
-        (0x8D, AddressingMode::Absolute,  sta::Sta),
-        (0x9D, AddressingMode::AbsoluteX, sta::Sta),
-        (0x99, AddressingMode::AbsoluteY, sta::Sta),
-        (0x81, AddressingMode::IndirectX, sta::Sta),
-        (0x91, AddressingMode::IndirectY, sta::Sta),
-
-        (0x86, AddressingMode::ZeroPage,  stx::Stx),
-        (0x96, AddressingMode::ZeroPageY, stx::Stx),
-        (0x8E, AddressingMode::Absolute,  stx::Stx),
-
-        (0x84, AddressingMode::ZeroPage,  sty::Sty),
-        (0x94, AddressingMode::ZeroPageX, sty::Sty),
-        (0x8C, AddressingMode::Absolute,  sty::Sty),
-
-        (0xAA, AddressingMode::Implicit, tax::Tax),
-        (0xA8, AddressingMode::Implicit, tay::Tay),
-        (0xBA, AddressingMode::Implicit, tsx::Tsx),
-        (0x8A, AddressingMode::Implicit, txa::Txa),
-        (0x9A, AddressingMode::Implicit, txs::Txs),
-        (0x98, AddressingMode::Implicit, tya::Tya)
-    )
+    fn read(&self, addr: u16) -> u8
+    {
+        self.0.peek(addr)
+    }
+
+    fn write(&mut self, addr: u16, value: u8) -> Result<(), ErrorKind>
+    {
+        self.0.write(addr, value)
+    }
+
+    fn peek(&self, addr: u16) -> u8
+    {
+        self.0.peek(addr)
+    }
 }
 
+// Decoding table indexed by opcode, built at compile time
+pub static OPCODES: OpcodeTable = opcodes!(
+    official: [
+        (0x69, AddressingMode::Immediate, adc::Adc, 2),
+        (0x65, AddressingMode::ZeroPage,  adc::Adc, 3),
+        (0x75, AddressingMode::ZeroPageX, adc::Adc, 4),
+        (0x6D, AddressingMode::Absolute,  adc::Adc, 4),
+        (0x7D, AddressingMode::AbsoluteX, adc::Adc, 4),
+        (0x79, AddressingMode::AbsoluteY, adc::Adc, 4),
+        (0x61, AddressingMode::IndirectX, adc::Adc, 6),
+        (0x71, AddressingMode::IndirectY, adc::Adc, 5),
+
+        (0x29, AddressingMode::Immediate, and::And, 2),
+        (0x25, AddressingMode::ZeroPage,  and::And, 3),
+        (0x35, AddressingMode::ZeroPageX, and::And, 4),
+        (0x2D, AddressingMode::Absolute,  and::And, 4),
+        (0x3D, AddressingMode::AbsoluteX, and::And, 4),
+        (0x39, AddressingMode::AbsoluteY, and::And, 4),
+        (0x21, AddressingMode::IndirectX, and::And, 6),
+        (0x31, AddressingMode::IndirectY, and::And, 5),
+
+        (0x0A, AddressingMode::Accumulator, asl::Asl, 2),
+        (0x06, AddressingMode::ZeroPage,    asl::Asl, 5),
+        (0x16, AddressingMode::ZeroPageX,   asl::Asl, 6),
+        (0x0E, AddressingMode::Absolute,    asl::Asl, 6),
+        (0x1E, AddressingMode::AbsoluteX,   asl::Asl, 7),
+
+        (0x90, AddressingMode::Relative, branch::Bcc, 2),
+        (0xB0, AddressingMode::Relative, branch::Bcs, 2),
+        (0xF0, AddressingMode::Relative, branch::Beq, 2),
+        (0x30, AddressingMode::Relative, branch::Bmi, 2),
+        (0xD0, AddressingMode::Relative, branch::Bne, 2),
+        (0x10, AddressingMode::Relative, branch::Bpl, 2),
+        (0x50, AddressingMode::Relative, branch::Bvc, 2),
+        (0x70, AddressingMode::Relative, branch::Bvs, 2),
+
+        (0x24, AddressingMode::ZeroPage, bit::Bit, 3),
+        (0x2C, AddressingMode::Absolute, bit::Bit, 4),
+
+        (0x00, AddressingMode::Implicit, brk::Brk, 7),
+
+        (0x18, AddressingMode::Implicit, flags::Clc, 2),
+        (0xD8, AddressingMode::Implicit, flags::Cld, 2),
+        (0x58, AddressingMode::Implicit, flags::Cli, 2),
+        (0xB8, AddressingMode::Implicit, flags::Clv, 2),
+        (0x38, AddressingMode::Implicit, flags::Sec, 2),
+        (0xF8, AddressingMode::Implicit, flags::Sed, 2),
+        (0x78, AddressingMode::Implicit, flags::Sei, 2),
+
+        (0xC9, AddressingMode::Immediate, cmp::Cmp, 2),
+        (0xC5, AddressingMode::ZeroPage,  cmp::Cmp, 3),
+        (0xD5, AddressingMode::ZeroPageX, cmp::Cmp, 4),
+        (0xCD, AddressingMode::Absolute,  cmp::Cmp, 4),
+        (0xDD, AddressingMode::AbsoluteX, cmp::Cmp, 4),
+        (0xD9, AddressingMode::AbsoluteY, cmp::Cmp, 4),
+        (0xC1, AddressingMode::IndirectX, cmp::Cmp, 6),
+        (0xD1, AddressingMode::IndirectY, cmp::Cmp, 5),
+
+        (0xE0, AddressingMode::Immediate, cpx::Cpx, 2),
+        (0xE4, AddressingMode::ZeroPage,  cpx::Cpx, 3),
+        (0xEC, AddressingMode::Absolute,  cpx::Cpx, 4),
+
+        (0xC0, AddressingMode::Immediate, cpy::Cpy, 2),
+        (0xC4, AddressingMode::ZeroPage,  cpy::Cpy, 3),
+        (0xCC, AddressingMode::Absolute,  cpy::Cpy, 4),
+
+        (0xC6, AddressingMode::ZeroPage,  dec::Dec, 5),
+        (0xD6, AddressingMode::ZeroPageX, dec::Dec, 6),
+        (0xCE, AddressingMode::Absolute,  dec::Dec, 6),
+        (0xDE, AddressingMode::AbsoluteX, dec::Dec, 7),
+
+        (0xCA, AddressingMode::Implicit, dex::Dex, 2),
+        (0x88, AddressingMode::Implicit, dey::Dey, 2),
+
+        (0x49, AddressingMode::Immediate, eor::Eor, 2),
+        (0x45, AddressingMode::ZeroPage,  eor::Eor, 3),
+        (0x55, AddressingMode::ZeroPageX, eor::Eor, 4),
+        (0x4D, AddressingMode::Absolute,  eor::Eor, 4),
+        (0x5D, AddressingMode::AbsoluteX, eor::Eor, 4),
+        (0x59, AddressingMode::AbsoluteY, eor::Eor, 4),
+        (0x41, AddressingMode::IndirectX, eor::Eor, 6),
+        (0x51, AddressingMode::IndirectY, eor::Eor, 5),
+
+        (0xE6, AddressingMode::ZeroPage,  inc::Inc, 5),
+        (0xF6, AddressingMode::ZeroPageX, inc::Inc, 6),
+        (0xEE, AddressingMode::Absolute,  inc::Inc, 6),
+        (0xFE, AddressingMode::AbsoluteX, inc::Inc, 7),
+
+        (0xE8, AddressingMode::Implicit, inx::Inx, 2),
+        (0xC8, AddressingMode::Implicit, iny::Iny, 2),
+
+        (0x4C, AddressingMode::Absolute, jmp::Jmp, 3),
+        (0x6C, AddressingMode::Indirect, jmp::Jmp, 5),
+
+        (0x20, AddressingMode::Absolute, jsr::Jsr, 6),
+
+        (0xA9, AddressingMode::Immediate, lda::Lda, 2),
+        (0xA5, AddressingMode::ZeroPage,  lda::Lda, 3),
+        (0xB5, AddressingMode::ZeroPageX, lda::Lda, 4),
+        (0xAD, AddressingMode::Absolute,  lda::Lda, 4),
+        (0xBD, AddressingMode::AbsoluteX, lda::Lda, 4),
+        (0xB9, AddressingMode::AbsoluteY, lda::Lda, 4),
+        (0xA1, AddressingMode::IndirectX, lda::Lda, 6),
+        (0xB1, AddressingMode::IndirectY, lda::Lda, 5),
+
+        (0xA2, AddressingMode::Immediate, ldx::Ldx, 2),
+        (0xA6, AddressingMode::ZeroPage,  ldx::Ldx, 3),
+        (0xB6, AddressingMode::ZeroPageY, ldx::Ldx, 4),
+        (0xAE, AddressingMode::Absolute,  ldx::Ldx, 4),
+        (0xBE, AddressingMode::AbsoluteY, ldx::Ldx, 4),
+
+        (0xA0, AddressingMode::Immediate, ldy::Ldy, 2),
+        (0xA4, AddressingMode::ZeroPage,  ldy::Ldy, 3),
+        (0xB4, AddressingMode::ZeroPageX, ldy::Ldy, 4),
+        (0xAC, AddressingMode::Absolute,  ldy::Ldy, 4),
+        (0xBC, AddressingMode::AbsoluteX, ldy::Ldy, 4),
+
+        (0x4A, AddressingMode::Accumulator, lsr::Lsr, 2),
+        (0x46, AddressingMode::ZeroPage,    lsr::Lsr, 5),
+        (0x56, AddressingMode::ZeroPageX,   lsr::Lsr, 6),
+        (0x4E, AddressingMode::Absolute,    lsr::Lsr, 6),
+        (0x5E, AddressingMode::AbsoluteX,   lsr::Lsr, 7),
+
+        (0xEA, AddressingMode::Implicit, nop::Nop, 2),
+
+        (0x09, AddressingMode::Immediate, ora::Ora, 2),
+        (0x05, AddressingMode::ZeroPage,  ora::Ora, 3),
+        (0x15, AddressingMode::ZeroPageX, ora::Ora, 4),
+        (0x0D, AddressingMode::Absolute,  ora::Ora, 4),
+        (0x1D, AddressingMode::AbsoluteX, ora::Ora, 4),
+        (0x19, AddressingMode::AbsoluteY, ora::Ora, 4),
+        (0x01, AddressingMode::IndirectX, ora::Ora, 6),
+        (0x11, AddressingMode::IndirectY, ora::Ora, 5),
+
+        (0x48, AddressingMode::Implicit, pha::Pha, 3),
+        (0x08, AddressingMode::Implicit, php::Php, 3),
+        (0x68, AddressingMode::Implicit, pla::Pla, 4),
+        (0x28, AddressingMode::Implicit, plp::Plp, 4),
+
+        (0x2A, AddressingMode::Accumulator, rol::Rol, 2),
+        (0x26, AddressingMode::ZeroPage,    rol::Rol, 5),
+        (0x36, AddressingMode::ZeroPageX,   rol::Rol, 6),
+        (0x2E, AddressingMode::Absolute,    rol::Rol, 6),
+        (0x3E, AddressingMode::AbsoluteX,   rol::Rol, 7),
+
+        (0x6A, AddressingMode::Accumulator, ror::Ror, 2),
+        (0x66, AddressingMode::ZeroPage,    ror::Ror, 5),
+        (0x76, AddressingMode::ZeroPageX,   ror::Ror, 6),
+        (0x6E, AddressingMode::Absolute,    ror::Ror, 6),
+        (0x7E, AddressingMode::AbsoluteX,   ror::Ror, 7),
+
+        (0x40, AddressingMode::Implicit, rti::Rti, 6),
+        (0x60, AddressingMode::Implicit, rts::Rts, 6),
+
+        (0xE9, AddressingMode::Immediate, sbc::Sbc, 2),
+        (0xE5, AddressingMode::ZeroPage,  sbc::Sbc, 3),
+        (0xF5, AddressingMode::ZeroPageX, sbc::Sbc, 4),
+        (0xED, AddressingMode::Absolute,  sbc::Sbc, 4),
+        (0xFD, AddressingMode::AbsoluteX, sbc::Sbc, 4),
+        (0xF9, AddressingMode::AbsoluteY, sbc::Sbc, 4),
+        (0xE1, AddressingMode::IndirectX, sbc::Sbc, 6),
+        (0xF1, AddressingMode::IndirectY, sbc::Sbc, 5),
+
+        (0x85, AddressingMode::ZeroPage,  sta::Sta, 3),
+        (0x95, AddressingMode::ZeroPageX, sta::Sta, 4),
+        (0x8D, AddressingMode::Absolute,  sta::Sta, 4),
+        (0x9D, AddressingMode::AbsoluteX, sta::Sta, 5),
+        (0x99, AddressingMode::AbsoluteY, sta::Sta, 5),
+        (0x81, AddressingMode::IndirectX, sta::Sta, 6),
+        (0x91, AddressingMode::IndirectY, sta::Sta, 6),
+
+        (0x86, AddressingMode::ZeroPage,  stx::Stx, 3),
+        (0x96, AddressingMode::ZeroPageY, stx::Stx, 4),
+        (0x8E, AddressingMode::Absolute,  stx::Stx, 4),
+
+        (0x84, AddressingMode::ZeroPage,  sty::Sty, 3),
+        (0x94, AddressingMode::ZeroPageX, sty::Sty, 4),
+        (0x8C, AddressingMode::Absolute,  sty::Sty, 4),
+
+        (0xAA, AddressingMode::Implicit, tax::Tax, 2),
+        (0xA8, AddressingMode::Implicit, tay::Tay, 2),
+        (0xBA, AddressingMode::Implicit, tsx::Tsx, 2),
+        (0x8A, AddressingMode::Implicit, txa::Txa, 2),
+        (0x9A, AddressingMode::Implicit, txs::Txs, 2),
+        (0x98, AddressingMode::Implicit, tya::Tya, 2)
+    ],
+    unofficial: [
+        (0x4B, AddressingMode::Immediate, alr::Alr, 2),
+        (0x0B, AddressingMode::Immediate, anc::Anc, 2),
+        (0x2B, AddressingMode::Immediate, anc::Anc, 2),
+        (0x8B, AddressingMode::Immediate, ane::Ane, 2),
+        (0x6B, AddressingMode::Immediate, arr::Arr, 2),
+        (0xCB, AddressingMode::Immediate, axs::Axs, 2),
+
+        (0xC7, AddressingMode::ZeroPage,  dcp::Dcp, 5),
+        (0xD7, AddressingMode::ZeroPageX, dcp::Dcp, 6),
+        (0xCF, AddressingMode::Absolute,  dcp::Dcp, 6),
+        (0xDF, AddressingMode::AbsoluteX, dcp::Dcp, 7),
+        (0xDB, AddressingMode::AbsoluteY, dcp::Dcp, 7),
+        (0xC3, AddressingMode::IndirectX, dcp::Dcp, 8),
+        (0xD3, AddressingMode::IndirectY, dcp::Dcp, 8),
+
+        (0xE7, AddressingMode::ZeroPage,  isb::Isb, 5),
+        (0xF7, AddressingMode::ZeroPageX, isb::Isb, 6),
+        (0xEF, AddressingMode::Absolute,  isb::Isb, 6),
+        (0xFF, AddressingMode::AbsoluteX, isb::Isb, 7),
+        (0xFB, AddressingMode::AbsoluteY, isb::Isb, 7),
+        (0xE3, AddressingMode::IndirectX, isb::Isb, 8),
+        (0xF3, AddressingMode::IndirectY, isb::Isb, 8),
+
+        (0x02, AddressingMode::Implicit, jam::Jam, 2),
+        (0x12, AddressingMode::Implicit, jam::Jam, 2),
+        (0x22, AddressingMode::Implicit, jam::Jam, 2),
+        (0x32, AddressingMode::Implicit, jam::Jam, 2),
+        (0x42, AddressingMode::Implicit, jam::Jam, 2),
+        (0x52, AddressingMode::Implicit, jam::Jam, 2),
+        (0x62, AddressingMode::Implicit, jam::Jam, 2),
+        (0x72, AddressingMode::Implicit, jam::Jam, 2),
+        (0x92, AddressingMode::Implicit, jam::Jam, 2),
+        (0xB2, AddressingMode::Implicit, jam::Jam, 2),
+        (0xD2, AddressingMode::Implicit, jam::Jam, 2),
+        (0xF2, AddressingMode::Implicit, jam::Jam, 2),
+
+        (0xBB, AddressingMode::AbsoluteY, las::Las, 4),
+
+        (0xA7, AddressingMode::ZeroPage,  lax::Lax, 3),
+        (0xB7, AddressingMode::ZeroPageY, lax::Lax, 4),
+        (0xAF, AddressingMode::Absolute,  lax::Lax, 4),
+        (0xBF, AddressingMode::AbsoluteY, lax::Lax, 4),
+        (0xA3, AddressingMode::IndirectX, lax::Lax, 6),
+        (0xB3, AddressingMode::IndirectY, lax::Lax, 5),
+
+        (0xAB, AddressingMode::Immediate, lxa::Lxa, 2),
+
+        (0x1A, AddressingMode::Implicit,  nop::Nop, 2),
+        (0x3A, AddressingMode::Implicit,  nop::Nop, 2),
+        (0x5A, AddressingMode::Implicit,  nop::Nop, 2),
+        (0x7A, AddressingMode::Implicit,  nop::Nop, 2),
+        (0xDA, AddressingMode::Implicit,  nop::Nop, 2),
+        (0xFA, AddressingMode::Implicit,  nop::Nop, 2),
+        (0x80, AddressingMode::Immediate, nop::Nop, 2),
+        (0x82, AddressingMode::Immediate, nop::Nop, 2),
+        (0x89, AddressingMode::Immediate, nop::Nop, 2),
+        (0xC2, AddressingMode::Immediate, nop::Nop, 2),
+        (0xE2, AddressingMode::Immediate, nop::Nop, 2),
+        (0x04, AddressingMode::ZeroPage,  nop::Nop, 3),
+        (0x44, AddressingMode::ZeroPage,  nop::Nop, 3),
+        (0x64, AddressingMode::ZeroPage,  nop::Nop, 3),
+        (0x14, AddressingMode::ZeroPageX, nop::Nop, 4),
+        (0x34, AddressingMode::ZeroPageX, nop::Nop, 4),
+        (0x54, AddressingMode::ZeroPageX, nop::Nop, 4),
+        (0x74, AddressingMode::ZeroPageX, nop::Nop, 4),
+        (0xD4, AddressingMode::ZeroPageX, nop::Nop, 4),
+        (0xF4, AddressingMode::ZeroPageX, nop::Nop, 4),
+        (0x0C, AddressingMode::Absolute,  nop::Nop, 4),
+        (0x1C, AddressingMode::AbsoluteX, nop::Nop, 4),
+        (0x3C, AddressingMode::AbsoluteX, nop::Nop, 4),
+        (0x5C, AddressingMode::AbsoluteX, nop::Nop, 4),
+        (0x7C, AddressingMode::AbsoluteX, nop::Nop, 4),
+        (0xDC, AddressingMode::AbsoluteX, nop::Nop, 4),
+        (0xFC, AddressingMode::AbsoluteX, nop::Nop, 4),
+
+        (0x27, AddressingMode::ZeroPage,  rla::Rla, 5),
+        (0x37, AddressingMode::ZeroPageX, rla::Rla, 6),
+        (0x2F, AddressingMode::Absolute,  rla::Rla, 6),
+        (0x3F, AddressingMode::AbsoluteX, rla::Rla, 7),
+        (0x3B, AddressingMode::AbsoluteY, rla::Rla, 7),
+        (0x23, AddressingMode::IndirectX, rla::Rla, 8),
+        (0x33, AddressingMode::IndirectY, rla::Rla, 8),
+
+        (0x67, AddressingMode::ZeroPage,  rra::Rra, 5),
+        (0x77, AddressingMode::ZeroPageX, rra::Rra, 6),
+        (0x6F, AddressingMode::Absolute,  rra::Rra, 6),
+        (0x7F, AddressingMode::AbsoluteX, rra::Rra, 7),
+        (0x7B, AddressingMode::AbsoluteY, rra::Rra, 7),
+        (0x63, AddressingMode::IndirectX, rra::Rra, 8),
+        (0x73, AddressingMode::IndirectY, rra::Rra, 8),
+
+        (0x87, AddressingMode::ZeroPage,  sax::Sax, 3),
+        (0x97, AddressingMode::ZeroPageY, sax::Sax, 4),
+        (0x8F, AddressingMode::Absolute,  sax::Sax, 4),
+        (0x83, AddressingMode::IndirectX, sax::Sax, 6),
+
+        (0xEB, AddressingMode::Immediate, sbc::Sbc, 2),
+
+        (0x93, AddressingMode::IndirectY, sha::Sha, 6),
+        (0x9F, AddressingMode::AbsoluteY, sha::Sha, 5),
+        (0x9E, AddressingMode::AbsoluteY, sha::Shx, 5),
+        (0x9C, AddressingMode::AbsoluteX, sha::Shy, 5),
+        (0x9B, AddressingMode::AbsoluteY, sha::Tas, 5),
+
+        (0x07, AddressingMode::ZeroPage,  slo::Slo, 5),
+        (0x17, AddressingMode::ZeroPageX, slo::Slo, 6),
+        (0x0F, AddressingMode::Absolute,  slo::Slo, 6),
+        (0x1F, AddressingMode::AbsoluteX, slo::Slo, 7),
+        (0x1B, AddressingMode::AbsoluteY, slo::Slo, 7),
+        (0x03, AddressingMode::IndirectX, slo::Slo, 8),
+        (0x13, AddressingMode::IndirectY, slo::Slo, 8),
+
+        (0x47, AddressingMode::ZeroPage,  sre::Sre, 5),
+        (0x57, AddressingMode::ZeroPageX, sre::Sre, 6),
+        (0x4F, AddressingMode::Absolute,  sre::Sre, 6),
+        (0x5F, AddressingMode::AbsoluteX, sre::Sre, 7),
+        (0x5B, AddressingMode::AbsoluteY, sre::Sre, 7),
+        (0x43, AddressingMode::IndirectX, sre::Sre, 8),
+        (0x53, AddressingMode::IndirectY, sre::Sre, 8)
+    ]
+);
+
 pub fn opcode_length(mode: AddressingMode) -> u8
 {
     match mode
@@ -393,23 +663,26 @@ mod tests
     op!(DummyOp);
     impl Op for DummyOp
     {
-        fn call(&self, _: AddressingMode, _: &mut CpuRegisters, _: &mut Memory) {}
+        fn call(&self, _: AddressingMode, _: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind> { Ok(()) }
     }
 
     #[test]
-    #[should_panic(expected = "You cannot get operand address for Implicit addressing mode")]
     fn implicit_operand_addr()
     {
         let (op, r, m) = test_op(DummyOp);
-        op.operand_addr(AddressingMode::Implicit, &r, &m);
+
+        assert_eq!(
+            Err(ErrorKind::InvalidOperand(AddressingMode::Implicit)),
+            op.operand_addr(AddressingMode::Implicit, &r, &m)
+        );
     }
 
     #[test]
-    #[should_panic]
     fn implicit_operand()
     {
         let (op, r, m) = test_op(DummyOp);
-        op.operand(AddressingMode::Implicit, &r, &m);
+
+        assert!(op.operand(AddressingMode::Implicit, &r, &m).is_err());
     }
 
     #[test]
@@ -419,16 +692,18 @@ mod tests
 
         r.a.set(0x50);
 
-        assert_eq!(0x50, op.operand(AddressingMode::Accumulator, &r, &m));
+        assert_eq!(0x50, op.operand(AddressingMode::Accumulator, &r, &m).unwrap());
     }
 
     #[test]
-    #[should_panic(expected = "You cannot get operand address for Accumulator addressing mode")]
     fn accumulator_operand_addr()
     {
         let (op, r, m) = test_op(DummyOp);
 
-        op.operand_addr(AddressingMode::Accumulator, &r, &m);
+        assert_eq!(
+            Err(ErrorKind::InvalidOperand(AddressingMode::Accumulator)),
+            op.operand_addr(AddressingMode::Accumulator, &r, &m)
+        );
     }
 
     #[test]
@@ -439,8 +714,8 @@ mod tests
         r.pc.set(0x1000);
         m.write(0x1000, 0x50);
 
-        assert_eq!(0x1000, op.operand_addr(AddressingMode::Immediate, &r, &m));
-        assert_eq!(0x50, op.operand(AddressingMode::Immediate, &r, &m));
+        assert_eq!(0x1000, op.operand_addr(AddressingMode::Immediate, &r, &m).unwrap());
+        assert_eq!(0x50, op.operand(AddressingMode::Immediate, &r, &m).unwrap());
     }
 
     #[test]
@@ -451,8 +726,8 @@ mod tests
         m.write(0x0000, 0x80);
         m.write(0x0080, 0xFF);
 
-        assert_eq!(0x0080, op.operand_addr(AddressingMode::ZeroPage, &r, &m));
-        assert_eq!(0xFF, op.operand(AddressingMode::ZeroPage, &r, &m));
+        assert_eq!(0x0080, op.operand_addr(AddressingMode::ZeroPage, &r, &m).unwrap());
+        assert_eq!(0xFF, op.operand(AddressingMode::ZeroPage, &r, &m).unwrap());
     }
 
     #[test]
@@ -465,8 +740,8 @@ mod tests
 
         m.write(0x008F, 0xAA);
 
-        assert_eq!(0x008F, op.operand_addr(AddressingMode::ZeroPageX, &r, &m));
-        assert_eq!(0xAA, op.operand(AddressingMode::ZeroPageX, &r, &m));
+        assert_eq!(0x008F, op.operand_addr(AddressingMode::ZeroPageX, &r, &m).unwrap());
+        assert_eq!(0xAA, op.operand(AddressingMode::ZeroPageX, &r, &m).unwrap());
     }
 
     #[test]
@@ -479,8 +754,8 @@ mod tests
 
         m.write(0x0080, 0xAA);
 
-        assert_eq!(0x0080, op.operand_addr(AddressingMode::ZeroPageX, &r, &m));
-        assert_eq!(0xAA, op.operand(AddressingMode::ZeroPageX, &r, &m));
+        assert_eq!(0x0080, op.operand_addr(AddressingMode::ZeroPageX, &r, &m).unwrap());
+        assert_eq!(0xAA, op.operand(AddressingMode::ZeroPageX, &r, &m).unwrap());
     }
 
     #[test]
@@ -493,8 +768,8 @@ mod tests
 
         m.write(0x008C, 0xCC);
 
-        assert_eq!(0x008C, op.operand_addr(AddressingMode::ZeroPageY, &r, &m));
-        assert_eq!(0xCC, op.operand(AddressingMode::ZeroPageY, &r, &m));
+        assert_eq!(0x008C, op.operand_addr(AddressingMode::ZeroPageY, &r, &m).unwrap());
+        assert_eq!(0xCC, op.operand(AddressingMode::ZeroPageY, &r, &m).unwrap());
     }
 
     #[test]
@@ -507,8 +782,8 @@ mod tests
 
         m.write(0x0080, 0xAA);
 
-        assert_eq!(0x0080, op.operand_addr(AddressingMode::ZeroPageY, &r, &m));
-        assert_eq!(0xAA, op.operand(AddressingMode::ZeroPageY, &r, &m));
+        assert_eq!(0x0080, op.operand_addr(AddressingMode::ZeroPageY, &r, &m).unwrap());
+        assert_eq!(0xAA, op.operand(AddressingMode::ZeroPageY, &r, &m).unwrap());
     }
 
     #[test]
@@ -516,12 +791,12 @@ mod tests
     {
         let (op, r, mut m) = test_op(DummyOp);
 
-        m.write_u16(0x0000, 0x1234);
+        m.write_u16(0x0000, 0x1234).unwrap();
 
         m.write(0x1234, 0x80);
 
-        assert_eq!(0x1234, op.operand_addr(AddressingMode::Absolute, &r, &m));
-        assert_eq!(0x80, op.operand(AddressingMode::Absolute, &r, &m));
+        assert_eq!(0x1234, op.operand_addr(AddressingMode::Absolute, &r, &m).unwrap());
+        assert_eq!(0x80, op.operand(AddressingMode::Absolute, &r, &m).unwrap());
     }
 
     #[test]
@@ -529,13 +804,13 @@ mod tests
     {
         let (op, mut r, mut m) = test_op(DummyOp);
 
-        m.write_u16(0x0000, 0x2000);
+        m.write_u16(0x0000, 0x2000).unwrap();
         r.x.set(0x92);
 
         m.write(0x2092, 0x80);
 
-        assert_eq!(0x2092, op.operand_addr(AddressingMode::AbsoluteX, &r, &m));
-        assert_eq!(0x80, op.operand(AddressingMode::AbsoluteX, &r, &m));
+        assert_eq!(0x2092, op.operand_addr(AddressingMode::AbsoluteX, &r, &m).unwrap());
+        assert_eq!(0x80, op.operand(AddressingMode::AbsoluteX, &r, &m).unwrap());
     }
 
     #[test]
@@ -543,13 +818,13 @@ mod tests
     {
         let (op, mut r, mut m) = test_op(DummyOp);
 
-        m.write_u16(0x0000, 0x2000);
+        m.write_u16(0x0000, 0x2000).unwrap();
         r.y.set(0x92);
 
         m.write(0x2092, 0x80);
 
-        assert_eq!(0x2092, op.operand_addr(AddressingMode::AbsoluteY, &r, &m));
-        assert_eq!(0x80, op.operand(AddressingMode::AbsoluteY, &r, &m));
+        assert_eq!(0x2092, op.operand_addr(AddressingMode::AbsoluteY, &r, &m).unwrap());
+        assert_eq!(0x80, op.operand(AddressingMode::AbsoluteY, &r, &m).unwrap());
     }
 
     #[test]
@@ -557,13 +832,13 @@ mod tests
     {
         let (op, r, mut m) = test_op(DummyOp);
 
-        m.write_u16(0x0000, 0x2000);
-        m.write_u16(0x2000, 0x4000);
+        m.write_u16(0x0000, 0x2000).unwrap();
+        m.write_u16(0x2000, 0x4000).unwrap();
 
         m.write(0x4000, 0x80);
 
-        assert_eq!(0x4000, op.operand_addr(AddressingMode::Indirect, &r, &m));
-        assert_eq!(0x80, op.operand(AddressingMode::Indirect, &r, &m));
+        assert_eq!(0x4000, op.operand_addr(AddressingMode::Indirect, &r, &m).unwrap());
+        assert_eq!(0x80, op.operand(AddressingMode::Indirect, &r, &m).unwrap());
     }
 
     #[test]
@@ -574,11 +849,11 @@ mod tests
         m.write(0x0000, 0x80);
         r.x.set(0x0F);
 
-        m.write_u16(0x008F, 0x4000);
+        m.write_u16(0x008F, 0x4000).unwrap();
         m.write(0x4000, 0xFF);
 
-        assert_eq!(0x4000, op.operand_addr(AddressingMode::IndirectX, &r, &m));
-        assert_eq!(0xFF, op.operand(AddressingMode::IndirectX, &r, &m));
+        assert_eq!(0x4000, op.operand_addr(AddressingMode::IndirectX, &r, &m).unwrap());
+        assert_eq!(0xFF, op.operand(AddressingMode::IndirectX, &r, &m).unwrap());
     }
 
     #[test]
@@ -594,8 +869,8 @@ mod tests
         m.write(0x0000, 0x12);
         m.write(0x1234, 0xFF);
 
-        assert_eq!(0x1234, op.operand_addr(AddressingMode::IndirectX, &r, &m));
-        assert_eq!(0xFF, op.operand(AddressingMode::IndirectX, &r, &m));
+        assert_eq!(0x1234, op.operand_addr(AddressingMode::IndirectX, &r, &m).unwrap());
+        assert_eq!(0xFF, op.operand(AddressingMode::IndirectX, &r, &m).unwrap());
     }
 
     #[test]
@@ -605,13 +880,13 @@ mod tests
 
         m.write(0x0000, 0x80);
 
-        m.write_u16(0x0080, 0x1200);
+        m.write_u16(0x0080, 0x1200).unwrap();
         r.y.set(0x34);
 
         m.write(0x1234, 0xFF);
 
-        assert_eq!(0x1234, op.operand_addr(AddressingMode::IndirectY, &r, &m));
-        assert_eq!(0xFF, op.operand(AddressingMode::IndirectY, &r, &m));
+        assert_eq!(0x1234, op.operand_addr(AddressingMode::IndirectY, &r, &m).unwrap());
+        assert_eq!(0xFF, op.operand(AddressingMode::IndirectY, &r, &m).unwrap());
     }
 
     #[test]
@@ -628,8 +903,8 @@ mod tests
 
         m.write(0x1236, 0xFF);
 
-        assert_eq!(0x1236, op.operand_addr(AddressingMode::IndirectY, &r, &m));
-        assert_eq!(0xFF, op.operand(AddressingMode::IndirectY, &r, &m));
+        assert_eq!(0x1236, op.operand_addr(AddressingMode::IndirectY, &r, &m).unwrap());
+        assert_eq!(0xFF, op.operand(AddressingMode::IndirectY, &r, &m).unwrap());
     }
 
     #[test]
@@ -641,7 +916,7 @@ mod tests
         m.write(0x0000, 0x10);
 
         // 0x0010 + 1 (operand)
-        assert_eq!(0x0011, op.operand_addr(AddressingMode::Relative, &r, &m));
+        assert_eq!(0x0011, op.operand_addr(AddressingMode::Relative, &r, &m).unwrap());
     }
 
     #[test]
@@ -653,7 +928,56 @@ mod tests
         m.write(0x1000, 0xF6); // -10 as signed
 
         // 0x1000 + 1 - 0xF6
-        assert_eq!(0x0FF7, op.operand_addr(AddressingMode::Relative, &r, &m));
+        assert_eq!(0x0FF7, op.operand_addr(AddressingMode::Relative, &r, &m).unwrap());
+    }
+
+    #[test]
+    fn page_crossed_absolute_x()
+    {
+        let (op, mut r, mut m) = test_op(DummyOp);
+
+        m.write_u16(0x0000, 0x20F0).unwrap();
+
+        r.x.set(0x0F);
+        assert!(!op.page_crossed(AddressingMode::AbsoluteX, &r, &m));
+
+        r.x.set(0x10);
+        assert!(op.page_crossed(AddressingMode::AbsoluteX, &r, &m));
+    }
+
+    #[test]
+    fn page_crossed_indirect_y()
+    {
+        let (op, mut r, mut m) = test_op(DummyOp);
+
+        m.write(0x0000, 0x80);
+        m.write_u16(0x0080, 0x12FF).unwrap();
+
+        r.y.set(0x00);
+        assert!(!op.page_crossed(AddressingMode::IndirectY, &r, &m));
+
+        r.y.set(0x01);
+        assert!(op.page_crossed(AddressingMode::IndirectY, &r, &m));
+    }
+
+    #[test]
+    fn page_crossed_ignores_other_modes()
+    {
+        let (op, mut r, mut m) = test_op(DummyOp);
+
+        m.write(0x0000, 0xFF);
+        r.x.set(0xFF);
+
+        assert!(!op.page_crossed(AddressingMode::ZeroPageX, &r, &m));
+    }
+
+    #[test]
+    fn every_opcode_is_supported()
+    {
+        for (opcode, entry) in OPCODES.iter().enumerate()
+        {
+            assert!(entry.is_some(), "Missing opcode 0x{:02X}", opcode);
+        }
     }
 
 }
\ No newline at end of file
@@ -1,19 +1,21 @@
-use crate::cpu::memory::Memory;
+use crate::cpu::Bus;
 use crate::cpu::CpuRegisters;
 
-use super::{Op, AddressingMode};
+use super::{Op, AddressingMode, ErrorKind};
 
 op!(Adc);
 impl Op for Adc
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
+        let carry = registers.p.has_carry() as u8;
+        let a = *registers.a;
 
         let add_arg_result = registers.a.overflowing_add(value);
-        let add_carry_result = add_arg_result.0.overflowing_add(registers.p.has_carry() as u8);
+        let add_carry_result = add_arg_result.0.overflowing_add(carry);
 
-        registers.p.set_carry(add_arg_result.1 || add_arg_result.1);
+        registers.p.set_carry(add_arg_result.1 || add_carry_result.1);
 
         registers.p.update_for_value(add_carry_result.0);
 
@@ -23,8 +25,45 @@ impl Op for Adc
         );
 
         registers.a.set(add_carry_result.0);
+
+        if registers.p.decimal_mode() && memory.decimal_mode()
+        {
+            decimal_add(registers, a, value, carry);
+        }
+
+        Ok(())
+    }
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
+    }
+}
+
+// NMOS 6502: Z comes from the binary sum, N and V from the sum once the low nibble is adjusted
+// Ref: http://www.6502.org/tutorials/decimal_mode.html#A
+fn decimal_add(registers: &mut CpuRegisters, a: u8, value: u8, carry: u8)
+{
+    let mut low = (a & 0x0F) + (value & 0x0F) + carry;
+
+    if low >= 0x0A
+    {
+        low = ((low + 0x06) & 0x0F) + 0x10;
+    }
+
+    let mut sum = (a & 0xF0) as u16 + (value & 0xF0) as u16 + low as u16;
+    let signed = (a & 0xF0) as i8 as i16 + (value & 0xF0) as i8 as i16 + low as i16;
+
+    registers.p.set_negative(sum & 0x80 != 0);
+    registers.p.set_overflow(!(-128..=127).contains(&signed));
+
+    if sum >= 0xA0
+    {
+        sum += 0x60;
     }
 
+    registers.p.set_carry(sum >= 0x100);
+    registers.a.set(sum as u8);
 }
 
 #[cfg(test)]
@@ -40,7 +79,7 @@ mod tests
 
         m.write(0x0000, 0x1);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x01, *r.a);
         assert!(!r.p.is_negative());
@@ -57,7 +96,7 @@ mod tests
         r.p.set_carry(true);
         m.write(0x0000, 0x1);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x02, *r.a);
         assert!(!r.p.is_negative());
@@ -74,7 +113,7 @@ mod tests
         r.a.set(0xFF);
         m.write(0x0000, 0x1);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.a);
         assert!(!r.p.is_negative());
@@ -83,6 +122,23 @@ mod tests
         assert!(!r.p.has_overflown());
     }
 
+    #[test]
+    fn overflow_u8_from_carry_should_set_carry()
+    {
+        let (op, mut r, mut m) = test_op(Adc);
+
+        r.a.set(0xFF);
+        r.p.set_carry(true);
+        m.write(0x0000, 0x0);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x00, *r.a);
+        assert!(r.p.is_zero());
+        assert!(r.p.has_carry());
+        assert!(!r.p.has_overflown());
+    }
+
     #[test]
     fn two_positive_number_resulting_in_negative_result_overflow_without_carry()
     {
@@ -92,7 +148,7 @@ mod tests
         m.write(0x0000, 0x50);
 
         // 0x50 + 0x50 = 0xa0 / 80 + 80 = -96 signed (160 unsigned)
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0xa0, *r.a);
         assert!(r.p.is_negative());
@@ -113,7 +169,7 @@ mod tests
         // 0xd0 + 0x90 = 0x160
         // Unsigned: 208 + 144 = 352 (96 + carry)
         // Signed: -48 + -112 = 96
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x60, *r.a);
         assert!(!r.p.is_negative());
@@ -121,4 +177,39 @@ mod tests
         assert!(r.p.has_carry());
         assert!(r.p.has_overflown());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn decimal()
+    {
+        let (op, mut r, mut m) = test_op(Adc);
+
+        r.a.set(0x58);
+        m.write(0x0000, 0x46);
+        r.p.set_carry(true);
+        r.p.set_decimal_mode(true);
+        m.set_decimal_mode(true);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        // N and V come from $A5, the sum before the high nibble is adjusted
+        assert_eq!(0x05, *r.a);
+        assert!(r.p.is_negative());
+        assert!(!r.p.is_zero());
+        assert!(r.p.has_carry());
+        assert!(r.p.has_overflown());
+    }
+
+    #[test]
+    fn decimal_flag_ignored_without_decimal_mode()
+    {
+        let (op, mut r, mut m) = test_op(Adc);
+
+        r.a.set(0x09);
+        m.write(0x0000, 0x01);
+        r.p.set_decimal_mode(true);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x0A, *r.a);
+    }
+}
@@ -0,0 +1,52 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+use super::{and::And, lsr::Lsr};
+
+op!(Alr);
+impl Op for Alr
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        And.call(mode, registers, memory)?;
+        Lsr.call(AddressingMode::Accumulator, registers, memory)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Alr);
+
+        r.a.set(0b1111_0011);
+        m.write(0x0000, 0b1000_0001);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b0100_0000, *r.a);
+        assert!(r.p.has_carry());
+        assert!(!r.p.is_negative());
+        assert!(!r.p.is_zero());
+    }
+
+    #[test]
+    fn zero()
+    {
+        let (op, mut r, mut m) = test_op(Alr);
+
+        r.a.set(0b0000_0001);
+        m.write(0x0000, 0b0000_0011);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x00, *r.a);
+        assert!(r.p.has_carry());
+        assert!(r.p.is_zero());
+    }
+}
@@ -0,0 +1,54 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+use super::and::And;
+
+op!(Anc);
+impl Op for Anc
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        And.call(mode, registers, memory)?;
+
+        // Bit 7 is copied into carry, as if the result was shifted by ASL
+        registers.p.set_carry(registers.p.is_negative());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn negative_sets_carry()
+    {
+        let (op, mut r, mut m) = test_op(Anc);
+
+        r.a.set(0b1100_0000);
+        m.write(0x0000, 0b1000_0001);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b1000_0000, *r.a);
+        assert!(r.p.is_negative());
+        assert!(r.p.has_carry());
+    }
+
+    #[test]
+    fn positive_clears_carry()
+    {
+        let (op, mut r, mut m) = test_op(Anc);
+
+        r.a.set(0b0100_0000);
+        r.p.set_carry(true);
+        m.write(0x0000, 0b1100_0000);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b0100_0000, *r.a);
+        assert!(!r.p.is_negative());
+        assert!(!r.p.has_carry());
+    }
+}
@@ -1,15 +1,22 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(And);
 impl Op for And
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
 
         registers.a.set(*registers.a & value);
 
         registers.p.update_for_value(*registers.a);
+
+        Ok(())
+    }
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
     }
 }
 
@@ -28,7 +35,7 @@ mod tests
         r.a.set(0b1001_1001);
         m.write(0x0000, 0b1000_0000);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0b1000_0000, *r.a);
         assert!(r.p.is_negative());
@@ -0,0 +1,43 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+// Value the accumulator is ORed with before the AND. It depends on the chip and temperature,
+// 0xEE is the most commonly observed one.
+pub const MAGIC_CONSTANT: u8 = 0xEE;
+
+op!(Ane);
+impl Op for Ane
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        let value = self.operand(mode, registers, memory)?;
+        let result = (*registers.a | MAGIC_CONSTANT) & *registers.x & value;
+
+        registers.a.set(result);
+        registers.p.update_for_value(result);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Ane);
+
+        r.a.set(0x01);
+        r.x.set(0x0F);
+        m.write(0x0000, 0xFF);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x0F, *r.a);
+        assert!(!r.p.is_zero());
+        assert!(!r.p.is_negative());
+    }
+}
@@ -0,0 +1,69 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+op!(Arr);
+impl Op for Arr
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        let value = *registers.a & self.operand(mode, registers, memory)?;
+
+        let mut result = value >> 1;
+
+        if registers.p.has_carry()
+        {
+            result |= 0b1000_0000;
+        }
+
+        registers.a.set(result);
+        registers.p.update_for_value(result);
+
+        // Carry and overflow come from bits 6 and 5 of the rotated result
+        let bit_6 = result & 0b0100_0000 != 0;
+        let bit_5 = result & 0b0010_0000 != 0;
+
+        registers.p.set_carry(bit_6);
+        registers.p.set_overflow(bit_6 ^ bit_5);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn with_carry()
+    {
+        let (op, mut r, mut m) = test_op(Arr);
+
+        r.a.set(0b1111_1111);
+        r.p.set_carry(true);
+        m.write(0x0000, 0b1000_0000);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b1100_0000, *r.a);
+        assert!(r.p.is_negative());
+        assert!(r.p.has_carry());
+        assert!(r.p.has_overflown());
+    }
+
+    #[test]
+    fn bit_5_only()
+    {
+        let (op, mut r, mut m) = test_op(Arr);
+
+        r.a.set(0b0100_0000);
+        m.write(0x0000, 0b0100_0000);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b0010_0000, *r.a);
+        assert!(!r.p.is_negative());
+        assert!(!r.p.has_carry());
+        assert!(r.p.has_overflown());
+    }
+}
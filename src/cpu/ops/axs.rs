@@ -0,0 +1,61 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+op!(Axs);
+impl Op for Axs
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        let value = self.operand(mode, registers, memory)?;
+        let and_value = *registers.a & *registers.x;
+
+        // Compare-like subtraction: the carry is not used and overflow is not affected
+        let result = and_value.wrapping_sub(value);
+
+        registers.x.set(result);
+        registers.p.set_carry(and_value >= value);
+        registers.p.update_for_value(result);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Axs);
+
+        r.a.set(0b0011_1111);
+        r.x.set(0b1111_0000);
+        m.write(0x0000, 0x10);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x20, *r.x);
+        assert_eq!(0b0011_1111, *r.a);
+        assert!(r.p.has_carry());
+        assert!(!r.p.is_negative());
+        assert!(!r.p.is_zero());
+    }
+
+    #[test]
+    fn borrow()
+    {
+        let (op, mut r, mut m) = test_op(Axs);
+
+        r.a.set(0xFF);
+        r.x.set(0x01);
+        m.write(0x0000, 0x02);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0xFF, *r.x);
+        assert!(!r.p.has_carry());
+        assert!(r.p.is_negative());
+    }
+}
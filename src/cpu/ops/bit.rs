@@ -1,15 +1,17 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Bit);
 impl Op for Bit
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
 
         registers.p.set_zero(*registers.a & value == 0);
         registers.p.set_overflow(value & 0b0100_0000 != 0);
         registers.p.set_negative(value & 0b1000_0000 != 0);
+
+        Ok(())
     }
 }
 
@@ -25,10 +27,10 @@ mod tests
         let (op, mut r, mut m) = test_op(Bit);
 
         r.a.set(0b1000_0000);
-        m.write_u16(0x0000, 0x1000);
+        m.write_u16(0x0000, 0x1000).unwrap();
         m.write(0x1000, 0b0000_0000);
 
-        op.call(AddressingMode::Absolute, &mut r, &mut m);
+        op.call(AddressingMode::Absolute, &mut r, &mut m).unwrap();
 
         assert!(r.p.is_zero());
         assert!(!r.p.has_overflown());
@@ -41,10 +43,10 @@ mod tests
         let (op, mut r, mut m) = test_op(Bit);
 
         r.a.set(0b1000_0000);
-        m.write_u16(0x0000, 0x1000);
+        m.write_u16(0x0000, 0x1000).unwrap();
         m.write(0x1000, 0b1100_0000);
 
-        op.call(AddressingMode::Absolute, &mut r, &mut m);
+        op.call(AddressingMode::Absolute, &mut r, &mut m).unwrap();
 
         assert!(!r.p.is_zero());
         assert!(r.p.has_overflown());
@@ -1,100 +1,73 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use crate::cpu::register::StatusRegister;
 
-op!(Bcc);
-impl Op for Bcc
-{
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
-    {
-        if !registers.p.has_carry()
-        {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
-        }
-    }
-}
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
-op!(Bcs);
-impl Op for Bcs
+macro_rules! branch
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    ($name:ident, $condition:expr) =>
     {
-        if registers.p.has_carry()
+        op!($name);
+        impl Op for $name
         {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
+            fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+            {
+                // The offset is read even when the branch is not taken
+                let next_pc = registers.pc.wrapping_add(1);
+                let target = self.operand_addr(mode, registers, memory)?;
+
+                if $condition(&registers.p)
+                {
+                    // The next opcode is fetched while adding the offset to the low byte, then from the wrong page
+                    // while fixing the high byte
+                    memory.read(next_pc);
+
+                    if next_pc & 0xFF00 != target & 0xFF00
+                    {
+                        memory.read(next_pc & 0xFF00 | target & 0x00FF);
+                    }
+
+                    registers.pc.set(target);
+                }
+                else
+                {
+                    registers.pc.set(next_pc);
+                }
+
+                Ok(())
+            }
+
+            fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+            {
+                if !$condition(&registers.p) || mode != AddressingMode::Relative { return 0; }
+
+                let next_pc = registers.pc.wrapping_add(1); // PC once the operand is consumed
+                let target = next_pc.wrapping_add(memory.peek(*registers.pc) as i8 as u16);
+
+                branch_penalty(next_pc, target)
+            }
+
+            fn sets_pc(&self) -> bool
+            {
+                true
+            }
         }
-    }
+    };
 }
 
-op!(Beq);
-impl Op for Beq
+// A taken branch costs 1 cycle, and 1 more if it lands on another page
+fn branch_penalty(next_pc: u16, target: u16) -> u8
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
-    {
-        if registers.p.is_zero()
-        {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
-        }
-    }
+    if next_pc & 0xFF00 == target & 0xFF00 { 1 } else { 2 }
 }
 
-op!(Bmi);
-impl Op for Bmi
-{
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
-    {
-        if registers.p.is_negative()
-        {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
-        }
-    }
-}
-
-op!(Bne);
-impl Op for Bne
-{
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
-    {
-        if !registers.p.is_zero()
-        {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
-        }
-    }
-}
-
-op!(Bpl);
-impl Op for Bpl
-{
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
-    {
-        if !registers.p.is_negative()
-        {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
-        }
-    }
-}
-
-op!(Bvc);
-impl Op for Bvc
-{
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
-    {
-        if !registers.p.has_overflown()
-        {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
-        }
-    }
-}
-
-op!(Bvs);
-impl Op for Bvs
-{
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
-    {
-        if registers.p.has_overflown()
-        {
-            registers.pc.set(self.operand_addr(mode, registers, memory));
-        }
-    }
-}
+branch!(Bcc, |p: &StatusRegister| !p.has_carry());
+branch!(Bcs, |p: &StatusRegister| p.has_carry());
+branch!(Beq, |p: &StatusRegister| p.is_zero());
+branch!(Bmi, |p: &StatusRegister| p.is_negative());
+branch!(Bne, |p: &StatusRegister| !p.is_zero());
+branch!(Bpl, |p: &StatusRegister| !p.is_negative());
+branch!(Bvc, |p: &StatusRegister| !p.has_overflown());
+branch!(Bvs, |p: &StatusRegister| p.has_overflown());
 
 #[cfg(test)]
 mod tests
@@ -109,7 +82,7 @@ mod tests
         setup(&mut r);
 
         m.write(0x0000, 0x10);
-        op.call(AddressingMode::Relative, &mut r, &mut m);
+        op.call(AddressingMode::Relative, &mut r, &mut m).unwrap();
 
         0x11 == *r.pc
     }
@@ -170,4 +143,36 @@ mod tests
         assert!(!branch_taken(Bvs, |r| r.p.set_overflow(false)));
     }
 
+    #[test]
+    fn not_taken_has_no_penalty()
+    {
+        let (op, mut r, mut m) = test_op(Bcc);
+
+        r.p.set_carry(true);
+        m.write(0x0000, 0x10);
+
+        assert_eq!(0, op.extra_cycles(AddressingMode::Relative, &r, &m));
+    }
+
+    #[test]
+    fn taken_same_page_penalty()
+    {
+        let (op, r, mut m) = test_op(Bcc);
+
+        m.write(0x0000, 0x10);
+
+        assert_eq!(1, op.extra_cycles(AddressingMode::Relative, &r, &m));
+    }
+
+    #[test]
+    fn taken_page_cross_penalty()
+    {
+        let (op, mut r, mut m) = test_op(Bcc);
+
+        r.pc.set(0x0210);
+        m.write(0x0210, 0x80); // -128 from 0x0211, lands on 0x0191
+
+        assert_eq!(2, op.extra_cycles(AddressingMode::Relative, &r, &m));
+    }
+
 }
\ No newline at end of file
@@ -1,20 +1,21 @@
 use crate::cpu::{BRK_VECTOR, register::{BREAK_FLAG}};
 
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Brk);
 impl Op for Brk
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        self.stack_push_u16(registers, memory, *registers.pc);
+        // BRK is followed by a padding byte, skipped by the return address
+        registers.pc.set(registers.pc.wrapping_add(1));
 
-        let status_register: u8 = Into::<u8>::into(&registers.p) | BREAK_FLAG;
-        self.stack_push(registers, memory, status_register);
+        self.interrupt(registers, memory, BRK_VECTOR, BREAK_FLAG)
+    }
 
-        // This is an internal addition so the CPU can "know" when BRK is ran and exit properly
-        registers.p.set_break(true);
-        registers.pc.set(memory.read_u16(BRK_VECTOR));
+    fn sets_pc(&self) -> bool
+    {
+        true
     }
 }
 
@@ -34,15 +35,16 @@ mod tests
         r.p.set_negative(true);
         r.p.set_carry(true);
 
-        m.write_u16(0xFFFE, 0x1234);
+        m.write_u16(0xFFFE, 0x1234).unwrap();
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(STACK_POINTER_START - 3, *r.sp);
         assert_eq!(0x23, m.read(STACK_END));
-        assert_eq!(0x45, m.read(STACK_END - 1));
+        assert_eq!(0x46, m.read(STACK_END - 1));
         assert_eq!(0b1011_0001, m.read(STACK_END - 2));
 
+        assert!(r.p.interrupt_disabled());
         assert_eq!(0x1234, *r.pc);
     }
 }
\ No newline at end of file
@@ -1,16 +1,23 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Cmp);
 impl Op for Cmp
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
         let result = registers.a.wrapping_sub(value);
 
 
         registers.p.set_carry(*registers.a >= value);
         registers.p.update_for_value(result);
+
+        Ok(())
+    }
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
     }
 }
 
@@ -28,7 +35,7 @@ mod tests
         r.a.set(0x1C);
         m.write(0x0000, 0x0C);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert!(r.p.has_carry());
         assert!(!r.p.is_zero());
@@ -43,7 +50,7 @@ mod tests
         r.a.set(0x0C);
         m.write(0x0000, 0x1C);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert!(!r.p.has_carry());
         assert!(!r.p.is_zero());
@@ -58,7 +65,7 @@ mod tests
         r.a.set(0x1C);
         m.write(0x0000, 0x1C);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert!(r.p.has_carry());
         assert!(r.p.is_zero());
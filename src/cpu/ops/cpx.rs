@@ -1,15 +1,17 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Cpx);
 impl Op for Cpx
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
         let result = registers.x.wrapping_sub(value);
 
         registers.p.set_carry(*registers.x >= value);
         registers.p.update_for_value(result);
+
+        Ok(())
     }
 }
 
@@ -27,7 +29,7 @@ mod tests
         r.x.set(0x1C);
         m.write(0x0000, 0x0C);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert!(r.p.has_carry());
         assert!(!r.p.is_zero());
@@ -42,7 +44,7 @@ mod tests
         r.x.set(0x0C);
         m.write(0x0000, 0x1C);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert!(!r.p.has_carry());
         assert!(!r.p.is_zero());
@@ -57,7 +59,7 @@ mod tests
         r.x.set(0x1C);
         m.write(0x0000, 0x1C);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert!(r.p.has_carry());
         assert!(r.p.is_zero());
@@ -0,0 +1,55 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind, Peeking};
+use super::{dec::Dec, cmp::Cmp};
+
+op!(Dcp);
+impl Op for Dcp
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        Dec.call(mode, registers, memory)?;
+        Cmp.call(mode, registers, &mut Peeking(memory))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn equal_after_decrement()
+    {
+        let (op, mut r, mut m) = test_op(Dcp);
+
+        r.a.set(0x10);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0x11);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x10, m.read(0x0010));
+        assert!(r.p.is_zero());
+        assert!(r.p.has_carry());
+        assert!(!r.p.is_negative());
+    }
+
+    #[test]
+    fn lower_accumulator()
+    {
+        let (op, mut r, mut m) = test_op(Dcp);
+
+        r.a.set(0x01);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0x00);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0xFF, m.read(0x0010));
+        assert!(!r.p.is_zero());
+        assert!(!r.p.has_carry());
+        assert!(!r.p.is_negative());
+    }
+}
@@ -1,17 +1,15 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Dec);
 impl Op for Dec
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let addr = self.operand_addr(mode, registers, memory);
-        let mut value = memory.read(addr);
+        let (_, result) = self.read_modify_write(mode, registers, memory, &|value| value.wrapping_sub(1))?;
 
-        value = value.wrapping_sub(1);
+        registers.p.update_for_value(result);
 
-        memory.write(addr, value);
-        registers.p.update_for_value(value);
+        Ok(())
     }
 }
 
@@ -29,7 +27,7 @@ mod tests
         m.write(0x0000, 0x10);
         m.write(0x0010, 0x0F);
 
-        op.call(AddressingMode::ZeroPage, &mut r, &mut m);
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
 
         assert_eq!(0x0E, m.read(0x0010));
 
@@ -45,7 +43,7 @@ mod tests
         m.write(0x0000, 0x10);
         m.write(0x0010, 0x00);
 
-        op.call(AddressingMode::ZeroPage, &mut r, &mut m);
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
 
         assert_eq!(0xFF, m.read(0x0010));
 
@@ -61,7 +59,7 @@ mod tests
         m.write(0x0000, 0x10);
         m.write(0x0010, 0x01);
 
-        op.call(AddressingMode::ZeroPage, &mut r, &mut m);
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, m.read(0x0010));
 
@@ -1,14 +1,16 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Dex);
 impl Op for Dex
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         let value = registers.x.wrapping_sub(1);
 
         registers.x.set(value);
         registers.p.update_for_value(value);
+
+        Ok(())
     }
 }
 
@@ -25,7 +27,7 @@ mod tests
 
         r.x.set(0x0F);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x0E, *r.x);
 
@@ -40,7 +42,7 @@ mod tests
 
         r.x.set(0x00);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0xFF, *r.x);
 
@@ -55,7 +57,7 @@ mod tests
 
         r.x.set(0x01);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.x);
 
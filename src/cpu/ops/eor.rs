@@ -1,15 +1,22 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Eor);
 impl Op for Eor
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
         let result = *registers.a ^ value;
 
         registers.a.set(result);
         registers.p.update_for_value(result);
+
+        Ok(())
+    }
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
     }
 }
 
@@ -27,7 +34,7 @@ mod tests
         r.a.set(0b1010_0101);
         m.write(0x0000, 0b0101_1010);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0b1111_1111, *r.a);
 
@@ -43,7 +50,7 @@ mod tests
         r.a.set(0xFF);
         m.write(0x0000, 0xFF);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.a);
 
@@ -1,65 +1,79 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Clc);
 impl Op for Clc
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         registers.p.set_carry(false);
+
+        Ok(())
     }
 }
 
 op!(Cld);
 impl Op for Cld
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         registers.p.set_decimal_mode(false);
+
+        Ok(())
     }
 }
 
 op!(Cli);
 impl Op for Cli
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         registers.p.set_interrupt_disable(false);
+
+        Ok(())
     }
 }
 
 op!(Clv);
 impl Op for Clv
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         registers.p.set_overflow(false);
+
+        Ok(())
     }
 }
 
 op!(Sec);
 impl Op for Sec
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         registers.p.set_carry(true);
+
+        Ok(())
     }
 }
 
 op!(Sed);
 impl Op for Sed
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         registers.p.set_decimal_mode(true);
+
+        Ok(())
     }
 }
 
 op!(Sei);
 impl Op for Sei
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         registers.p.set_interrupt_disable(true);
+
+        Ok(())
     }
 }
 
@@ -76,7 +90,7 @@ mod tests
 
         r.p.set_carry(true);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert!(!r.p.has_carry());
     }
@@ -88,7 +102,7 @@ mod tests
 
         r.p.set_decimal_mode(true);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert!(!r.p.decimal_mode());
     }
@@ -100,7 +114,7 @@ mod tests
 
         r.p.set_interrupt_disable(true);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert!(!r.p.interrupt_disabled());
     }
@@ -112,7 +126,7 @@ mod tests
 
         r.p.set_overflow(true);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert!(!r.p.has_overflown());
     }
@@ -124,7 +138,7 @@ mod tests
 
         r.p.set_carry(false);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert!(r.p.has_carry());
     }
@@ -136,7 +150,7 @@ mod tests
 
         r.p.set_decimal_mode(false);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert!(r.p.decimal_mode());
     }
@@ -148,7 +162,7 @@ mod tests
 
         r.p.set_interrupt_disable(false);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert!(r.p.interrupt_disabled());
     }
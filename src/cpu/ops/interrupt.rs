@@ -0,0 +1,60 @@
+use crate::cpu::{NMI_VECTOR, IRQ_VECTOR, register::HARDWARE_INTERRUPT_FLAG};
+
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+// Hardware interrupts, serviced by the CPU between instructions as a forced BRK.
+// Unlike BRK, the pushed status has the B flag cleared, and the opcode fetch and
+// operand read are replaced by two dummy reads of PC (7 bus accesses in all).
+
+op!(Nmi);
+impl Op for Nmi
+{
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        memory.read(*registers.pc);
+        memory.read(*registers.pc);
+
+        self.interrupt(registers, memory, NMI_VECTOR, HARDWARE_INTERRUPT_FLAG)
+    }
+}
+
+op!(Irq);
+impl Op for Irq
+{
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        memory.read(*registers.pc);
+        memory.read(*registers.pc);
+
+        self.interrupt(registers, memory, IRQ_VECTOR, HARDWARE_INTERRUPT_FLAG)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use crate::cpu::{STACK_POINTER_START, STACK_END};
+    use super::*;
+
+    #[test]
+    fn nmi()
+    {
+        let (op, mut r, mut m) = test_op(Nmi);
+
+        r.pc.set(0x0345);
+        r.p.set_carry(true);
+
+        m.write_u16(0xFFFA, 0x0200).unwrap();
+
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
+
+        assert_eq!(STACK_POINTER_START - 3, *r.sp);
+        assert_eq!(0x03, m.read(STACK_END));
+        assert_eq!(0x45, m.read(STACK_END - 1));
+        assert_eq!(0b0010_0001, m.read(STACK_END - 2));
+
+        assert!(r.p.interrupt_disabled());
+        assert_eq!(0x0200, *r.pc);
+    }
+}
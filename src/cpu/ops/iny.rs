@@ -1,14 +1,16 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Iny);
 impl Op for Iny
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         let value = registers.y.wrapping_add(1);
 
         registers.y.set(value);
         registers.p.update_for_value(value);
+
+        Ok(())
     }
 }
 
@@ -25,7 +27,7 @@ mod tests
 
         r.y.set(0x0F);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x10, *r.y);
 
@@ -40,7 +42,7 @@ mod tests
 
         r.y.set(0xFF);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.y);
 
@@ -55,7 +57,7 @@ mod tests
 
         r.y.set(0xFE);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0xFF, *r.y);
 
@@ -0,0 +1,57 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind, Peeking};
+use super::{inc::Inc, sbc::Sbc};
+
+op!(Isb);
+impl Op for Isb
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        Inc.call(mode, registers, memory)?;
+        Sbc.call(mode, registers, &mut Peeking(memory))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Isb);
+
+        r.a.set(0x10);
+        r.p.set_carry(true);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0x02);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x03, m.read(0x0010));
+        assert_eq!(0x0D, *r.a);
+        assert!(r.p.has_carry());
+        assert!(!r.p.is_zero());
+        assert!(!r.p.is_negative());
+    }
+
+    #[test]
+    fn wrapping_increment()
+    {
+        let (op, mut r, mut m) = test_op(Isb);
+
+        r.a.set(0x10);
+        r.p.set_carry(true);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0xFF);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x00, m.read(0x0010));
+        assert_eq!(0x10, *r.a);
+        assert!(r.p.has_carry());
+    }
+}
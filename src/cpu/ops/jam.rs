@@ -0,0 +1,37 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+op!(Jam);
+impl Op for Jam
+{
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        // The real CPU locks up until reset, we stay on the opcode forever instead
+        registers.pc.set(registers.pc.wrapping_sub(1));
+
+        Ok(())
+    }
+
+    fn sets_pc(&self) -> bool
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn stays_on_opcode()
+    {
+        let (op, mut r, mut m) = test_op(Jam);
+
+        r.pc.set(0x0601);
+
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x0600, *r.pc);
+    }
+}
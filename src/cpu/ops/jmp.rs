@@ -1,9 +1,9 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Jmp);
 impl Op for Jmp
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         let addr = match mode
         {
@@ -18,10 +18,17 @@ impl Op for Jmp
 
                 u16::from_le_bytes([lsb, msb])
             },
-            _ => self.operand_addr(mode, registers, memory)
+            _ => self.operand_addr(mode, registers, memory)?
         };
 
         registers.pc.set(addr);
+
+        Ok(())
+    }
+
+    fn sets_pc(&self) -> bool
+    {
+        true
     }
 }
 
@@ -36,9 +43,9 @@ mod tests
     {
         let (op, mut r, mut m) = test_op(Jmp);
 
-        m.write_u16(0x0000, 0x1234);
+        m.write_u16(0x0000, 0x1234).unwrap();
 
-        op.call(AddressingMode::Absolute, &mut r, &mut m);
+        op.call(AddressingMode::Absolute, &mut r, &mut m).unwrap();
 
         assert_eq!(0x1234, *r.pc);
     }
@@ -52,9 +59,9 @@ mod tests
         m.write(0x1100, 0x12);
         m.write(0x1000, 0x34);
 
-        m.write_u16(0x0000, 0x10FF);
+        m.write_u16(0x0000, 0x10FF).unwrap();
 
-        op.call(AddressingMode::Indirect, &mut r, &mut m);
+        op.call(AddressingMode::Indirect, &mut r, &mut m).unwrap();
 
         assert_eq!(0x3489, *r.pc);
     }
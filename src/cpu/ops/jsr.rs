@@ -1,14 +1,25 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Jsr);
 impl Op for Jsr
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        self.stack_push_u16(registers, memory, *registers.pc + 1); // PC is after opcode, 2 bytes operand, -1 (JSR)
-        let addr = self.operand_addr(mode, registers, memory);
+        // The high byte of the target is only read after pushing the return address
+        let lsb = memory.read(*registers.pc);
+        self.stack_dummy_read(registers, memory);
 
-        registers.pc.set(addr);
+        self.stack_push_u16(registers, memory, registers.pc.wrapping_add(1))?; // PC is after opcode, 2 bytes operand, -1 (JSR)
+        let msb = memory.read(registers.pc.wrapping_add(1));
+
+        registers.pc.set(u16::from_le_bytes([lsb, msb]));
+
+        Ok(())
+    }
+
+    fn sets_pc(&self) -> bool
+    {
+        true
     }
 }
 
@@ -24,11 +35,11 @@ mod tests
         let (op, mut r, mut m) = test_op(Jsr);
 
         r.pc.set(0x200);
-        m.write_u16(0x0200, 0x1234);
+        m.write_u16(0x0200, 0x1234).unwrap();
 
-        op.call(AddressingMode::Absolute, &mut r, &mut m);
+        op.call(AddressingMode::Absolute, &mut r, &mut m).unwrap();
 
         assert_eq!(0x1234, *r.pc);
-        assert_eq!(0x0202, op.stack_peek_u16(&r, &m));
+        assert_eq!(0x0201, op.stack_peek_u16(&r, &m));
     }
 }
\ No newline at end of file
@@ -0,0 +1,47 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+op!(Las);
+impl Op for Las
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        let value = self.operand(mode, registers, memory)? & *registers.sp;
+
+        registers.a.set(value);
+        registers.x.set(value);
+        registers.sp.set(value);
+        registers.p.update_for_value(value);
+
+        Ok(())
+    }
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Las);
+
+        r.sp.set(0xF0);
+        m.write_u16(0x0000, 0x0300).unwrap();
+        m.write(0x0300, 0b1001_1001);
+
+        op.call(AddressingMode::AbsoluteY, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b1001_0000, *r.a);
+        assert_eq!(0b1001_0000, *r.x);
+        assert_eq!(0b1001_0000, *r.sp);
+        assert!(r.p.is_negative());
+        assert!(!r.p.is_zero());
+    }
+}
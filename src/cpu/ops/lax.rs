@@ -0,0 +1,60 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind, Peeking};
+use super::{lda::Lda, ldx::Ldx};
+
+op!(Lax);
+impl Op for Lax
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        Lda.call(mode, registers, memory)?;
+        Ldx.call(mode, registers, &mut Peeking(memory))?;
+
+        Ok(())
+    }
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Lax);
+
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0x80);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x80, *r.a);
+        assert_eq!(0x80, *r.x);
+        assert!(!r.p.is_zero());
+        assert!(r.p.is_negative());
+    }
+
+    #[test]
+    fn zero()
+    {
+        let (op, mut r, mut m) = test_op(Lax);
+
+        r.a.set(0x10);
+        r.x.set(0x20);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0x00);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x00, *r.a);
+        assert_eq!(0x00, *r.x);
+        assert!(r.p.is_zero());
+        assert!(!r.p.is_negative());
+    }
+}
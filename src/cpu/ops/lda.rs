@@ -1,14 +1,21 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Lda);
 impl Op for Lda
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
 
         registers.a.set(value);
         registers.p.update_for_value(value);
+
+        Ok(())
+    }
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
     }
 }
 
@@ -25,7 +32,7 @@ mod tests
 
         m.write(0x0000, 0x10);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x10, *r.a);
         assert!(!r.p.is_zero());
@@ -39,7 +46,7 @@ mod tests
 
         m.write(0x0000, 0x00);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.a);
         assert!(r.p.is_zero());
@@ -53,7 +60,7 @@ mod tests
 
         m.write(0x0000, 0xFF);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0xFF, *r.a);
         assert!(!r.p.is_zero());
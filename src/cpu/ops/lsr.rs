@@ -1,28 +1,26 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Lsr);
 impl Op for Lsr
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = self.operand(mode, registers, memory);
-
-        let result = value >> 1;
-
-        if let AddressingMode::Accumulator = mode
+        let (value, result) = if let AddressingMode::Accumulator = mode
         {
-            registers.a.set(result);
+            let value = *registers.a;
+            registers.a.set(value >> 1);
+
+            (value, *registers.a)
         }
         else
         {
-            memory.write(
-                self.operand_addr(mode, registers, memory),
-                result
-            );
-        }
+            self.read_modify_write(mode, registers, memory, &|value| value >> 1)?
+        };
 
         registers.p.update_for_value(result);
         registers.p.set_carry(value & 0b0000_0001 != 0);
+
+        Ok(())
     }
 }
 
@@ -39,7 +37,7 @@ mod tests
 
         r.a.set(0b0000_0010);
 
-        op.call(AddressingMode::Accumulator, &mut r, &mut m);
+        op.call(AddressingMode::Accumulator, &mut r, &mut m).unwrap();
 
         assert_eq!(0b0000_0001, *r.a);
         assert!(!r.p.is_negative());
@@ -54,7 +52,7 @@ mod tests
 
         r.a.set(0b0000_0001);
 
-        op.call(AddressingMode::Accumulator, &mut r, &mut m);
+        op.call(AddressingMode::Accumulator, &mut r, &mut m).unwrap();
 
         assert_eq!(0b0000_0000, *r.a);
         assert!(!r.p.is_negative());
@@ -0,0 +1,40 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+use super::ane::MAGIC_CONSTANT;
+
+op!(Lxa);
+impl Op for Lxa
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        let value = self.operand(mode, registers, memory)?;
+        let result = (*registers.a | MAGIC_CONSTANT) & value;
+
+        registers.a.set(result);
+        registers.x.set(result);
+        registers.p.update_for_value(result);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Lxa);
+
+        r.a.set(0x00);
+        m.write(0x0000, 0x8F);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x8E, *r.a);
+        assert_eq!(0x8E, *r.x);
+        assert!(r.p.is_negative());
+    }
+}
@@ -1,21 +1,34 @@
 #[macro_export]
 macro_rules! opcodes
 {
-    ( $( ($opcode:expr, $mode:expr, $value:expr) ),* ) =>
+    (
+        official: [ $( ($opcode:expr, $mode:expr, $module:ident::$op:ident, $cycles:expr) ),* ],
+        unofficial: [ $( ($u_opcode:expr, $u_mode:expr, $u_module:ident::$u_op:ident, $u_cycles:expr) ),* ]
+    ) =>
     {
         {
-            let mut map = OpcodeMap::new();
-            $(
-                map.insert($opcode, Opcode
-                {
-                    opcode: $opcode,
-                    mode: $mode,
-                    op: Box::new($value)
-                });
-            )*
-            map
+            let mut table: OpcodeTable = [None; 256];
+            $( opcodes!(@insert table, true, $opcode, $mode, $module::$op, $cycles); )*
+            $( opcodes!(@insert table, false, $u_opcode, $u_mode, $u_module::$u_op, $u_cycles); )*
+            table
         }
     };
+
+    (@insert $table:ident, $official:expr, $opcode:expr, $mode:expr, $module:ident::$op:ident, $cycles:expr) =>
+    {
+        assert!($table[$opcode].is_none(), "Duplicated opcode in table");
+
+        $table[$opcode] = Some(OpcodeEntry
+        {
+            name:         stringify!($op),
+            mode:         $mode,
+            cycles:       $cycles,
+            official:     $official,
+            call:         |mode, registers, memory| $module::$op.call(mode, registers, memory),
+            extra_cycles: |mode, registers, memory| $module::$op.extra_cycles(mode, registers, memory),
+            sets_pc:      || $module::$op.sets_pc(),
+        });
+    };
 }
 
 #[macro_export]
@@ -1,10 +1,43 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Nop);
 impl Op for Nop
 {
-    fn call(&self, _: AddressingMode, _: &mut CpuRegisters, _: &mut Memory)
+    // Unofficial NOPs with an operand still read it, like the loads they are decoded as
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
+        if mode != AddressingMode::Implicit
+        {
+            memory.read(self.operand_addr(mode, registers, memory)?);
+        }
 
+        Ok(())
     }
-}
\ No newline at end of file
+
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::cpu::Access;
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn reads_operand()
+    {
+        let (op, mut r, mut m) = test_op(Nop);
+
+        m.write_u16(*r.pc, 0x0300).unwrap();
+        m.add_watchpoint(0x0305, Access::Read);
+        r.x.set(0x05);
+
+        op.call(AddressingMode::AbsoluteX, &mut r, &mut m).unwrap();
+
+        assert_eq!(Some(0x0305), m.take_watch_hit().map(|hit| hit.addr));
+    }
+}
@@ -1,11 +1,13 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Pha);
 impl Op for Pha
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        self.stack_push(registers, memory, *registers.a);
+        self.stack_push(registers, memory, *registers.a)?;
+
+        Ok(())
     }
 }
 
@@ -22,7 +24,7 @@ mod tests
 
         r.a.set(0x50);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x50, op.stack_peek(&r, &m));
         assert_eq!(0xFE, *r.sp);
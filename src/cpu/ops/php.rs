@@ -1,13 +1,15 @@
 use crate::cpu::register::BREAK_FLAG;
 
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Php);
 impl Op for Php
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        self.stack_push(registers, memory, BREAK_FLAG | Into::<u8>::into(&registers.p));
+        self.stack_push(registers, memory, BREAK_FLAG | Into::<u8>::into(&registers.p))?;
+
+        Ok(())
     }
 }
 
@@ -26,7 +28,7 @@ mod tests
         r.p.set_negative(true);
         r.p.set_overflow(true);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0b1111_0001, op.stack_peek(&r, &m));
         assert_eq!(0xFE, *r.sp);
@@ -1,14 +1,18 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Pla);
 impl Op for Pla
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
+        self.stack_dummy_read(registers, memory);
+
         let value = self.stack_pop(registers, memory);
         registers.a.set(value);
 
         registers.p.update_for_value(value);
+
+        Ok(())
     }
 }
 
@@ -30,7 +34,7 @@ mod tests
         r.sp.set(0x80);
         m.write(STACK_START + 0x81, 0x50);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x50, *r.a);
         assert_eq!(0x81, *r.sp);
@@ -48,7 +52,7 @@ mod tests
         r.sp.set(0x80);
         m.write(STACK_START + 0x81, 0x00);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.a);
 
@@ -65,7 +69,7 @@ mod tests
         r.sp.set(0x80);
         m.write(STACK_START + 0x81, 0xFF);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0xFF, *r.a);
 
@@ -1,14 +1,18 @@
 use crate::cpu::register::StatusRegister;
 
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Plp);
 impl Op for Plp
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
+        self.stack_dummy_read(registers, memory);
+
         let status_register_value = self.stack_pop(registers, memory);
         registers.p = StatusRegister::from(status_register_value);
+
+        Ok(())
     }
 }
 
@@ -28,7 +32,7 @@ mod tests
         r.sp.set(0x80);
         m.write(STACK_START + 0x81, 0b1100_0001);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x81, *r.sp);
 
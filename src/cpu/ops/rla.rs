@@ -0,0 +1,40 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind, Peeking};
+use super::{rol::Rol, and::And};
+
+op!(Rla);
+impl Op for Rla
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        Rol.call(mode, registers, memory)?;
+        And.call(mode, registers, &mut Peeking(memory))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Rla);
+
+        r.a.set(0b0000_1111);
+        r.p.set_carry(true);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0b1000_0101);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b0000_1011, m.read(0x0010));
+        assert_eq!(0b0000_1011, *r.a);
+        assert!(r.p.has_carry());
+        assert!(!r.p.is_negative());
+        assert!(!r.p.is_zero());
+    }
+}
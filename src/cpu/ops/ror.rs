@@ -1,32 +1,28 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Ror);
 impl Op for Ror
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let value = match mode
-        {
-            AddressingMode::Accumulator => *registers.a,
-            _ => self.operand(mode, registers, memory)
-        };
-
-        let mut rotated_value = value >> 1;
+        let carry = (registers.p.has_carry() as u8) << 7; // 0b1000_0000
+        let rotate = |value: u8| value >> 1 | carry;
 
-        if registers.p.has_carry()
+        let (value, rotated_value) = match mode
         {
-            rotated_value |= 0b1000_0000;
-        }
+            AddressingMode::Accumulator => {
+                let value = *registers.a;
+                registers.a.set(rotate(value));
 
-        match mode
-        {
-            AddressingMode::Accumulator => registers.a.set(rotated_value),
-            _ => memory.write(self.operand_addr(mode, registers, memory), rotated_value)
-        }
+                (value, *registers.a)
+            },
+            _ => self.read_modify_write(mode, registers, memory, &rotate)?
+        };
 
         registers.p.set_carry(value & 0b0000_0001 != 0);
         registers.p.update_for_value(rotated_value);
 
+        Ok(())
     }
 }
 
@@ -43,7 +39,7 @@ mod tests
 
         r.a.set(0b1010_1010);
 
-        op.call(AddressingMode::Accumulator, &mut r, &mut m);
+        op.call(AddressingMode::Accumulator, &mut r, &mut m).unwrap();
 
         assert_eq!(0b0101_0101, *r.a);
 
@@ -59,7 +55,7 @@ mod tests
 
         r.a.set(0x00);
 
-        op.call(AddressingMode::Accumulator, &mut r, &mut m);
+        op.call(AddressingMode::Accumulator, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.a);
 
@@ -75,7 +71,7 @@ mod tests
 
         r.a.set(0b1000_0001);
 
-        op.call(AddressingMode::Accumulator, &mut r, &mut m);
+        op.call(AddressingMode::Accumulator, &mut r, &mut m).unwrap();
 
         assert_eq!(0b0100_0000, *r.a);
 
@@ -92,7 +88,7 @@ mod tests
         r.p.set_carry(true);
         r.a.set(0b0000_1000);
 
-        op.call(AddressingMode::Accumulator, &mut r, &mut m);
+        op.call(AddressingMode::Accumulator, &mut r, &mut m).unwrap();
 
         assert_eq!(0b1000_0100, *r.a);
 
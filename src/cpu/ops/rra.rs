@@ -0,0 +1,58 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind, Peeking};
+use super::{ror::Ror, adc::Adc};
+
+op!(Rra);
+impl Op for Rra
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        Ror.call(mode, registers, memory)?;
+        Adc.call(mode, registers, &mut Peeking(memory))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Rra);
+
+        r.a.set(0x10);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0x05);
+
+        // 0x05 >> 1 = 0x02 with carry, then 0x10 + 0x02 + 1
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x02, m.read(0x0010));
+        assert_eq!(0x13, *r.a);
+        assert!(!r.p.has_carry());
+        assert!(!r.p.is_negative());
+        assert!(!r.p.is_zero());
+    }
+
+    #[test]
+    fn carry_out_of_addition()
+    {
+        let (op, mut r, mut m) = test_op(Rra);
+
+        r.a.set(0xFF);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0x01);
+
+        // 0x01 >> 1 = 0x00 with carry, then 0xFF + 0x00 + 1
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x00, m.read(0x0010));
+        assert_eq!(0x00, *r.a);
+        assert!(r.p.has_carry());
+        assert!(r.p.is_zero());
+    }
+}
@@ -1,17 +1,26 @@
 use crate::cpu::register::StatusRegister;
 
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Rti);
 impl Op for Rti
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
+        self.stack_dummy_read(registers, memory);
+
         let status_register = self.stack_pop(registers, memory);
         let pc_addr = self.stack_pop_u16(registers, memory);
 
         registers.p = StatusRegister::from(status_register);
         registers.pc.set(pc_addr);
+
+        Ok(())
+    }
+
+    fn sets_pc(&self) -> bool
+    {
+        true
     }
 }
 
@@ -27,11 +36,11 @@ mod tests
     {
         let (op, mut r, mut m) = test_op(Rti);
 
-        op.stack_push_u16(&mut r, &mut m, 0x8000);
+        op.stack_push_u16(&mut r, &mut m, 0x8000).unwrap();
         // NVss DIZC
-        op.stack_push(&mut r, &mut m, 0b1100_0001);
+        op.stack_push(&mut r, &mut m, 0b1100_0001).unwrap();
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x8000, *r.pc);
         assert_eq!(0xFF, *r.sp);
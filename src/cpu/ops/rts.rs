@@ -1,12 +1,24 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Rts);
 impl Op for Rts
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
+        self.stack_dummy_read(registers, memory);
+
+        // The pulled address is the last byte of the JSR, read again while incrementing it
         let pc_addr = self.stack_pop_u16(registers, memory);
-        registers.pc.set(pc_addr + 1);
+        memory.read(pc_addr);
+
+        registers.pc.set(pc_addr.wrapping_add(1));
+
+        Ok(())
+    }
+
+    fn sets_pc(&self) -> bool
+    {
+        true
     }
 }
 
@@ -21,9 +33,9 @@ mod tests
     {
         let (op, mut r, mut m) = test_op(Rts);
 
-        op.stack_push_u16(&mut r, &mut m, 0x8000);
+        op.stack_push_u16(&mut r, &mut m, 0x8000).unwrap();
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x8001, *r.pc);
     }
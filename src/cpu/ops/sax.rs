@@ -0,0 +1,38 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+op!(Sax);
+impl Op for Sax
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        let addr = self.write_addr(mode, registers, memory)?;
+
+        // Flags are not affected
+        memory.write(addr, *registers.a & *registers.x)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Sax);
+
+        r.a.set(0b1100_1100);
+        r.x.set(0b1010_1010);
+        m.write(0x0000, 0x10);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b1000_1000, m.read(0x0010));
+        assert!(!r.p.is_zero());
+        assert!(!r.p.is_negative());
+    }
+}
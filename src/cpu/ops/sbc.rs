@@ -1,23 +1,24 @@
-use crate::cpu::memory::Memory;
+use crate::cpu::Bus;
 use crate::cpu::CpuRegisters;
 
-use super::{Op, AddressingMode};
+use super::{Op, AddressingMode, ErrorKind};
 
 op!(Sbc);
 impl Op for Sbc
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         // SBC:
         // A - M - (1-C)
         // A - M - (1-C) + 256
         // A + (255-M) + C
 
-        let value = self.operand(mode, registers, memory);
+        let value = self.operand(mode, registers, memory)?;
         let one_compl_value = value ^ 0xFF; // Flip bits
+        let carry = registers.p.has_carry() as u8;
 
         let first_add = registers.a.overflowing_add(one_compl_value);
-        let second_add = first_add.0.overflowing_add(registers.p.has_carry() as u8);
+        let second_add = first_add.0.overflowing_add(carry);
 
         // Set carry as addition op (we 1's complemented N)
         registers.p.set_carry(first_add.1 || second_add.1);
@@ -29,9 +30,43 @@ impl Op for Sbc
             (*registers.a ^ second_add.0) & (one_compl_value ^ second_add.0) & 0x80 != 0
         );
 
-        registers.a.set(second_add.0);
+        // Flags are the binary ones, only the result is adjusted
+        if registers.p.decimal_mode() && memory.decimal_mode()
+        {
+            registers.a.set(decimal_subtract(*registers.a, value, carry));
+        }
+        else
+        {
+            registers.a.set(second_add.0);
+        }
+
+        Ok(())
     }
 
+    fn extra_cycles(&self, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> u8
+    {
+        self.page_crossed(mode, registers, memory) as u8
+    }
+}
+
+// NMOS 6502, ref: http://www.6502.org/tutorials/decimal_mode.html#A
+fn decimal_subtract(a: u8, value: u8, carry: u8) -> u8
+{
+    let mut low = (a & 0x0F) as i16 - (value & 0x0F) as i16 + carry as i16 - 1;
+
+    if low < 0
+    {
+        low = ((low - 0x06) & 0x0F) - 0x10;
+    }
+
+    let mut difference = (a & 0xF0) as i16 - (value & 0xF0) as i16 + low;
+
+    if difference < 0
+    {
+        difference -= 0x60;
+    }
+
+    difference as u8
 }
 
 #[cfg(test)]
@@ -51,7 +86,7 @@ mod tests
         r.p.set_carry(true);
         m.write(0x0000, 0x03);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x0D, *r.a);
         assert!(r.p.has_carry());
@@ -69,7 +104,7 @@ mod tests
         r.p.set_carry(false); // Borrow = !Carry
         m.write(0x0000, 0x01);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x07, *r.a);
         assert!(!r.p.is_negative());
@@ -87,7 +122,7 @@ mod tests
         m.write(0x0000, 0x70);
         r.p.set_carry(true);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0xE0, *r.a);
         assert!(r.p.is_negative());
@@ -105,7 +140,7 @@ mod tests
         m.write(0x0000, 0xb0);
         r.p.set_carry(true);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0xA0, *r.a);
         assert!(r.p.is_negative());
@@ -123,7 +158,7 @@ mod tests
         m.write(0x0000, 0x70);
         r.p.set_carry(true);
 
-        op.call(AddressingMode::Immediate, &mut r, &mut m);
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
 
         assert_eq!(0x60, *r.a);
         assert!(!r.p.is_negative());
@@ -131,4 +166,24 @@ mod tests
         assert!(r.p.has_carry());
         assert!(r.p.has_overflown());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn decimal()
+    {
+        let (op, mut r, mut m) = test_op(Sbc);
+
+        r.a.set(0x12);
+        m.write(0x0000, 0x21);
+        r.p.set_carry(true);
+        r.p.set_decimal_mode(true);
+        m.set_decimal_mode(true);
+
+        op.call(AddressingMode::Immediate, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x91, *r.a);
+        assert!(r.p.is_negative());
+        assert!(!r.p.is_zero());
+        assert!(!r.p.has_carry());
+        assert!(!r.p.has_overflown());
+    }
+}
@@ -0,0 +1,131 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
+
+// Unstable stores: the value is ANDed with the high byte of the base address + 1, and when
+// indexing crosses a page the high byte of the target address is replaced by the stored value.
+// Real chips vary on this, we emulate the most commonly documented behavior.
+fn store_and_high(op: &impl Op, mode: AddressingMode, registers: &CpuRegisters, memory: &mut dyn Bus, index: u8, value: u8) -> Result<(), ErrorKind>
+{
+    let addr = op.write_addr(mode, registers, memory)?;
+    let base = addr.wrapping_sub(index as u16);
+
+    let result = value & ((base >> 8) as u8).wrapping_add(1);
+
+    let addr = if base & 0xFF00 != addr & 0xFF00
+    {
+        (result as u16) << 8 | (addr & 0xFF)
+    }
+    else
+    {
+        addr
+    };
+
+    memory.write(addr, result)
+}
+
+op!(Sha);
+impl Op for Sha
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        store_and_high(self, mode, registers, memory, *registers.y, *registers.a & *registers.x)
+    }
+}
+
+op!(Shx);
+impl Op for Shx
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        store_and_high(self, mode, registers, memory, *registers.y, *registers.x)
+    }
+}
+
+op!(Shy);
+impl Op for Shy
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        store_and_high(self, mode, registers, memory, *registers.x, *registers.y)
+    }
+}
+
+op!(Tas);
+impl Op for Tas
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        registers.sp.set(*registers.a & *registers.x);
+
+        store_and_high(self, mode, registers, memory, *registers.y, *registers.sp)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn sha()
+    {
+        let (op, mut r, mut m) = test_op(Sha);
+
+        r.a.set(0xFF);
+        r.x.set(0xF3);
+        r.y.set(0x10);
+        m.write_u16(0x0000, 0x0400).unwrap();
+
+        op.call(AddressingMode::AbsoluteY, &mut r, &mut m).unwrap();
+
+        // 0xF3 & (0x04 + 1)
+        assert_eq!(0x01, m.read(0x0410));
+    }
+
+    #[test]
+    fn shx_page_cross()
+    {
+        let (op, mut r, mut m) = test_op(Shx);
+
+        r.x.set(0x01);
+        r.y.set(0x20);
+        m.write_u16(0x0000, 0x01F0).unwrap();
+        m.write(0x0010, 0xFF);
+
+        op.call(AddressingMode::AbsoluteY, &mut r, &mut m).unwrap();
+
+        // 0x01 & (0x01 + 1) = 0x00, which also replaces the high byte of 0x0210
+        assert_eq!(0x00, m.read(0x0010));
+        assert_eq!(0x00, m.read(0x0210));
+    }
+
+    #[test]
+    fn shy()
+    {
+        let (op, mut r, mut m) = test_op(Shy);
+
+        r.x.set(0x10);
+        r.y.set(0xFF);
+        m.write_u16(0x0000, 0x0300).unwrap();
+
+        op.call(AddressingMode::AbsoluteX, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x04, m.read(0x0310));
+    }
+
+    #[test]
+    fn tas()
+    {
+        let (op, mut r, mut m) = test_op(Tas);
+
+        r.a.set(0xF7);
+        r.x.set(0x7F);
+        r.y.set(0x10);
+        m.write_u16(0x0000, 0x0300).unwrap();
+
+        op.call(AddressingMode::AbsoluteY, &mut r, &mut m).unwrap();
+
+        assert_eq!(0x77, *r.sp);
+        assert_eq!(0x04, m.read(0x0310));
+    }
+}
@@ -0,0 +1,39 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind, Peeking};
+use super::{asl::Asl, ora::Ora};
+
+op!(Slo);
+impl Op for Slo
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        Asl.call(mode, registers, memory)?;
+        Ora.call(mode, registers, &mut Peeking(memory))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Slo);
+
+        r.a.set(0b0000_0001);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0b1100_0000);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b1000_0000, m.read(0x0010));
+        assert_eq!(0b1000_0001, *r.a);
+        assert!(r.p.has_carry());
+        assert!(r.p.is_negative());
+        assert!(!r.p.is_zero());
+    }
+}
@@ -0,0 +1,39 @@
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind, Peeking};
+use super::{lsr::Lsr, eor::Eor};
+
+op!(Sre);
+impl Op for Sre
+{
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
+    {
+        Lsr.call(mode, registers, memory)?;
+        Eor.call(mode, registers, &mut Peeking(memory))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::super::test_helpers::*;
+    use super::*;
+
+    #[test]
+    fn simple()
+    {
+        let (op, mut r, mut m) = test_op(Sre);
+
+        r.a.set(0b0000_0110);
+        m.write(0x0000, 0x10);
+        m.write(0x0010, 0b0000_0101);
+
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
+
+        assert_eq!(0b0000_0010, m.read(0x0010));
+        assert_eq!(0b0000_0100, *r.a);
+        assert!(r.p.has_carry());
+        assert!(!r.p.is_negative());
+        assert!(!r.p.is_zero());
+    }
+}
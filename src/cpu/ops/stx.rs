@@ -1,13 +1,15 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Stx);
 impl Op for Stx
 {
-    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut Memory)
+    fn call(&self, mode: AddressingMode, registers: &mut CpuRegisters, memory: &mut dyn Bus) -> Result<(), ErrorKind>
     {
-        let addr = self.operand_addr(mode, registers, memory);
+        let addr = self.write_addr(mode, registers, memory)?;
 
-        memory.write(addr, *registers.x);
+        memory.write(addr, *registers.x)?;
+
+        Ok(())
     }
 }
 
@@ -25,7 +27,7 @@ mod tests
         r.x.set(0x80);
         m.write(0x0000, 0x10);
 
-        op.call(AddressingMode::ZeroPage, &mut r, &mut m);
+        op.call(AddressingMode::ZeroPage, &mut r, &mut m).unwrap();
 
         assert_eq!(0x80, m.read(0x0010));
     }
@@ -1,14 +1,16 @@
-use super::{Op, AddressingMode, CpuRegisters, Memory};
+use super::{Op, AddressingMode, CpuRegisters, Bus, ErrorKind};
 
 op!(Tax);
 impl Op for Tax
 {
-    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut Memory)
+    fn call(&self, _: AddressingMode, registers: &mut CpuRegisters, _: &mut dyn Bus) -> Result<(), ErrorKind>
     {
         let value = *registers.a;
 
         registers.x.set(value);
         registers.p.update_for_value(value);
+
+        Ok(())
     }
 }
 
@@ -25,7 +27,7 @@ mod tests
 
         r.a.set(0x10);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x10, *r.x);
 
@@ -41,7 +43,7 @@ mod tests
         r.a.set(0x00);
         r.x.set(0xFF);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0x00, *r.x);
 
@@ -56,7 +58,7 @@ mod tests
 
         r.a.set(0xFF);
 
-        op.call(AddressingMode::Implicit, &mut r, &mut m);
+        op.call(AddressingMode::Implicit, &mut r, &mut m).unwrap();
 
         assert_eq!(0xFF, *r.x);
 
@@ -1,6 +1,8 @@
-use super::{Op, CpuRegisters, Memory};
+use crate::cpu::Memory;
+
+use super::{Op, CpuRegisters};
 
 pub fn test_op(op: impl Op + 'static) -> (Box<dyn Op>, CpuRegisters, Memory)
 {
-    (Box::new(op), CpuRegisters::new(), Memory::new())
+    (Box::new(op), CpuRegisters::new(), Memory::flat())
 }
\ No newline at end of file
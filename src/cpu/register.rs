@@ -1,3 +1,4 @@
+use std::fmt::Display;
 use std::ops::{Deref, AddAssign};
 
 use super::STACK_POINTER_START;
@@ -29,6 +30,42 @@ impl CpuRegisters
     }
 }
 
+/// Plain copy of the registers, for reporting the CPU state outside of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterSnapshot
+{
+    pub a:  u8,
+    pub x:  u8,
+    pub y:  u8,
+    pub sp: u8,
+    pub pc: u16,
+    pub p:  u8,
+}
+
+impl From<&CpuRegisters> for RegisterSnapshot
+{
+    fn from(registers: &CpuRegisters) -> Self
+    {
+        RegisterSnapshot {
+            a:  *registers.a,
+            x:  *registers.x,
+            y:  *registers.y,
+            sp: *registers.sp,
+            pc: *registers.pc,
+            p:  Into::<u8>::into(&registers.p),
+        }
+    }
+}
+
+impl Display for RegisterSnapshot
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "A:{0:02X} X:{1:02X} Y:{2:02X} P:{3:02X} SP:{4:02X} PC:{5:04X}",
+            self.a, self.x, self.y, self.p, self.sp, self.pc
+        )
+    }
+}
 
 #[derive(Debug)]
 pub struct Register<T>
@@ -46,7 +83,7 @@ impl<T> Register<T>
 
     pub fn from(value: T) -> Self
     {
-        Register { value: value }
+        Register { value }
     }
 
     pub fn set(&mut self, value: T)
@@ -108,8 +145,8 @@ pub const DECIMAL_FLAG: u8   = 0b0000_1000;
 pub const OVERFLOW_FLAG: u8  = 0b0100_0000;
 pub const NEGATIVE_FLAG: u8  = 0b1000_0000;
 
-pub const BREAK_FLAG: u8     = 0b0011_0000;
-pub const NMI_FLAG: u8       = 0b0010_0000;
+pub const BREAK_FLAG: u8              = 0b0011_0000;
+pub const HARDWARE_INTERRUPT_FLAG: u8 = 0b0010_0000;
 
 #[derive(Debug)]
 pub struct StatusRegister
@@ -120,9 +157,6 @@ pub struct StatusRegister
     decimal_mode:       bool,
     overflow:           bool,
     negative:           bool,
-
-    // Not in NES, internal impl
-    break_state:        bool
 }
 
 impl StatusRegister
@@ -136,8 +170,6 @@ impl StatusRegister
             decimal_mode:       false,
             overflow:           false,
             negative:           false,
-
-            break_state:        false
         }
     }
 
@@ -149,8 +181,6 @@ impl StatusRegister
         self.decimal_mode =         false;
         self.overflow =             false;
         self.negative =             false;
-
-        self.break_state =          false;
     }
 
     pub fn update_for_value(&mut self, value : u8)
@@ -219,17 +249,14 @@ impl StatusRegister
         self.interrupt_disable
     }
 
-    // Internal impl
-    pub fn set_break(&mut self, flag: bool)
-    {
-        self.break_state = flag;
-    }
+}
 
-    pub fn has_broken(&self) -> bool
+impl Default for StatusRegister
+{
+    fn default() -> Self
     {
-        self.break_state
+        Self::new()
     }
-
 }
 
 impl From<u8> for StatusRegister
@@ -243,8 +270,6 @@ impl From<u8> for StatusRegister
             decimal_mode:       byte & DECIMAL_FLAG != 0,
             overflow:           byte & OVERFLOW_FLAG != 0,
             negative:           byte & NEGATIVE_FLAG != 0,
-
-            break_state: false
         }
     }
 }
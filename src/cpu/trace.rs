@@ -0,0 +1,350 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use super::{Cpu, CpuError, Bus, Memory};
+use super::register::CpuRegisters;
+use super::ops::AddressingMode;
+use super::disasm::decode;
+
+// Bit 5 is not stored but always reads as set
+const UNUSED_FLAG: u8 = 0b0010_0000;
+
+const PPU_DOTS_PER_SCANLINE: u64 = 341;
+const PPU_DOTS_PER_CPU_CYCLE: u64 = 3;
+
+/// Called by the CPU before every instruction it executes
+pub trait Tracer<B: Bus = Memory>
+{
+    fn trace(&mut self, cpu: &Cpu<B>);
+
+    /// Called when an instruction fails, after which the CPU is stopped
+    fn error(&mut self, _error: &CpuError) {}
+}
+
+// Lets the host keep a handle on the tracer given to the CPU
+impl<B: Bus, T: Tracer<B>> Tracer<B> for Rc<RefCell<T>>
+{
+    fn trace(&mut self, cpu: &Cpu<B>)
+    {
+        self.borrow_mut().trace(cpu);
+    }
+
+    fn error(&mut self, error: &CpuError)
+    {
+        self.borrow_mut().error(error);
+    }
+}
+
+/// Default tracer, does nothing
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopTracer;
+
+impl<B: Bus> Tracer<B> for NoopTracer
+{
+    fn trace(&mut self, _: &Cpu<B>) {}
+}
+
+/// Writes every instruction as a `nestest_line`
+pub struct NestestTracer<W: Write>
+{
+    writer: W,
+}
+
+impl NestestTracer<BufWriter<File>>
+{
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self>
+    {
+        Ok(NestestTracer::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> NestestTracer<W>
+{
+    pub fn new(writer: W) -> Self
+    {
+        NestestTracer { writer }
+    }
+
+    pub fn into_inner(self) -> W
+    {
+        self.writer
+    }
+}
+
+impl<B: Bus, W: Write> Tracer<B> for NestestTracer<W>
+{
+    fn trace(&mut self, cpu: &Cpu<B>)
+    {
+        writeln!(self.writer, "{}", nestest_line(cpu)).expect("Unable to write trace");
+    }
+
+    fn error(&mut self, _: &CpuError)
+    {
+        self.writer.flush().expect("Unable to write trace");
+    }
+}
+
+/// Keeps the last instructions in memory, for the host to `dump` when the CPU fails
+#[derive(Debug)]
+pub struct RingBufferTracer
+{
+    capacity: usize,
+    lines:    VecDeque<String>,
+}
+
+impl RingBufferTracer
+{
+    pub fn new(capacity: usize) -> Self
+    {
+        RingBufferTracer {
+            capacity,
+            lines: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Oldest instruction first
+    pub fn lines(&self) -> impl Iterator<Item = &str>
+    {
+        self.lines.iter().map(String::as_str)
+    }
+
+    pub fn dump<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        for line in &self.lines
+        {
+            writeln!(writer, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: Bus> Tracer<B> for RingBufferTracer
+{
+    fn trace(&mut self, cpu: &Cpu<B>)
+    {
+        if self.capacity == 0 { return }
+
+        if self.lines.len() == self.capacity
+        {
+            self.lines.pop_front();
+        }
+
+        self.lines.push_back(nestest_line(cpu));
+    }
+
+}
+
+/// Formats the instruction about to be executed the way Nintendulator logs it,
+/// e.g. `C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7`
+pub fn nestest_line<B: Bus>(cpu: &Cpu<B>) -> String
+{
+    let registers = &cpu.registers;
+    let memory = &cpu.memory;
+
+    let pc = *registers.pc;
+    let opcode = memory.peek(pc);
+
+    let (bytes, marker, disassembly) = match decode(|addr| memory.peek(addr), pc)
+    {
+        Some(instruction) => {
+            let bytes = instruction.bytes.iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let name = instruction.mnemonic();
+            let operand = operand_text(&name, instruction.mode, registers, memory);
+            let disassembly = if operand.is_empty() { name } else { format!("{} {}", name, operand) };
+
+            (bytes, if instruction.official { ' ' } else { '*' }, disassembly)
+        },
+        None => (format!("{:02X}", opcode), '*', String::from("???")),
+    };
+
+    let ppu_dots = cpu.cycles * PPU_DOTS_PER_CPU_CYCLE;
+
+    format!("{:04X}  {:<9}{}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:3},{:3} CYC:{}",
+        pc, bytes, marker, disassembly,
+        *registers.a, *registers.x, *registers.y, Into::<u8>::into(&registers.p) | UNUSED_FLAG, *registers.sp,
+        ppu_dots / PPU_DOTS_PER_SCANLINE, ppu_dots % PPU_DOTS_PER_SCANLINE, cpu.cycles
+    )
+}
+
+// Operand with the effective address and the value currently stored there, as computed before execution
+fn operand_text(name: &str, mode: AddressingMode, registers: &CpuRegisters, memory: &dyn Bus) -> String
+{
+    let pc = registers.pc.wrapping_add(1);
+    let byte = memory.peek(pc);
+    let word = u16::from_le_bytes([byte, memory.peek(pc.wrapping_add(1))]);
+
+    // Reads a pointer from the zero page, wrapping inside of it
+    let zero_page_u16 = |addr: u8| u16::from_le_bytes([memory.peek(addr as u16), memory.peek(addr.wrapping_add(1) as u16)]);
+
+    match mode
+    {
+        AddressingMode::Implicit => String::new(),
+        AddressingMode::Accumulator => String::from("A"),
+        AddressingMode::Immediate => format!("#${:02X}", byte),
+        AddressingMode::ZeroPage => format!("${:02X} = {:02X}", byte, memory.peek(byte as u16)),
+        AddressingMode::ZeroPageX => {
+            let addr = byte.wrapping_add(*registers.x);
+            format!("${:02X},X @ {:02X} = {:02X}", byte, addr, memory.peek(addr as u16))
+        },
+        AddressingMode::ZeroPageY => {
+            let addr = byte.wrapping_add(*registers.y);
+            format!("${:02X},Y @ {:02X} = {:02X}", byte, addr, memory.peek(addr as u16))
+        },
+        AddressingMode::Absolute => match name
+        {
+            "JMP" | "JSR" => format!("${:04X}", word),
+            _ => format!("${:04X} = {:02X}", word, memory.peek(word)),
+        },
+        AddressingMode::AbsoluteX => {
+            let addr = word.wrapping_add(*registers.x as u16);
+            format!("${:04X},X @ {:04X} = {:02X}", word, addr, memory.peek(addr))
+        },
+        AddressingMode::AbsoluteY => {
+            let addr = word.wrapping_add(*registers.y as u16);
+            format!("${:04X},Y @ {:04X} = {:02X}", word, addr, memory.peek(addr))
+        },
+        AddressingMode::Indirect => {
+            // Same page wrap bug as JMP
+            let msb_addr = (word & 0xFF00) | (word as u8).wrapping_add(1) as u16;
+            let target = u16::from_le_bytes([memory.peek(word), memory.peek(msb_addr)]);
+            format!("(${:04X}) = {:04X}", word, target)
+        },
+        AddressingMode::IndirectX => {
+            let pointer = byte.wrapping_add(*registers.x);
+            let addr = zero_page_u16(pointer);
+            format!("(${:02X},X) @ {:02X} = {:04X} = {:02X}", byte, pointer, addr, memory.peek(addr))
+        },
+        AddressingMode::IndirectY => {
+            let base = zero_page_u16(byte);
+            let addr = base.wrapping_add(*registers.y as u16);
+            format!("(${:02X}),Y = {:04X} @ {:04X} = {:02X}", byte, base, addr, memory.peek(addr))
+        },
+        AddressingMode::Relative => {
+            let target = pc.wrapping_add(1).wrapping_add(byte as i8 as u16);
+            format!("${:04X}", target)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn cpu_with_program(program: &[u8]) -> Cpu
+    {
+        let mut cpu = Cpu::new();
+
+        cpu.memory.write_slice(0x0600, program).unwrap();
+        cpu.registers.pc.set(0x0600);
+        cpu.registers.sp.set(0xFD);
+        cpu.registers.p.set_interrupt_disable(true);
+        cpu.cycles = 7;
+
+        cpu
+    }
+
+    #[test]
+    fn absolute_jump()
+    {
+        let cpu = cpu_with_program(&[0x4C, 0xF5, 0xC5]);
+
+        assert_eq!(
+            "0600  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+            nestest_line(&cpu)
+        );
+    }
+
+    #[test]
+    fn unofficial_opcode_is_marked()
+    {
+        let mut cpu = cpu_with_program(&[0x04, 0xA9]);
+        cpu.memory.write(0x00A9, 0x00);
+
+        assert_eq!(
+            "0600  04 A9    *NOP $A9 = 00                    A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+            nestest_line(&cpu)
+        );
+    }
+
+    #[test]
+    fn indirect_indexed()
+    {
+        let mut cpu = cpu_with_program(&[0xB1, 0x89]);
+        cpu.registers.y.set(0x01);
+        cpu.memory.write_u16(0x0089, 0x0300).unwrap();
+        cpu.memory.write(0x0301, 0x89);
+        cpu.cycles = 120;
+
+        assert_eq!(
+            "0600  B1 89     LDA ($89),Y = 0300 @ 0301 = 89  A:00 X:00 Y:01 P:24 SP:FD PPU:  1, 19 CYC:120",
+            nestest_line(&cpu)
+        );
+    }
+
+    #[test]
+    fn nestest_tracer()
+    {
+        let mut cpu = cpu_with_program(&[0xA9, 0x01, 0xEA]);
+        let tracer = Rc::new(RefCell::new(NestestTracer::new(Vec::new())));
+        cpu.set_tracer(Box::new(tracer.clone()));
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        let output = String::from_utf8(tracer.borrow().writer.clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("0600  A9 01     LDA #$01"));
+        assert!(lines[1].starts_with("0602  EA        NOP"));
+        assert!(lines[1].ends_with("A:01 X:00 Y:00 P:24 SP:FD PPU:  0, 27 CYC:9"));
+    }
+
+    #[test]
+    fn ring_buffer_keeps_last_instructions()
+    {
+        let mut cpu = cpu_with_program(&[0xE8, 0xE8, 0xE8, 0xE8]);
+        let tracer = Rc::new(RefCell::new(RingBufferTracer::new(2)));
+        cpu.set_tracer(Box::new(tracer.clone()));
+
+        for _ in 0..4
+        {
+            cpu.step().unwrap();
+        }
+
+        let tracer = tracer.borrow();
+        let lines: Vec<&str> = tracer.lines().collect();
+
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("0602  E8        INX"));
+        assert!(lines[1].starts_with("0603  E8        INX"));
+    }
+
+    #[test]
+    fn ring_buffer_ends_with_the_failing_instruction()
+    {
+        let mut cpu = cpu_with_program(&[0xE8, 0x8D, 0x00, 0x80]); // INX, STA $8000
+        let tracer = Rc::new(RefCell::new(RingBufferTracer::new(8)));
+        cpu.set_tracer(Box::new(tracer.clone()));
+
+        cpu.step().unwrap();
+        assert!(cpu.step().is_err());
+
+        let mut dump = Vec::new();
+        tracer.borrow().dump(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+
+        assert_eq!(2, dump.lines().count());
+        assert!(dump.lines().last().unwrap().starts_with("0601  8D 00 80  STA $8000"));
+    }
+}
@@ -0,0 +1,756 @@
+mod debug_info;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::cpu::{Cpu, StatusRegister};
+use crate::debugger::{Debugger, StopReason, StepTarget};
+use crate::rom::Rom;
+
+pub use self::debug_info::{DebugInfo, Line};
+
+// The CPU is the only thread
+const THREAD_ID: u64 = 1;
+
+// Instructions run between two checks for a pause request
+const RESUME_CHUNK: u64 = 10_000;
+
+const REGISTERS_REFERENCE: u64 = 1;
+const FLAGS_REFERENCE: u64 = 2;
+
+/// Debug Adapter Protocol server, editors talk to it over stdio
+pub struct DapServer<W: Write>
+{
+    output:     W,
+    seq:        u64,
+    debugger:   Option<Debugger>,
+    debug_info: Option<DebugInfo>,
+    running:    bool,
+    step_target: Option<StepTarget>, // Set while a step over or a step out runs
+    stop_on_entry: bool,
+
+    // Kept apart since each request replaces the breakpoints of one source, or all the instruction ones
+    source_breakpoints:      HashMap<String, Vec<u16>>,
+    instruction_breakpoints: Vec<u16>,
+}
+
+impl<W: Write> DapServer<W>
+{
+    /// The program is loaded by a launch request
+    pub fn new(output: W) -> DapServer<W>
+    {
+        DapServer {
+            output,
+            seq: 1,
+            debugger: None,
+            debug_info: None,
+            running: false,
+            step_target: None,
+            stop_on_entry: false,
+            source_breakpoints: HashMap::new(),
+            instruction_breakpoints: vec![],
+        }
+    }
+
+    /// The program is already loaded, for attach requests
+    pub fn with_debugger(output: W, debugger: Debugger, debug_info: Option<DebugInfo>) -> DapServer<W>
+    {
+        DapServer {
+            debugger: Some(debugger),
+            debug_info,
+            ..DapServer::new(output)
+        }
+    }
+
+    pub fn debugger(&mut self) -> Option<&mut Debugger>
+    {
+        self.debugger.as_mut()
+    }
+
+    /// Serves requests until a disconnect or the end of the input
+    pub fn serve<R: Read + Send + 'static>(&mut self, input: R) -> io::Result<()>
+    {
+        // Requests are read on their own thread so a running program can be paused
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut input = BufReader::new(input);
+
+            while let Ok(Some(message)) = read_message(&mut input)
+            {
+                if sender.send(message).is_err() { break }
+            }
+        });
+
+        loop
+        {
+            let message = if self.running
+            {
+                match receiver.try_recv()
+                {
+                    Ok(message) => message,
+                    Err(TryRecvError::Empty) => {
+                        self.run()?;
+                        continue;
+                    },
+                    Err(TryRecvError::Disconnected) => return Ok(()),
+                }
+            }
+            else
+            {
+                match receiver.recv()
+                {
+                    Ok(message) => message,
+                    Err(_) => return Ok(()),
+                }
+            };
+
+            if !self.dispatch(message)?
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    // Returns false once the session is over
+    fn dispatch(&mut self, request: Value) -> io::Result<bool>
+    {
+        // Anything else than a request is ignored
+        if request["type"] != "request" { return Ok(true) }
+
+        let command = request["command"].as_str().unwrap_or_default().to_string();
+        let arguments = &request["arguments"];
+
+        let result = match command.as_str()
+        {
+            "initialize" => Ok(capabilities()),
+            "launch" => self.launch(arguments),
+            "attach" => self.attach(arguments),
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "setInstructionBreakpoints" => self.set_instruction_breakpoints(arguments),
+            "setExceptionBreakpoints" => Ok(json!({})),
+            "configurationDone" => Ok(json!({})),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "6502" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => Ok(scopes()),
+            "variables" => self.variables(arguments),
+            "continue" => self.loaded().map(|_| json!({ "allThreadsContinued": true })),
+            "next" | "stepIn" | "stepOut" | "pause" => self.loaded().map(|_| json!({})),
+            "disconnect" | "terminate" => Ok(json!({})),
+            _ => Err(format!("Unsupported request '{}'", command)),
+        };
+
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+
+        match result
+        {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+
+        let success = response["success"] == true;
+        self.send(response)?;
+
+        if !success { return Ok(true) }
+
+        // Events have to come after the response
+        match command.as_str()
+        {
+            "launch" | "attach" => self.send_event("initialized", json!({}))?,
+            "configurationDone" if self.debugger.is_some() => {
+                if self.stop_on_entry
+                {
+                    self.send_stopped("entry", None)?;
+                }
+                else
+                {
+                    self.running = true;
+                }
+            },
+            "continue" => {
+                self.step_target = None;
+                self.running = true;
+            },
+            // Subroutines can run for long, so stepping over or out of them goes through the run loop
+            "next" => match self.debugger.as_ref().unwrap().step_over_target()
+            {
+                Some(target) => self.run_to(target),
+                None => self.step_in()?,
+            },
+            "stepIn" => self.step_in()?,
+            "stepOut" => {
+                let target = self.debugger.as_ref().unwrap().step_out_target();
+                self.run_to(target);
+            },
+            "pause" => {
+                self.running = false;
+                self.step_target = None;
+                self.send_stopped("pause", None)?;
+            },
+            "disconnect" | "terminate" => {
+                self.send_event("terminated", json!({}))?;
+                return Ok(false);
+            },
+            _ => {},
+        }
+
+        Ok(true)
+    }
+
+    fn step_in(&mut self) -> io::Result<()>
+    {
+        let stop = self.debugger.as_mut().unwrap().step();
+        self.report(stop, "step")
+    }
+
+    fn run_to(&mut self, target: StepTarget)
+    {
+        self.step_target = Some(target);
+        self.running = true;
+    }
+
+    // Runs a chunk of the program, stops on anything but the end of the chunk
+    fn run(&mut self) -> io::Result<()>
+    {
+        let stop = match (self.debugger.as_mut(), self.step_target.as_mut())
+        {
+            (Some(debugger), Some(target)) => debugger.run_to(target, RESUME_CHUNK),
+            (Some(debugger), None) => debugger.resume(RESUME_CHUNK),
+            (None, _) => StopReason::Step,
+        };
+
+        if stop != StopReason::Limit
+        {
+            let reason = if self.step_target.take().is_some() { "step" } else { "pause" };
+
+            self.running = false;
+            self.report(stop, reason)?;
+        }
+
+        Ok(())
+    }
+
+    // `program` is an iNES file, `debugInfo` an optional ld65 debug info file
+    fn launch(&mut self, arguments: &Value) -> Result<Value, String>
+    {
+        let program = arguments["program"].as_str().ok_or("Missing 'program' argument")?;
+        let rom = Rom::from_file(program)?;
+
+        let mut cpu = Cpu::new();
+        cpu.load_rom(rom)?;
+        cpu.reset();
+
+        self.debug_info = match arguments["debugInfo"].as_str()
+        {
+            Some(path) => Some(DebugInfo::from_file(path)?),
+            None => None,
+        };
+
+        self.debugger = Some(Debugger::new(cpu));
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.update_breakpoints();
+
+        Ok(json!({}))
+    }
+
+    fn attach(&mut self, arguments: &Value) -> Result<Value, String>
+    {
+        if self.debugger.is_none()
+        {
+            return Err(String::from("No program to attach to"));
+        }
+
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.update_breakpoints();
+
+        Ok(json!({}))
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value, String>
+    {
+        let path = arguments["source"]["path"].as_str().ok_or("Missing source path")?.to_string();
+        let lines: Vec<u64> = match arguments["breakpoints"].as_array()
+        {
+            Some(breakpoints) => breakpoints.iter().filter_map(|breakpoint| breakpoint["line"].as_u64()).collect(),
+            None => arguments["lines"].as_array().map(|lines| lines.iter().filter_map(Value::as_u64).collect()).unwrap_or_default(),
+        };
+
+        let mut addrs = vec![];
+        let mut breakpoints = vec![];
+
+        for line in lines
+        {
+            let line_addrs = match &self.debug_info
+            {
+                Some(info) => info.addresses(&path, line as u32),
+                None => vec![],
+            };
+
+            breakpoints.push(match line_addrs.first()
+            {
+                Some(addr) => json!({ "verified": true, "line": line, "instructionReference": format_addr(*addr) }),
+                None if self.debug_info.is_none() => json!({ "verified": false, "line": line, "message": "No debug info" }),
+                None => json!({ "verified": false, "line": line, "message": "No code on this line" }),
+            });
+
+            addrs.extend(line_addrs);
+        }
+
+        self.source_breakpoints.insert(path, addrs);
+        self.update_breakpoints();
+
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn set_instruction_breakpoints(&mut self, arguments: &Value) -> Result<Value, String>
+    {
+        let mut breakpoints = vec![];
+        self.instruction_breakpoints.clear();
+
+        for breakpoint in arguments["breakpoints"].as_array().into_iter().flatten()
+        {
+            let reference = breakpoint["instructionReference"].as_str().and_then(parse_addr);
+            let offset = breakpoint["offset"].as_i64().unwrap_or(0);
+
+            breakpoints.push(match reference
+            {
+                Some(addr) => {
+                    let addr = (addr as i64 + offset) as u16;
+                    self.instruction_breakpoints.push(addr);
+
+                    json!({ "verified": true, "instructionReference": format_addr(addr) })
+                },
+                None => json!({ "verified": false, "message": "Invalid address" }),
+            });
+        }
+
+        self.update_breakpoints();
+
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    // Breakpoints can be set before the program is loaded
+    fn update_breakpoints(&mut self)
+    {
+        let debugger = match self.debugger.as_mut()
+        {
+            Some(debugger) => debugger,
+            None => return,
+        };
+
+        let current: Vec<u16> = debugger.breakpoints().collect();
+
+        for addr in current
+        {
+            debugger.remove_breakpoint(addr);
+        }
+
+        for addr in self.source_breakpoints.values().flatten().chain(&self.instruction_breakpoints)
+        {
+            debugger.add_breakpoint(*addr);
+        }
+    }
+
+    fn stack_trace(&mut self) -> Result<Value, String>
+    {
+        let debugger = self.debugger.as_ref().ok_or("No program loaded")?;
+
+        let addrs = std::iter::once(debugger.pc()).chain(debugger.backtrace());
+        let frames: Vec<Value> = addrs.enumerate().map(|(id, addr)| self.frame(id, addr)).collect();
+
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    fn frame(&self, id: usize, addr: u16) -> Value
+    {
+        let name = match self.debug_info.as_ref().and_then(|info| info.label(addr))
+        {
+            Some((label, 0)) => label.to_string(),
+            Some((label, offset)) => format!("{}+{}", label, offset),
+            None => format!("${:04X}", addr),
+        };
+
+        let mut frame = json!({
+            "id": id,
+            "name": name,
+            "line": 0,
+            "column": 0,
+            "instructionPointerReference": format_addr(addr),
+        });
+
+        if let Some(line) = self.debug_info.as_ref().and_then(|info| info.line(addr))
+        {
+            frame["source"] = json!({ "path": line.file });
+            frame["line"] = json!(line.line);
+            frame["column"] = json!(1);
+        }
+
+        frame
+    }
+
+    fn variables(&mut self, arguments: &Value) -> Result<Value, String>
+    {
+        let registers = self.debugger.as_ref().ok_or("No program loaded")?.cpu.registers();
+
+        let variables = match arguments["variablesReference"].as_u64()
+        {
+            Some(REGISTERS_REFERENCE) => vec![
+                variable("A", format!("${:02X}", registers.a)),
+                variable("X", format!("${:02X}", registers.x)),
+                variable("Y", format!("${:02X}", registers.y)),
+                variable("SP", format!("${:02X}", registers.sp)),
+                variable("PC", format!("${:04X}", registers.pc)),
+                variable("P", format!("${:02X}", registers.p)),
+            ],
+            Some(FLAGS_REFERENCE) => {
+                let status = StatusRegister::from(registers.p);
+
+                vec![
+                    variable("N", status.is_negative().to_string()),
+                    variable("V", status.has_overflown().to_string()),
+                    variable("D", status.decimal_mode().to_string()),
+                    variable("I", status.interrupt_disabled().to_string()),
+                    variable("Z", status.is_zero().to_string()),
+                    variable("C", status.has_carry().to_string()),
+                ]
+            },
+            _ => return Err(String::from("Unknown variables reference")),
+        };
+
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn loaded(&self) -> Result<(), String>
+    {
+        match self.debugger
+        {
+            Some(_) => Ok(()),
+            None => Err(String::from("No program loaded")),
+        }
+    }
+
+    // `reason` is the one given when the program stopped by itself
+    fn report(&mut self, stop: StopReason, reason: &str) -> io::Result<()>
+    {
+        match stop
+        {
+            StopReason::Step | StopReason::Limit => self.send_stopped(reason, None),
+            StopReason::Breakpoint(_) => self.send_stopped("breakpoint", None),
+            StopReason::Watchpoint(hit) => {
+                let text = format!("{:?} of ${:02X} at ${:04X}", hit.access, hit.value, hit.addr);
+                self.send_stopped("data breakpoint", Some(text))
+            },
+            StopReason::Error(err) => self.send_stopped("exception", Some(err.to_string())),
+        }
+    }
+
+    fn send_stopped(&mut self, reason: &str, text: Option<String>) -> io::Result<()>
+    {
+        let mut body = json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true });
+
+        if let Some(text) = text
+        {
+            body["text"] = json!(text);
+        }
+
+        self.send_event("stopped", body)
+    }
+
+    fn send_event(&mut self, event: &str, body: Value) -> io::Result<()>
+    {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()>
+    {
+        message["seq"] = json!(self.seq);
+        self.seq += 1;
+
+        let content = message.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+        self.output.flush()
+    }
+}
+
+// Messages are JSON preceded by HTTP like headers, returns None at the end of the input
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>>
+{
+    let mut length = None;
+
+    loop
+    {
+        let mut header = String::new();
+
+        if input.read_line(&mut header)? == 0 { return Ok(None) }
+
+        let header = header.trim_end();
+
+        if header.is_empty() { break }
+
+        if let Some((name, value)) = header.split_once(':')
+        {
+            if name.eq_ignore_ascii_case("Content-Length")
+            {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length header"))?;
+
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content).map(Some).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn capabilities() -> Value
+{
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsInstructionBreakpoints": true,
+        "supportsTerminateRequest": true,
+    })
+}
+
+fn scopes() -> Value
+{
+    json!({
+        "scopes": [
+            { "name": "Registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false },
+            { "name": "Flags", "variablesReference": FLAGS_REFERENCE, "expensive": false },
+        ]
+    })
+}
+
+fn variable(name: &str, value: String) -> Value
+{
+    json!({ "name": name, "value": value, "variablesReference": 0 })
+}
+
+fn format_addr(addr: u16) -> String
+{
+    format!("0x{:04X}", addr)
+}
+
+// `0xC000`, `$C000` or decimal
+fn parse_addr(text: &str) -> Option<u16>
+{
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix('$'))
+    {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::net::{TcpListener, TcpStream};
+    use std::path::Path;
+
+    use crate::cpu::{asm, RegisterSnapshot};
+    use super::*;
+
+    const PROGRAM: &str = "
+        .org $0600
+        main:   jsr sub
+        loop:   inx
+                jmp loop
+        sub:    lda #$80
+                jsr inner
+                rts
+        inner:  iny
+                rts
+    ";
+
+    // Line 3 is `main`, line 7 `jsr inner` and line 9 `inner`
+    const DEBUG_INFO: &str = "\
+file\tid=0,name=\"snake.s\",size=0,mtime=0x00000000,mod=0
+line\tid=0,file=0,line=3,span=0
+line\tid=1,file=0,line=7,span=1
+line\tid=2,file=0,line=9,span=2
+seg\tid=0,name=\"CODE\",start=0x000600,size=0x0011,addrsize=absolute,type=rw
+span\tid=0,seg=0,start=0,size=3
+span\tid=1,seg=0,start=9,size=3
+span\tid=2,seg=0,start=13,size=1
+sym\tid=0,name=\"main\",addrsize=absolute,scope=0,def=0,val=0x0600,seg=0,type=lab
+sym\tid=1,name=\"sub\",addrsize=absolute,scope=0,def=0,val=0x0607,seg=0,type=lab
+sym\tid=2,name=\"inner\",addrsize=absolute,scope=0,def=0,val=0x060D,seg=0,type=lab
+";
+
+    struct Client
+    {
+        stream: BufReader<TcpStream>,
+        seq:    u64,
+    }
+
+    impl Client
+    {
+        // Sends a request and returns its response, events are skipped
+        fn request(&mut self, command: &str, arguments: Value) -> Value
+        {
+            self.seq += 1;
+
+            let content = json!({ "seq": self.seq, "type": "request", "command": command, "arguments": arguments }).to_string();
+            write!(self.stream.get_mut(), "Content-Length: {}\r\n\r\n{}", content.len(), content).unwrap();
+
+            loop
+            {
+                let message = read_message(&mut self.stream).unwrap().unwrap();
+
+                if message["type"] == "response"
+                {
+                    assert_eq!(self.seq, message["request_seq"]);
+                    return message;
+                }
+            }
+        }
+
+        fn event(&mut self, event: &str) -> Value
+        {
+            loop
+            {
+                let message = read_message(&mut self.stream).unwrap().unwrap();
+
+                if message["event"] == event
+                {
+                    return message["body"].clone();
+                }
+            }
+        }
+    }
+
+    // Runs a server on a local socket, `script` is the client side
+    fn session<F>(script: F) -> Debugger
+        where F: FnOnce(&mut Client) + Send + 'static
+    {
+        let assembly = asm::assemble(PROGRAM).unwrap();
+        let mut cpu = Cpu::flat();
+        cpu.load_at(assembly.origin, assembly.bytes).unwrap();
+        cpu.set_registers(RegisterSnapshot { a: 0, x: 0, y: 0, sp: 0xFD, pc: assembly.origin, p: 0x04 });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut client = Client { stream: BufReader::new(TcpStream::connect(addr).unwrap()), seq: 0 };
+
+            assert_eq!(true, client.request("initialize", json!({}))["success"]);
+            assert_eq!(true, client.request("attach", json!({ "stopOnEntry": true }))["success"]);
+            client.event("initialized");
+
+            script(&mut client);
+
+            client.request("disconnect", json!({}));
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut server = DapServer::with_debugger(stream.try_clone().unwrap(), Debugger::new(cpu), Some(DebugInfo::parse(DEBUG_INFO, Path::new("/home/dev/snake")).unwrap()));
+        server.serve(stream).unwrap();
+
+        client.join().unwrap();
+        server.debugger.unwrap()
+    }
+
+    #[test]
+    fn source_breakpoints_and_stack()
+    {
+        session(|client| {
+            let response = client.request("setBreakpoints", json!({
+                "source": { "path": "/home/dev/snake/snake.s" },
+                "breakpoints": [{ "line": 9 }, { "line": 4 }],
+            }));
+            let breakpoints = &response["body"]["breakpoints"];
+
+            assert_eq!(true, breakpoints[0]["verified"]);
+            assert_eq!("0x060D", breakpoints[0]["instructionReference"]);
+            assert_eq!(false, breakpoints[1]["verified"]);
+
+            client.request("configurationDone", json!({}));
+            client.event("stopped");
+
+            client.request("continue", json!({}));
+            assert_eq!("breakpoint", client.event("stopped")["reason"]);
+
+            let frames = client.request("stackTrace", json!({ "threadId": THREAD_ID }))["body"]["stackFrames"].clone();
+            let names: Vec<&str> = frames.as_array().unwrap().iter().map(|frame| frame["name"].as_str().unwrap()).collect();
+
+            assert_eq!(vec!["inner", "sub+2", "main"], names);
+            assert_eq!(7, frames[1]["line"]);
+            assert_eq!("/home/dev/snake/snake.s", frames[1]["source"]["path"]);
+        });
+    }
+
+    #[test]
+    fn variables()
+    {
+        session(|client| {
+            client.request("next", json!({}));
+            assert_eq!("step", client.event("stopped")["reason"]);
+
+            client.request("stepIn", json!({}));
+            assert_eq!("step", client.event("stopped")["reason"]);
+
+            let scopes = client.request("scopes", json!({ "frameId": 0 }))["body"]["scopes"].clone();
+            assert_eq!("Flags", scopes[1]["name"]);
+
+            let registers = client.request("variables", json!({ "variablesReference": REGISTERS_REFERENCE }))["body"]["variables"].clone();
+            assert_eq!("X", registers[1]["name"]);
+            assert_eq!("$01", registers[1]["value"]);
+            assert_eq!("$0604", registers[4]["value"]);
+
+            let flags = client.request("variables", json!({ "variablesReference": FLAGS_REFERENCE }))["body"]["variables"].clone();
+            let values: Vec<&str> = flags.as_array().unwrap().iter().map(|flag| flag["value"].as_str().unwrap()).collect();
+
+            // LDA #$80 set N and the INX cleared it
+            assert_eq!(vec!["false", "false", "false", "true", "false", "false"], values);
+        });
+    }
+
+    #[test]
+    fn step_out_can_be_paused()
+    {
+        session(|client| {
+            // Nothing to return from at the top level, so this runs the main loop
+            client.request("stepOut", json!({}));
+            client.request("pause", json!({}));
+            assert_eq!("pause", client.event("stopped")["reason"]);
+
+            let frames = client.request("stackTrace", json!({ "threadId": THREAD_ID }))["body"]["stackFrames"].clone();
+            assert!(frames[0]["name"].as_str().unwrap().starts_with("main+"));
+        });
+    }
+
+    #[test]
+    fn instruction_breakpoints_and_pause()
+    {
+        let debugger = session(|client| {
+            let response = client.request("setInstructionBreakpoints", json!({
+                "breakpoints": [{ "instructionReference": "0x0600", "offset": 3 }],
+            }));
+            assert_eq!(true, response["body"]["breakpoints"][0]["verified"]);
+
+            client.request("continue", json!({}));
+            assert_eq!("breakpoint", client.event("stopped")["reason"]);
+
+            client.request("setInstructionBreakpoints", json!({ "breakpoints": [] }));
+            client.request("continue", json!({}));
+            client.request("pause", json!({}));
+            assert_eq!("pause", client.event("stopped")["reason"]);
+
+            assert_eq!(false, client.request("readMemory", json!({}))["success"]);
+        });
+
+        assert_eq!(0, debugger.breakpoints().count());
+    }
+}
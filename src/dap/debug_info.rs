@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Source lines and labels from a ca65/ld65 debug info file (`ld65 --dbgfile`)
+#[derive(Debug, Default)]
+pub struct DebugInfo
+{
+    files:  HashMap<u32, PathBuf>, // Absolute
+    lines:  Vec<Line>,
+    labels: Vec<(u16, String)>, // Sorted by address
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line
+{
+    pub file: PathBuf,
+    pub line: u32,
+    pub addr: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Span
+{
+    seg:   u32,
+    start: u32,
+}
+
+impl DebugInfo
+{
+    /// File names are relative to the directory of the debug info file
+    pub fn from_file(path: &str) -> Result<DebugInfo, String>
+    {
+        let text = match std::fs::read_to_string(path)
+        {
+            Ok(text) => text,
+            Err(err) => return Err(format!("Unable to read file '{0}', ({1})", path, err))
+        };
+
+        let dir = Path::new(path).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+        match std::path::absolute(dir)
+        {
+            Ok(dir) => Self::parse(&text, &dir),
+            Err(err) => Err(format!("Unable to resolve the directory of '{0}', ({1})", path, err))
+        }
+    }
+
+    /// `dir` is the absolute directory relative file names are resolved against
+    pub fn parse(text: &str, dir: &Path) -> Result<DebugInfo, String>
+    {
+        let mut info = DebugInfo::default();
+
+        let mut segments: HashMap<u32, u32> = HashMap::new();
+        let mut spans: HashMap<u32, Span> = HashMap::new();
+        let mut lines: Vec<(u32, u32, Vec<u32>)> = vec![]; // File, line and spans, resolved once every span is known
+        let mut symbols: Vec<(String, u32)> = vec![];
+
+        for (number, line) in text.lines().enumerate()
+        {
+            let (kind, fields) = match line.split_once('\t')
+            {
+                Some((kind, fields)) => (kind, parse_fields(fields)),
+                None => continue,
+            };
+
+            let error = |field: &str| format!("Line {}: missing or invalid '{}' in '{}'", number + 1, field, kind);
+            let number_field = |field: &str| fields.get(field).and_then(|value| parse_number(value)).ok_or_else(|| error(field));
+
+            match kind
+            {
+                "file" => {
+                    let name = fields.get("name").ok_or_else(|| error("name"))?;
+                    info.files.insert(number_field("id")?, normalize(&dir.join(name.trim_matches('"'))));
+                },
+                "seg" => {
+                    segments.insert(number_field("id")?, number_field("start")?);
+                },
+                "span" => {
+                    spans.insert(number_field("id")?, Span { seg: number_field("seg")?, start: number_field("start")? });
+                },
+                "line" => {
+                    // Lines without code have no span
+                    if let Some(span_ids) = fields.get("span")
+                    {
+                        let span_ids = span_ids.split('+').filter_map(parse_number).collect();
+                        lines.push((number_field("file")?, number_field("line")?, span_ids));
+                    }
+                },
+                "sym" if fields.get("type").map(String::as_str) == Some("lab") => {
+                    let name = fields.get("name").ok_or_else(|| error("name"))?;
+                    symbols.push((name.trim_matches('"').to_string(), number_field("val")?));
+                },
+                _ => {},
+            }
+        }
+
+        for (file, line, span_ids) in lines
+        {
+            let file = match info.files.get(&file)
+            {
+                Some(name) => name.clone(),
+                None => continue,
+            };
+
+            for span in span_ids.iter().filter_map(|id| spans.get(id))
+            {
+                if let Some(start) = segments.get(&span.seg)
+                {
+                    info.lines.push(Line { file: file.clone(), line, addr: (start + span.start) as u16 });
+                }
+            }
+        }
+
+        info.labels = symbols.into_iter().map(|(name, value)| (value as u16, name)).collect();
+        info.labels.sort();
+
+        Ok(info)
+    }
+
+    /// Addresses of the code generated for a line, a relative `path` is taken from the current directory
+    pub fn addresses(&self, path: &str, line: u32) -> Vec<u16>
+    {
+        let path = match std::path::absolute(path)
+        {
+            Ok(path) => normalize(&path),
+            Err(_) => return vec![],
+        };
+
+        let mut addrs: Vec<u16> = self.lines.iter()
+            .filter(|info| info.line == line && info.file == path)
+            .map(|info| info.addr)
+            .collect();
+
+        addrs.sort();
+        addrs.dedup();
+        addrs
+    }
+
+    /// Source line whose code starts at `addr`, or the closest one before
+    pub fn line(&self, addr: u16) -> Option<&Line>
+    {
+        self.lines.iter()
+            .filter(|info| info.addr <= addr)
+            .max_by_key(|info| info.addr)
+    }
+
+    /// Closest label at or before `addr`, with the distance from it
+    pub fn label(&self, addr: u16) -> Option<(&str, u16)>
+    {
+        self.labels.iter()
+            .rev()
+            .find(|(label_addr, _)| *label_addr <= addr)
+            .map(|(label_addr, name)| (name.as_str(), addr - label_addr))
+    }
+}
+
+// `key=value,key="value, with comma",...`
+fn parse_fields(text: &str) -> HashMap<String, String>
+{
+    let mut fields = HashMap::new();
+    let mut quoted = false;
+    let mut start = 0;
+
+    for (index, c) in text.char_indices().chain(std::iter::once((text.len(), ',')))
+    {
+        match c
+        {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                if let Some((key, value)) = text[start..index].split_once('=')
+                {
+                    fields.insert(key.to_string(), value.to_string());
+                }
+
+                start = index + 1;
+            },
+            _ => {},
+        }
+    }
+
+    fields
+}
+
+fn parse_number(text: &str) -> Option<u32>
+{
+    match text.strip_prefix("0x")
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+// Removes `.` and `..` without going to the file system, the files may not exist here
+fn normalize(path: &Path) -> PathBuf
+{
+    let mut normalized = PathBuf::new();
+
+    for component in path.components()
+    {
+        match component
+        {
+            Component::CurDir => {},
+            Component::ParentDir => { normalized.pop(); },
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const DEBUG_INFO: &str = "\
+version\tmajor=2,minor=0
+info\tcsym=0,file=2,lib=0,line=4,mod=1,scope=1,seg=2,span=4,sym=2,type=0
+file\tid=0,name=\"src/main.s\",size=200,mtime=0x60000000,mod=0
+file\tid=1,name=\"src/macros, with comma.inc\",size=10,mtime=0x60000000,mod=0
+line\tid=0,file=0,line=3
+line\tid=1,file=0,line=5,span=0
+line\tid=2,file=0,line=6,span=1+2
+line\tid=3,file=0,line=9,span=3
+seg\tid=0,name=\"CODE\",start=0x00C000,size=0x0010,addrsize=absolute,type=ro,oname=\"game.nes\",ooffs=16
+seg\tid=1,name=\"VECTORS\",start=0x00FFFA,size=0x0006,addrsize=absolute,type=ro,oname=\"game.nes\",ooffs=16400
+span\tid=0,seg=0,start=0,size=2
+span\tid=1,seg=0,start=2,size=3
+span\tid=2,seg=0,start=8,size=1
+span\tid=3,seg=1,start=0,size=2
+sym\tid=0,name=\"reset\",addrsize=absolute,scope=0,def=1,val=0xC000,seg=0,type=lab
+sym\tid=1,name=\"loop\",addrsize=absolute,scope=0,def=2,val=0xC002,seg=0,type=lab
+sym\tid=2,name=\"PPUCTRL\",addrsize=absolute,scope=0,def=2,val=0x2000,type=equ
+";
+
+    #[test]
+    fn line_addresses()
+    {
+        let info = DebugInfo::parse(DEBUG_INFO, Path::new("/home/dev/game")).unwrap();
+
+        assert_eq!(vec![0xC000], info.addresses("/home/dev/game/src/main.s", 5));
+        assert_eq!(vec![0xC002, 0xC008], info.addresses("/home/dev/game/./lib/../src/main.s", 6));
+        assert_eq!(vec![0xFFFA], info.addresses("/home/dev/game/src/main.s", 9));
+        assert!(info.addresses("/home/dev/game/src/main.s", 3).is_empty());
+        assert!(info.addresses("/home/dev/other/src/main.s", 5).is_empty());
+        assert!(info.addresses("main.s", 5).is_empty());
+    }
+
+    #[test]
+    fn address_lines()
+    {
+        let info = DebugInfo::parse(DEBUG_INFO, Path::new("/home/dev/game")).unwrap();
+
+        assert_eq!(5, info.line(0xC001).unwrap().line);
+        assert_eq!(6, info.line(0xC002).unwrap().line);
+        assert_eq!(Path::new("/home/dev/game/src/main.s"), info.line(0xC002).unwrap().file);
+        assert_eq!(None, info.line(0x8000));
+    }
+
+    #[test]
+    fn labels()
+    {
+        let info = DebugInfo::parse(DEBUG_INFO, Path::new("/home/dev/game")).unwrap();
+
+        assert_eq!(Some(("reset", 1)), info.label(0xC001));
+        assert_eq!(Some(("loop", 6)), info.label(0xC008));
+        assert_eq!(None, info.label(0x2000)); // Constants are not labels
+    }
+
+    #[test]
+    fn quoted_comma()
+    {
+        let info = DebugInfo::parse(DEBUG_INFO, Path::new("/home/dev/game")).unwrap();
+
+        assert_eq!(Some(&PathBuf::from("/home/dev/game/src/macros, with comma.inc")), info.files.get(&1));
+    }
+}
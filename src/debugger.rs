@@ -0,0 +1,388 @@
+use std::collections::BTreeSet;
+
+use crate::cpu::{Cpu, CpuError, Access, WatchHit, disasm::{self, Instruction}};
+
+const JSR_OPCODE: u8 = 0x20;
+const RTS_OPCODE: u8 = 0x60;
+const RTI_OPCODE: u8 = 0x40;
+
+const JSR_LENGTH: u16 = 3;
+
+const STACK_START: u16 = 0x0100;
+const STACK_END: u16   = 0x01FF;
+
+// Longest instruction, used to find where to start disassembling before an address
+const MAX_INSTRUCTION_LENGTH: u16 = 3;
+
+/// Why the execution stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason
+{
+    Step,
+    Breakpoint(u16),
+    Watchpoint(WatchHit),
+    Error(CpuError),
+    Limit, // The maximum number of instructions was executed
+}
+
+/// Where a step over or a step out ends, kept across calls to `Debugger::run_to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepTarget
+{
+    Return { addr: u16, sp: u8 },   // Back from the JSR being stepped over
+    Out { sp: u8, returned: bool }, // Past the RTS or RTI of the current subroutine
+}
+
+impl StepTarget
+{
+    // Sees the state before every instruction
+    fn reached(&mut self, debugger: &Debugger) -> bool
+    {
+        let registers = debugger.cpu.registers();
+
+        match self
+        {
+            // The stack pointer tells a recursive call from the return of the first one
+            StepTarget::Return { addr, sp } => registers.pc == *addr && registers.sp == *sp,
+            StepTarget::Out { sp, returned } => {
+                let done = *returned;
+
+                let opcode = debugger.cpu.memory.peek(registers.pc);
+                *returned = (opcode == RTS_OPCODE || opcode == RTI_OPCODE) && registers.sp >= *sp;
+
+                done
+            },
+        }
+    }
+}
+
+/// Wraps a CPU to run it under breakpoints and watchpoints
+pub struct Debugger
+{
+    pub cpu: Cpu,
+    breakpoints: BTreeSet<u16>,
+}
+
+impl Debugger
+{
+    pub fn new(cpu: Cpu) -> Debugger
+    {
+        Debugger {
+            cpu,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn pc(&self) -> u16
+    {
+        self.cpu.registers().pc
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16)
+    {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Returns false if there was no breakpoint at `addr`
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool
+    {
+        self.breakpoints.remove(&addr)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_
+    {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, addr: u16, access: Access)
+    {
+        self.cpu.memory.add_watchpoint(addr, access);
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: u16, access: Access)
+    {
+        self.cpu.memory.remove_watchpoint(addr, access);
+    }
+
+    pub fn watchpoints(&self) -> &[(u16, Access)]
+    {
+        self.cpu.memory.watchpoints()
+    }
+
+    /// Executes a single instruction
+    pub fn step(&mut self) -> StopReason
+    {
+        match self.cpu.step()
+        {
+            Ok(_) => match self.cpu.memory.take_watch_hit()
+            {
+                Some(hit) => StopReason::Watchpoint(hit),
+                None => StopReason::Step,
+            },
+            Err(err) => StopReason::Error(err),
+        }
+    }
+
+    /// Steps, but runs a whole subroutine when the instruction is a JSR
+    pub fn step_over(&mut self, limit: u64) -> StopReason
+    {
+        match self.step_over_target()
+        {
+            Some(mut target) => self.run_to(&mut target, limit),
+            None => self.step(),
+        }
+    }
+
+    /// Runs until the current subroutine or interrupt handler returns
+    pub fn step_out(&mut self, limit: u64) -> StopReason
+    {
+        let mut target = self.step_out_target();
+        self.run_to(&mut target, limit)
+    }
+
+    /// Target of a step over, None when the instruction at PC is not a JSR and a single step will do
+    pub fn step_over_target(&self) -> Option<StepTarget>
+    {
+        let pc = self.pc();
+
+        match self.cpu.memory.peek(pc)
+        {
+            JSR_OPCODE => Some(StepTarget::Return { addr: pc.wrapping_add(JSR_LENGTH), sp: self.cpu.registers().sp }),
+            _ => None,
+        }
+    }
+
+    pub fn step_out_target(&self) -> StepTarget
+    {
+        StepTarget::Out { sp: self.cpu.registers().sp, returned: false }
+    }
+
+    /// Runs until `target` is reached (reported as a step), a breakpoint, a watchpoint, an error or `limit`
+    /// instructions, a target stopped by the limit can be resumed by another call
+    pub fn run_to(&mut self, target: &mut StepTarget, limit: u64) -> StopReason
+    {
+        self.run_until(limit, |debugger| target.reached(debugger))
+    }
+
+    /// Runs until a breakpoint, a watchpoint, an error or `limit` instructions
+    pub fn resume(&mut self, limit: u64) -> StopReason
+    {
+        self.run_until(limit, |_| false)
+    }
+
+    // `done` sees the state before every instruction, but cannot stop before the first one
+    fn run_until<F>(&mut self, limit: u64, mut done: F) -> StopReason
+        where F: FnMut(&Debugger) -> bool
+    {
+        done(self);
+
+        for _ in 0..limit
+        {
+            let stop = self.step();
+
+            if stop != StopReason::Step
+            {
+                return stop;
+            }
+
+            if done(self)
+            {
+                return StopReason::Step;
+            }
+
+            let pc = self.pc();
+
+            if self.breakpoints.contains(&pc)
+            {
+                return StopReason::Breakpoint(pc);
+            }
+        }
+
+        StopReason::Limit
+    }
+
+    /// Addresses of the JSR instructions the current code was called from, innermost first
+    pub fn backtrace(&self) -> Vec<u16>
+    {
+        let memory = &self.cpu.memory;
+        let mut calls = vec![];
+
+        // The stack also holds pushed data and interrupt frames, a pair of bytes is taken
+        // for a return address when it points to the last byte of a JSR
+        let mut addr = STACK_START + self.cpu.registers().sp as u16 + 1;
+
+        while addr < STACK_END
+        {
+            let pushed = u16::from_le_bytes([memory.peek(addr), memory.peek(addr + 1)]);
+            let call = pushed.wrapping_sub(JSR_LENGTH - 1);
+
+            if memory.peek(call) == JSR_OPCODE
+            {
+                calls.push(call);
+                addr += 2;
+            }
+            else
+            {
+                addr += 1;
+            }
+        }
+
+        calls
+    }
+
+    /// Disassembles up to `before` instructions ahead of `addr` and `after` from it (included)
+    pub fn disassemble_around(&self, addr: u16, before: usize, after: usize) -> Vec<Instruction>
+    {
+        let memory = &self.cpu.memory;
+        let end = addr.saturating_add(after as u16 * MAX_INSTRUCTION_LENGTH);
+
+        // Code can be decoded from several offsets, take the furthest one that lines up with `addr`
+        let mut instructions = (1..=before as u16 * MAX_INSTRUCTION_LENGTH)
+            .rev()
+            .filter(|&distance| distance <= addr)
+            .map(|distance| disasm::disassemble_memory(memory, addr - distance, end))
+            .find(|instructions| instructions.iter().any(|instruction| instruction.addr == addr))
+            .unwrap_or_else(|| disasm::disassemble_memory(memory, addr, end));
+
+        let position = instructions.iter().position(|instruction| instruction.addr == addr).unwrap_or(0);
+        instructions.drain(..position.saturating_sub(before));
+        instructions.truncate(before.min(position) + after);
+
+        instructions
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::cpu::{asm, RegisterSnapshot};
+    use super::*;
+
+    fn debugger(source: &str) -> Debugger
+    {
+        let assembly = asm::assemble(source).unwrap();
+        let mut cpu = Cpu::flat();
+
+        cpu.load_at(assembly.origin, assembly.bytes).unwrap();
+        cpu.set_registers(RegisterSnapshot { a: 0, x: 0, y: 0, sp: 0xFD, pc: assembly.origin, p: 0 });
+
+        Debugger::new(cpu)
+    }
+
+    const PROGRAM: &str = "
+        .org $0600
+        main:   jsr sub
+                inx
+        loop:   jmp loop
+        sub:    lda #1
+                sta $10
+                jsr inner
+                rts
+        inner:  iny
+                rts
+    ";
+
+    #[test]
+    fn breakpoint()
+    {
+        let mut debugger = debugger(PROGRAM);
+        debugger.add_breakpoint(0x0603);
+
+        assert_eq!(StopReason::Breakpoint(0x0603), debugger.resume(100));
+        assert_eq!(StopReason::Limit, debugger.resume(100));
+
+        assert!(debugger.remove_breakpoint(0x0603));
+        assert!(!debugger.remove_breakpoint(0x0603));
+    }
+
+    #[test]
+    fn write_watchpoint()
+    {
+        let mut debugger = debugger(PROGRAM);
+        debugger.add_watchpoint(0x0010, Access::Write);
+
+        assert_eq!(
+            StopReason::Watchpoint(WatchHit { addr: 0x0010, access: Access::Write, value: 1 }),
+            debugger.resume(100)
+        );
+        assert_eq!(0x060B, debugger.pc());
+    }
+
+    #[test]
+    fn step_over()
+    {
+        let mut debugger = debugger(PROGRAM);
+
+        assert_eq!(StopReason::Step, debugger.step_over(100));
+        assert_eq!(0x0603, debugger.pc());
+        assert_eq!(0x01, debugger.cpu.registers().y);
+
+        assert_eq!(StopReason::Step, debugger.step_over(100));
+        assert_eq!(0x0604, debugger.pc());
+    }
+
+    #[test]
+    fn step_out()
+    {
+        let mut debugger = debugger(PROGRAM);
+        debugger.step();
+
+        assert_eq!(StopReason::Step, debugger.step_out(100));
+        assert_eq!(0x0603, debugger.pc());
+        assert_eq!(0x01, debugger.cpu.registers().y);
+    }
+
+    #[test]
+    fn step_over_resumes_after_the_limit()
+    {
+        let mut debugger = debugger(PROGRAM);
+
+        let mut target = debugger.step_over_target().unwrap();
+        let mut stops = vec![];
+
+        while stops.last() != Some(&StopReason::Step)
+        {
+            stops.push(debugger.run_to(&mut target, 1));
+        }
+
+        assert_eq!(7, stops.len());
+        assert_eq!(0x0603, debugger.pc());
+
+        debugger.step();
+        assert_eq!(None, debugger.step_over_target());
+    }
+
+    #[test]
+    fn step_out_resumes_after_the_limit()
+    {
+        let mut debugger = debugger(PROGRAM);
+        debugger.step();
+
+        let mut target = debugger.step_out_target();
+        assert_eq!(StopReason::Limit, debugger.run_to(&mut target, 5));
+        assert_eq!(StopReason::Step, debugger.run_to(&mut target, 5));
+        assert_eq!(0x0603, debugger.pc());
+    }
+
+    #[test]
+    fn backtrace()
+    {
+        let mut debugger = debugger(PROGRAM);
+        debugger.add_breakpoint(0x060F);
+
+        assert_eq!(StopReason::Breakpoint(0x060F), debugger.resume(100));
+        assert_eq!(vec![0x060B, 0x0600], debugger.backtrace());
+
+        debugger.step_out(100);
+        assert_eq!(vec![0x0600], debugger.backtrace());
+    }
+
+    #[test]
+    fn disassemble_around()
+    {
+        let debugger = debugger(PROGRAM);
+        let addrs: Vec<u16> = debugger.disassemble_around(0x060B, 2, 2).iter().map(|i| i.addr).collect();
+
+        assert_eq!(vec![0x0607, 0x0609, 0x060B, 0x060E], addrs);
+    }
+}
@@ -0,0 +1,605 @@
+use std::io::{self, Read, Write, ErrorKind as IoErrorKind};
+use std::net::TcpStream;
+
+use crate::cpu::{Access, ErrorKind, RegisterSnapshot};
+use crate::debugger::{Debugger, StopReason};
+
+const INTERRUPT: u8 = 0x03;
+
+// Stop signals
+const SIGINT: u8  = 2;
+const SIGILL: u8  = 4;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+// Instructions run between two checks for an interrupt from the client
+const RESUME_CHUNK: u64 = 10_000;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rust-nes.6502">
+    <reg name="a" bitsize="8" regnum="0"/>
+    <reg name="x" bitsize="8"/>
+    <reg name="y" bitsize="8"/>
+    <reg name="sp" bitsize="8"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="p" bitsize="8"/>
+  </feature>
+</target>
+"#;
+
+enum Packet
+{
+    Command(String),
+    Interrupt,
+}
+
+/// GDB remote serial protocol server, registers are exposed as `a x y sp pc p` (see `TARGET_XML`)
+pub struct GdbStub
+{
+    debugger: Debugger,
+    no_ack:   bool,
+}
+
+/// Buffered connection, so the stream can be polled for an interrupt while running
+struct Connection
+{
+    stream: TcpStream,
+    buffer: Vec<u8>,
+}
+
+impl GdbStub
+{
+    pub fn new(debugger: Debugger) -> GdbStub
+    {
+        GdbStub {
+            debugger,
+            no_ack: false,
+        }
+    }
+
+    pub fn debugger(&mut self) -> &mut Debugger
+    {
+        &mut self.debugger
+    }
+
+    /// Serves a client until it detaches, kills the session or disconnects
+    pub fn serve(&mut self, stream: TcpStream) -> io::Result<()>
+    {
+        let mut connection = Connection { stream, buffer: vec![] };
+        self.no_ack = false;
+
+        loop
+        {
+            let command = match connection.read_packet(self.no_ack)?
+            {
+                Some(Packet::Command(command)) => command,
+                Some(Packet::Interrupt) => {
+                    connection.send(&format!("S{:02x}", SIGINT))?;
+                    continue;
+                },
+                None => return Ok(()),
+            };
+
+            let reply = match command.as_str()
+            {
+                "c" => self.resume(&mut connection)?,
+                "D" => {
+                    connection.send("OK")?;
+                    return Ok(());
+                },
+                "k" => return Ok(()),
+                "QStartNoAckMode" => {
+                    connection.send("OK")?;
+                    self.no_ack = true;
+                    continue;
+                },
+                _ => self.execute(&command),
+            };
+
+            connection.send(&reply)?;
+        }
+    }
+
+    // Every command but the ones changing the session, empty replies stand for unsupported
+    fn execute(&mut self, command: &str) -> String
+    {
+        // Queries are named, other commands are a single letter followed by hexadecimal arguments
+        let name_length = match command.chars().next()
+        {
+            Some('q' | 'Q' | 'v') => command.find([':', ',']).unwrap_or(command.len()),
+            Some(_) => 1,
+            None => 0,
+        };
+        let (name, arguments) = command.split_at(name_length);
+
+        match name
+        {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => encode_registers(self.debugger.cpu.registers()),
+            "G" => match decode_hex(arguments).and_then(|bytes| decode_registers(&bytes))
+            {
+                Some(registers) => {
+                    self.debugger.cpu.set_registers(registers);
+                    String::from("OK")
+                },
+                None => String::from("E01"),
+            },
+            "p" => match usize::from_str_radix(arguments, 16).ok().and_then(|index| register_bytes(self.debugger.cpu.registers(), index))
+            {
+                Some(bytes) => encode_hex(&bytes),
+                None => String::from("E01"),
+            },
+            "P" => self.write_register(arguments).unwrap_or_else(|| String::from("E01")),
+            "m" => match parse_range(arguments)
+            {
+                Some((addr, length)) => {
+                    let bytes: Vec<u8> = (0..length).map(|i| self.debugger.cpu.memory.peek(addr.wrapping_add(i))).collect();
+                    encode_hex(&bytes)
+                },
+                None => String::from("E01"),
+            },
+            "M" => self.write_memory(arguments).unwrap_or_else(|| String::from("E01")),
+            "s" => stop_reply(self.debugger.step()),
+            "Z" | "z" => self.set_point(name == "Z", arguments).unwrap_or_else(|| String::from("E01")),
+            "H" => String::from("OK"),
+            "qC" => String::from("QC1"),
+            "qfThreadInfo" => String::from("m1"),
+            "qsThreadInfo" => String::from("l"),
+            "qAttached" => String::from("1"),
+            "qSupported" => String::from("PacketSize=1000;qXfer:features:read+;QStartNoAckMode+"),
+            "qXfer" => read_target_xml(arguments).unwrap_or_else(|| String::from("E01")),
+            _ => String::new(),
+        }
+    }
+
+    fn resume(&mut self, connection: &mut Connection) -> io::Result<String>
+    {
+        loop
+        {
+            match self.debugger.resume(RESUME_CHUNK)
+            {
+                StopReason::Limit => {
+                    if connection.poll_interrupt()?
+                    {
+                        return Ok(format!("S{:02x}", SIGINT));
+                    }
+                },
+                stop => return Ok(stop_reply(stop)),
+            }
+        }
+    }
+
+    // `P<index>=<value>`
+    fn write_register(&mut self, arguments: &str) -> Option<String>
+    {
+        let (index, value) = arguments.split_once('=')?;
+        let index = usize::from_str_radix(index, 16).ok()?;
+        let value = decode_hex(value)?;
+
+        let mut bytes = registers_to_bytes(self.debugger.cpu.registers());
+        let offset = REGISTER_OFFSETS.get(index)?;
+
+        if value.len() != register_size(index)
+        {
+            return None;
+        }
+
+        bytes[*offset..*offset + value.len()].copy_from_slice(&value);
+        self.debugger.cpu.set_registers(decode_registers(&bytes)?);
+
+        Some(String::from("OK"))
+    }
+
+    // `M<addr>,<length>:<bytes>`
+    fn write_memory(&mut self, arguments: &str) -> Option<String>
+    {
+        let (range, data) = arguments.split_once(':')?;
+        let (addr, length) = parse_range(range)?;
+        let bytes = decode_hex(data)?;
+
+        if bytes.len() != length as usize
+        {
+            return None;
+        }
+
+        // Nothing is written when part of the range is not writable
+        self.debugger.cpu.memory.write_slice(addr, &bytes).ok()?;
+
+        Some(String::from("OK"))
+    }
+
+    // `Z<type>,<addr>,<kind>`, software and hardware breakpoints are the same here
+    fn set_point(&mut self, insert: bool, arguments: &str) -> Option<String>
+    {
+        let mut fields = arguments.split(',');
+        let kind = fields.next()?;
+        let addr = u16::from_str_radix(fields.next()?, 16).ok()?;
+
+        let accesses: &[Access] = match kind
+        {
+            "0" | "1" => {
+                if insert { self.debugger.add_breakpoint(addr) } else { self.debugger.remove_breakpoint(addr); }
+                &[]
+            },
+            "2" => &[Access::Write],
+            "3" => &[Access::Read],
+            "4" => &[Access::Read, Access::Write],
+            _ => return Some(String::new()),
+        };
+
+        for access in accesses
+        {
+            if insert { self.debugger.add_watchpoint(addr, *access) } else { self.debugger.remove_watchpoint(addr, *access) }
+        }
+
+        Some(String::from("OK"))
+    }
+}
+
+impl Connection
+{
+    fn read_byte(&mut self) -> io::Result<Option<u8>>
+    {
+        if self.buffer.is_empty()
+        {
+            let mut chunk = [0; 1024];
+            let length = self.stream.read(&mut chunk)?;
+
+            if length == 0 { return Ok(None) }
+
+            self.buffer.extend_from_slice(&chunk[..length]);
+        }
+
+        Ok(Some(self.buffer.remove(0)))
+    }
+
+    // Acknowledges the packet unless the client asked not to
+    fn read_packet(&mut self, no_ack: bool) -> io::Result<Option<Packet>>
+    {
+        loop
+        {
+            match self.read_byte()?
+            {
+                None => return Ok(None),
+                Some(INTERRUPT) => return Ok(Some(Packet::Interrupt)),
+                Some(b'$') => {},
+                Some(_) => continue, // Acknowledgements from the client
+            }
+
+            let mut data = vec![];
+
+            loop
+            {
+                match self.read_byte()?
+                {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                }
+            }
+
+            let checksum = [self.read_byte()?, self.read_byte()?];
+            let valid = match checksum
+            {
+                [Some(high), Some(low)] => std::str::from_utf8(&[high, low]).ok()
+                    .and_then(|text| u8::from_str_radix(text, 16).ok()) == Some(checksum_of(&data)),
+                _ => return Ok(None),
+            };
+
+            if !no_ack
+            {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+
+            if valid
+            {
+                return Ok(Some(Packet::Command(unescape(&data))));
+            }
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()>
+    {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())
+    }
+
+    /// Checks without blocking whether the client sent an interrupt
+    fn poll_interrupt(&mut self) -> io::Result<bool>
+    {
+        self.stream.set_nonblocking(true)?;
+
+        let mut chunk = [0; 1024];
+        let result = self.stream.read(&mut chunk);
+
+        self.stream.set_nonblocking(false)?;
+
+        match result
+        {
+            Ok(length) => self.buffer.extend_from_slice(&chunk[..length]),
+            Err(err) if err.kind() == IoErrorKind::WouldBlock => {},
+            Err(err) => return Err(err),
+        }
+
+        match self.buffer.iter().position(|&byte| byte == INTERRUPT)
+        {
+            Some(position) => {
+                self.buffer.remove(position);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+fn stop_reply(stop: StopReason) -> String
+{
+    match stop
+    {
+        StopReason::Watchpoint(hit) => {
+            let kind = if hit.access == Access::Read { "rwatch" } else { "watch" };
+            format!("T{:02x}{}:{:04x};", SIGTRAP, kind, hit.addr)
+        },
+        StopReason::Error(err) if err.kind == ErrorKind::UnsupportedOpcode => format!("S{:02x}", SIGILL),
+        StopReason::Error(_) => format!("S{:02x}", SIGSEGV),
+        _ => format!("S{:02x}", SIGTRAP),
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8
+{
+    data.iter().fold(0, |sum: u8, byte| sum.wrapping_add(*byte))
+}
+
+// `}` escapes the next byte, xored with 0x20
+fn unescape(data: &[u8]) -> String
+{
+    let mut bytes = vec![];
+    let mut escaped = false;
+
+    for &byte in data
+    {
+        match (escaped, byte)
+        {
+            (false, b'}') => escaped = true,
+            (true, _) => {
+                bytes.push(byte ^ 0x20);
+                escaped = false;
+            },
+            _ => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn encode_hex(bytes: &[u8]) -> String
+{
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>>
+{
+    if !text.len().is_multiple_of(2)
+    {
+        return None;
+    }
+
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// `<addr>,<length>`
+fn parse_range(text: &str) -> Option<(u16, u16)>
+{
+    let (addr, length) = text.split_once(',')?;
+
+    Some((u16::from_str_radix(addr, 16).ok()?, u16::from_str_radix(length, 16).ok()?))
+}
+
+// Position of each register in the `g` packet, in `TARGET_XML` order
+const REGISTER_OFFSETS: [usize; 6] = [0, 1, 2, 3, 4, 6];
+
+fn register_size(index: usize) -> usize
+{
+    if index == 4 { 2 } else { 1 }
+}
+
+fn registers_to_bytes(registers: RegisterSnapshot) -> Vec<u8>
+{
+    let pc = registers.pc.to_le_bytes();
+
+    vec![registers.a, registers.x, registers.y, registers.sp, pc[0], pc[1], registers.p]
+}
+
+fn encode_registers(registers: RegisterSnapshot) -> String
+{
+    encode_hex(&registers_to_bytes(registers))
+}
+
+fn decode_registers(bytes: &[u8]) -> Option<RegisterSnapshot>
+{
+    match bytes
+    {
+        &[a, x, y, sp, pc_low, pc_high, p] => Some(RegisterSnapshot { a, x, y, sp, pc: u16::from_le_bytes([pc_low, pc_high]), p }),
+        _ => None,
+    }
+}
+
+fn register_bytes(registers: RegisterSnapshot, index: usize) -> Option<Vec<u8>>
+{
+    let offset = *REGISTER_OFFSETS.get(index)?;
+
+    Some(registers_to_bytes(registers)[offset..offset + register_size(index)].to_vec())
+}
+
+// `:features:read:target.xml:<offset>,<length>`
+fn read_target_xml(arguments: &str) -> Option<String>
+{
+    let range = arguments.strip_prefix(":features:read:target.xml:")?;
+    let (offset, length) = range.split_once(',')?;
+    let offset = usize::from_str_radix(offset, 16).ok()?;
+    let length = usize::from_str_radix(length, 16).ok()?;
+
+    let start = offset.min(TARGET_XML.len());
+    let end = (offset + length).min(TARGET_XML.len());
+
+    Some(format!("{}{}", if end == TARGET_XML.len() { 'l' } else { 'm' }, &TARGET_XML[start..end]))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::cpu::{Cpu, asm};
+    use super::*;
+
+    const PROGRAM: &str = "
+        .org $0600
+        start:  lda #$42
+                sta $10
+        loop:   inx
+                jmp loop
+    ";
+
+    struct Client
+    {
+        stream: TcpStream,
+    }
+
+    impl Client
+    {
+        // Sends a packet and returns the reply, acknowledgements included
+        fn request(&mut self, command: &str) -> String
+        {
+            let packet = format!("${}#{:02x}", command, checksum_of(command.as_bytes()));
+            self.stream.write_all(packet.as_bytes()).unwrap();
+            self.read_reply()
+        }
+
+        fn read_reply(&mut self) -> String
+        {
+            let mut reply = vec![];
+            let mut byte = [0];
+
+            while !reply.ends_with(b"#") || !reply.contains(&b'$')
+            {
+                self.stream.read_exact(&mut byte).unwrap();
+                reply.push(byte[0]);
+            }
+
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum).unwrap();
+
+            let reply = String::from_utf8(reply).unwrap();
+            reply[..reply.len() - 1].to_string()
+        }
+    }
+
+    // Runs a stub on a local socket, `script` is the client side
+    fn session<F>(script: F) -> Debugger
+        where F: FnOnce(&mut Client) + Send + 'static
+    {
+        let assembly = asm::assemble(PROGRAM).unwrap();
+        let mut cpu = Cpu::flat();
+        cpu.load_at(assembly.origin, assembly.bytes).unwrap();
+        cpu.set_registers(RegisterSnapshot { a: 0, x: 0, y: 0, sp: 0xFD, pc: assembly.origin, p: 0x04 });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut client = Client { stream: TcpStream::connect(addr).unwrap() };
+            script(&mut client);
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut stub = GdbStub::new(Debugger::new(cpu));
+        stub.serve(stream).unwrap();
+
+        client.join().unwrap();
+        stub.debugger
+    }
+
+    #[test]
+    fn registers()
+    {
+        let debugger = session(|client| {
+            assert_eq!("+$S05", client.request("?"));
+            assert_eq!("+$000000fd000604", client.request("g"));
+            assert_eq!("+$0006", client.request("p4"));
+            assert_eq!("+$OK", client.request("P0=7f"));
+            assert_eq!("+$OK", client.request("G01020380000605"));
+            assert_eq!("+$E01", client.request("G0102"));
+            assert_eq!("+$OK", client.request("D"));
+        });
+
+        assert_eq!(RegisterSnapshot { a: 1, x: 2, y: 3, sp: 0x80, pc: 0x0600, p: 0x05 }, debugger.cpu.registers());
+    }
+
+    #[test]
+    fn memory()
+    {
+        let debugger = session(|client| {
+            assert_eq!("+$OK", client.request("QStartNoAckMode"));
+            assert_eq!("$a94285", client.request("m600,3"));
+            assert_eq!("$OK", client.request("M10,2:beef"));
+            assert_eq!("$E01", client.request("Mffff,2:0000"));
+            assert_eq!("$OK", client.request("D"));
+        });
+
+        assert_eq!(0xBE, debugger.cpu.memory.read(0x10));
+        assert_eq!(0xEF, debugger.cpu.memory.read(0x11));
+    }
+
+    #[test]
+    fn step_breakpoint_and_watchpoint()
+    {
+        let debugger = session(|client| {
+            assert_eq!("+$S05", client.request("s"));
+            assert_eq!("+$OK", client.request("Z2,10,1"));
+            assert_eq!("+$T05watch:0010;", client.request("c"));
+            assert_eq!("+$OK", client.request("z2,10,1"));
+            assert_eq!("+$OK", client.request("Z0,605,1"));
+            assert_eq!("+$S05", client.request("c"));
+            assert_eq!("+$S05", client.request("c"));
+
+            // No reply to a kill
+            let packet = format!("$k#{:02x}", checksum_of(b"k"));
+            client.stream.write_all(packet.as_bytes()).unwrap();
+        });
+
+        assert_eq!(0x0605, debugger.pc());
+        assert_eq!(0x02, debugger.cpu.registers().x);
+    }
+
+    #[test]
+    fn interrupt_while_running()
+    {
+        session(|client| {
+            let packet = format!("$c#{:02x}", checksum_of(b"c"));
+            client.stream.write_all(packet.as_bytes()).unwrap();
+            client.stream.write_all(&[INTERRUPT]).unwrap();
+
+            assert_eq!("+$S02", client.read_reply());
+            assert_eq!("+$OK", client.request("D"));
+        });
+    }
+
+    #[test]
+    fn target_description()
+    {
+        session(|client| {
+            assert!(client.request("qSupported:multiprocess+").contains("qXfer:features:read+"));
+            assert_eq!("+$m<?xml", client.request("qXfer:features:read:target.xml:0,5"));
+            assert!(client.request("qXfer:features:read:target.xml:5,1000").ends_with("</target>\n"));
+            assert_eq!("+$", client.request("vMustReplyEmpty"));
+            client.request("D");
+        });
+    }
+}
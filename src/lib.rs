@@ -1,2 +1,6 @@
 pub mod cpu;
-pub mod rom;
\ No newline at end of file
+pub mod rom;
+pub mod mapper;
+pub mod debugger;
+pub mod gdb;
+pub mod dap;
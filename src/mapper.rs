@@ -0,0 +1,171 @@
+mod nrom;
+mod mmc1;
+mod mmc2;
+mod mmc3;
+mod mmc5;
+mod discrete;
+
+use crate::rom::{Rom, Mirroring};
+
+pub use self::nrom::Nrom;
+pub use self::mmc1::Mmc1;
+pub use self::mmc2::Mmc2;
+pub use self::mmc3::Mmc3;
+pub use self::mmc5::Mmc5;
+pub use self::discrete::{Discrete, Board};
+
+pub const CARTRIDGE_START: u16 = 0x4020;
+pub const PRG_RAM_START:   u16 = 0x6000;
+pub const PRG_RAM_END:     u16 = 0x7FFF;
+pub const PRG_ROM_START:   u16 = 0x8000;
+
+pub const PRG_RAM_SIZE: usize = 0x2000;
+pub const CHR_RAM_SIZE: usize = 0x2000;
+
+/// Cartridge board, seen by the CPU in $4020-$FFFF and by the PPU in $0000-$1FFF
+pub trait Mapper
+{
+    /// None when the cartridge does not drive the data bus, the read returns open bus
+    fn cpu_read(&mut self, addr: u16) -> Option<u8>
+    {
+        self.cpu_peek(addr)
+    }
+
+    /// Reads without side effects, for debugging tools
+    fn cpu_peek(&self, addr: u16) -> Option<u8>;
+
+    /// Writes to ROM addresses usually go to the mapper registers
+    fn cpu_write(&mut self, addr: u16, value: u8);
+
+    fn ppu_read(&mut self, addr: u16) -> u8;
+
+    /// Ignored by boards with CHR ROM
+    fn ppu_write(&mut self, addr: u16, value: u8);
+
+    /// Nametable mirroring, fixed by the board or controlled by the mapper
+    fn mirroring(&self) -> Mirroring;
+
+    /// Nametable reads by the PPU in $2000-$2FFF, None when the console VRAM answers
+    fn nametable_read(&mut self, _addr: u16) -> Option<u8>
+    {
+        None
+    }
+
+    /// False when the write goes to the console VRAM
+    fn nametable_write(&mut self, _addr: u16, _value: u8) -> bool
+    {
+        false
+    }
+
+    /// Writes to the PPU registers, for mappers watching the PPU configuration
+    fn ppu_register_write(&mut self, _addr: u16, _value: u8)
+    {
+    }
+
+    /// CPU cycle at the start of the current instruction, see `Bus::set_cycle`
+    fn set_cycle(&mut self, _cycle: u64)
+    {
+    }
+
+    /// State of the IRQ line, for mappers with an interrupt counter
+    fn irq(&self) -> bool
+    {
+        false
+    }
+
+    /// Battery backed PRG RAM, for the host to save between sessions
+    fn battery_ram(&self) -> Option<&[u8]>
+    {
+        None
+    }
+
+    /// Restores the battery backed PRG RAM saved by the host
+    fn load_battery_ram(&mut self, _data: &[u8])
+    {
+    }
+}
+
+/// Creates the mapper for the board the ROM was made for
+pub fn create(rom: Rom) -> Result<Box<dyn Mapper>, String>
+{
+    match rom.mapper
+    {
+        0 => Ok(Box::new(Nrom::new(rom)?)),
+        1 => Ok(Box::new(Mmc1::new(rom)?)),
+        4 => Ok(Box::new(Mmc3::new(rom)?)),
+        5 => Ok(Box::new(Mmc5::new(rom)?)),
+        9 | 10 => Ok(Box::new(Mmc2::new(rom)?)),
+        number => match Board::from_mapper(number)
+        {
+            Some(board) => Ok(Box::new(Discrete::new(rom, board)?)),
+            None => Err(format!("Unsupported mapper {}", number)),
+        },
+    }
+}
+
+// Boards without CHR ROM have 8K of CHR RAM
+fn chr_or_ram(chr: Vec<u8>) -> (Vec<u8>, bool)
+{
+    if chr.is_empty()
+    {
+        (vec![0; CHR_RAM_SIZE], true)
+    }
+    else
+    {
+        (chr, false)
+    }
+}
+
+// The header gives the PRG RAM size, older dumps leave it at 0 for the default of the board
+fn prg_ram(size: usize, default: usize) -> Vec<u8>
+{
+    vec![0; if size == 0 { default } else { size }]
+}
+
+// Battery backed PRG RAM, shared by the boards saving it for the host
+fn battery_ram(prg_ram: &[u8], battery: bool) -> Option<&[u8]>
+{
+    battery.then_some(prg_ram)
+}
+
+// A save may come from a board with another PRG RAM size, only what fits is restored
+fn load_battery_ram(prg_ram: &mut [u8], data: &[u8])
+{
+    let len = data.len().min(prg_ram.len());
+    prg_ram[..len].copy_from_slice(&data[..len]);
+}
+
+// Each PRG bank is filled with its number, each CHR bank with its number + 0x80
+#[cfg(test)]
+fn numbered_rom(mapper: u8, prg_banks: usize, prg_bank_size: usize, chr_banks: usize, chr_bank_size: usize) -> Rom
+{
+    Rom
+    {
+        prg: (0..prg_banks * prg_bank_size).map(|i| (i / prg_bank_size) as u8).collect(),
+        chr: (0..chr_banks * chr_bank_size).map(|i| ((i / chr_bank_size) as u8).wrapping_add(0x80)).collect(),
+        mapper,
+        mirroring: Mirroring::Vertical,
+        ..Rom::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn unsupported_mapper()
+    {
+        let rom = Rom { prg: vec![0; 0x8000], mapper: 255, ..Rom::empty() };
+
+        assert_eq!(Some(String::from("Unsupported mapper 255")), create(rom).err());
+    }
+
+    #[test]
+    fn prg_ram_size_from_header()
+    {
+        assert_eq!(PRG_RAM_SIZE, prg_ram(0, PRG_RAM_SIZE).len());
+        assert_eq!(0x8000, prg_ram(0x8000, PRG_RAM_SIZE).len());
+    }
+}
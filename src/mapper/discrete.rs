@@ -0,0 +1,287 @@
+use crate::rom::{Rom, Mirroring, PRG_BANK_SIZE, CHR_BANK_SIZE};
+
+use super::{Mapper, chr_or_ram, PRG_ROM_START};
+
+const PRG_32K: usize = 2 * PRG_BANK_SIZE;
+
+/// Boards made of discrete logic, a latch written in $8000-$FFFF selects the banks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Board
+{
+    Uxrom,       // Mapper 2, 16K at $8000, the last bank fixed at $C000
+    Cnrom,       // Mapper 3, 8K of CHR
+    Axrom,       // Mapper 7, 32K of PRG and single screen mirroring
+    ColorDreams, // Mapper 11, 32K of PRG in bits 0-1, 8K of CHR in bits 4-7
+    Bnrom,       // Mapper 34, 32K of PRG
+    Gxrom,       // Mapper 66, 32K of PRG in bits 4-5, 8K of CHR in bits 0-1
+}
+
+impl Board
+{
+    pub fn from_mapper(number: u8) -> Option<Board>
+    {
+        match number
+        {
+            2  => Some(Board::Uxrom),
+            3  => Some(Board::Cnrom),
+            7  => Some(Board::Axrom),
+            11 => Some(Board::ColorDreams),
+            34 => Some(Board::Bnrom),
+            66 => Some(Board::Gxrom),
+            _  => None,
+        }
+    }
+
+    /// Whether the ROM drives the data bus during writes, what the CPU writes is then ANDed with the ROM byte.
+    /// AOROM, the most common AxROM board, has none, games made for it would break
+    pub fn bus_conflicts(self) -> bool
+    {
+        !matches!(self, Board::Axrom)
+    }
+}
+
+pub struct Discrete
+{
+    board:         Board,
+    prg:           Vec<u8>,
+    chr:           Vec<u8>,
+    chr_ram:       bool,
+    mirroring:     Mirroring,
+    latch:         u8,
+    bus_conflicts: bool,
+}
+
+impl Discrete
+{
+    pub fn new(rom: Rom, board: Board) -> Result<Discrete, String>
+    {
+        if rom.prg.is_empty() || !rom.prg.len().is_multiple_of(PRG_BANK_SIZE)
+        {
+            return Err(format!("{:?} needs PRG ROM in 16K banks, not {}K", board, rom.prg.len() / 1024));
+        }
+
+        // Mapper 34 with CHR ROM is the NINA-001 board, its registers are in $7FFD-$7FFF
+        if board == Board::Bnrom && !rom.chr.is_empty()
+        {
+            return Err(String::from("Unsupported mapper 34 board NINA-001"));
+        }
+
+        let (chr, chr_ram) = chr_or_ram(rom.chr);
+
+        Ok(Discrete {
+            board,
+            prg: rom.prg,
+            chr,
+            chr_ram,
+            mirroring: rom.mirroring,
+            latch: 0,
+            bus_conflicts: board.bus_conflicts(),
+        })
+    }
+
+    /// Overrides the bus conflicts of the board, for the variants which differ
+    pub fn with_bus_conflicts(mut self, enabled: bool) -> Discrete
+    {
+        self.bus_conflicts = enabled;
+        self
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize
+    {
+        let latch = self.latch as usize;
+
+        let (bank, size) = match self.board
+        {
+            Board::Uxrom if addr < 0xC000 => (latch, PRG_BANK_SIZE),
+            Board::Uxrom       => (self.prg.len() / PRG_BANK_SIZE - 1, PRG_BANK_SIZE),
+            Board::Cnrom       => (0, PRG_32K), // A single 16K bank is mirrored
+            Board::Axrom       => (latch & 0b0111, PRG_32K),
+            Board::ColorDreams => (latch & 0b0011, PRG_32K),
+            Board::Bnrom       => (latch, PRG_32K),
+            Board::Gxrom       => ((latch >> 4) & 0b0011, PRG_32K),
+        };
+
+        (bank * size + (addr - PRG_ROM_START) as usize % size) % self.prg.len()
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize
+    {
+        let bank = match self.board
+        {
+            Board::Cnrom       => self.latch,
+            Board::ColorDreams => self.latch >> 4,
+            Board::Gxrom       => self.latch & 0b0011,
+            _                  => 0,
+        } as usize;
+
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+}
+
+impl Mapper for Discrete
+{
+    fn cpu_peek(&self, addr: u16) -> Option<u8>
+    {
+        if addr < PRG_ROM_START { return None }
+
+        Some(self.prg[self.prg_addr(addr)])
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8)
+    {
+        if addr < PRG_ROM_START { return }
+
+        // A 0 from either side wins on the bus
+        self.latch = if self.bus_conflicts { value & self.prg[self.prg_addr(addr)] } else { value };
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8
+    {
+        self.chr[self.chr_addr(addr)]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8)
+    {
+        if self.chr_ram
+        {
+            let addr = self.chr_addr(addr);
+            self.chr[addr] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        match self.board
+        {
+            Board::Axrom if self.latch & 0b1_0000 != 0 => Mirroring::SingleScreenUpper,
+            Board::Axrom => Mirroring::SingleScreenLower,
+            _ => self.mirroring,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::mapper::{create, numbered_rom};
+    use super::*;
+
+    // Numbered in 16K PRG banks and 8K CHR banks
+    fn discrete(board: Board, prg_banks: usize, chr_banks: usize) -> Discrete
+    {
+        let rom = numbered_rom(0, prg_banks, PRG_BANK_SIZE, chr_banks, CHR_BANK_SIZE);
+
+        Discrete::new(rom, board).unwrap().with_bus_conflicts(false)
+    }
+
+    #[test]
+    fn uxrom()
+    {
+        let mut mapper = discrete(Board::Uxrom, 8, 0);
+
+        mapper.cpu_write(0x8000, 3);
+
+        assert_eq!(Some(0x03), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(0x03), mapper.cpu_peek(0xBFFF));
+        assert_eq!(Some(0x07), mapper.cpu_peek(0xC000));
+
+        // CHR RAM
+        mapper.ppu_write(0x0010, 0x42);
+        assert_eq!(0x42, mapper.ppu_read(0x0010));
+    }
+
+    #[test]
+    fn cnrom()
+    {
+        let mut mapper = discrete(Board::Cnrom, 1, 4);
+
+        mapper.cpu_write(0xFFFF, 2);
+
+        assert_eq!(0x82, mapper.ppu_read(0x0000));
+        assert_eq!(0x82, mapper.ppu_read(0x1FFF));
+        assert_eq!(Some(0x00), mapper.cpu_peek(0xC000));
+
+        mapper.ppu_write(0x0000, 0);
+        assert_eq!(0x82, mapper.ppu_read(0x0000));
+    }
+
+    #[test]
+    fn axrom()
+    {
+        let mut mapper = discrete(Board::Axrom, 8, 0);
+
+        assert_eq!(Mirroring::SingleScreenLower, mapper.mirroring());
+
+        mapper.cpu_write(0x8000, 0b1_0010);
+
+        assert_eq!(Some(0x04), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(0x05), mapper.cpu_peek(0xC000));
+        assert_eq!(Mirroring::SingleScreenUpper, mapper.mirroring());
+    }
+
+    #[test]
+    fn color_dreams()
+    {
+        let mut mapper = discrete(Board::ColorDreams, 8, 16);
+
+        mapper.cpu_write(0x8000, 0b1010_0001);
+
+        assert_eq!(Some(0x02), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(0x03), mapper.cpu_peek(0xC000));
+        assert_eq!(0x8A, mapper.ppu_read(0x0000));
+        assert_eq!(Mirroring::Vertical, mapper.mirroring());
+    }
+
+    #[test]
+    fn bnrom()
+    {
+        let mut mapper = discrete(Board::Bnrom, 8, 0);
+
+        mapper.cpu_write(0x8000, 3);
+
+        assert_eq!(Some(0x06), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(0x07), mapper.cpu_peek(0xFFFF));
+
+        let rom = Rom { prg: vec![0; 0x8000], chr: vec![0; 0x2000], mapper: 34, ..Rom::empty() };
+        assert!(Discrete::new(rom, Board::Bnrom).is_err());
+    }
+
+    #[test]
+    fn gxrom()
+    {
+        let mut mapper = discrete(Board::Gxrom, 8, 4);
+
+        mapper.cpu_write(0x8000, 0b0010_0011);
+
+        assert_eq!(Some(0x04), mapper.cpu_peek(0x8000));
+        assert_eq!(0x83, mapper.ppu_read(0x1000));
+    }
+
+    #[test]
+    fn bus_conflicts()
+    {
+        // The value written is ANDed with the ROM byte at the address
+        let mut mapper = discrete(Board::Uxrom, 8, 0).with_bus_conflicts(true);
+
+        mapper.cpu_write(0xC000, 0x0E);
+        assert_eq!(Some(0x06), mapper.cpu_peek(0x8000));
+
+        mapper.cpu_write(0x8000, 0x03);
+        assert_eq!(Some(0x02), mapper.cpu_peek(0x8000));
+    }
+
+    #[test]
+    fn boards()
+    {
+        for number in [2, 3, 7, 11, 34, 66]
+        {
+            let board = Board::from_mapper(number).unwrap();
+            let rom = Rom { prg: vec![0; 0x8000], mapper: number, ..Rom::empty() };
+
+            assert!(create(rom).is_ok(), "{:?}", board);
+        }
+
+        assert!(!Board::Axrom.bus_conflicts());
+        assert!(Board::Uxrom.bus_conflicts());
+    }
+}
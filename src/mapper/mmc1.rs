@@ -0,0 +1,408 @@
+use crate::rom::{Rom, Mirroring, PRG_BANK_SIZE};
+
+use super::{Mapper, chr_or_ram, prg_ram, PRG_RAM_START, PRG_RAM_END, PRG_ROM_START, PRG_RAM_SIZE};
+
+const CHR_BANK_SIZE:       usize = 0x1000; // CHR is switched by 4K, a pattern table
+const OUTER_PRG_BANK_SIZE: usize = 16 * PRG_BANK_SIZE; // What the PRG bank register reaches, SUROM has 2 of them
+const MAX_PRG_SIZE:        usize = 2 * OUTER_PRG_BANK_SIZE;
+
+const CONTROL_RESET: u8 = 0b0_1100; // PRG mode 3, the last bank is fixed at $C000
+
+/// Mapper 1, the SxROM boards, including SNROM (battery backed PRG RAM) and SUROM (512K of PRG ROM).
+/// Registers are loaded through a serial port, 1 bit per write in $8000-$FFFF.
+/// Writes during the same instruction are seen as consecutive cycles, see `Bus::set_cycle`
+pub struct Mmc1
+{
+    prg:         Vec<u8>,
+    prg_ram:     Vec<u8>,
+    chr:         Vec<u8>,
+    chr_ram:     bool,
+    battery:     bool,
+    shift:       u8,
+    shift_count: u8,
+    control:     u8, // Mirroring in bits 0-1, PRG bank mode in bits 2-3, CHR bank mode in bit 4
+    chr_banks:   [u8; 2],
+    prg_bank:    u8, // PRG RAM is disabled by bit 4
+    cycle:       u64,
+    last_write:  Option<u64>,
+}
+
+impl Mmc1
+{
+    pub fn new(rom: Rom) -> Result<Mmc1, String>
+    {
+        if rom.prg.is_empty() || rom.prg.len() > MAX_PRG_SIZE || !rom.prg.len().is_multiple_of(PRG_BANK_SIZE)
+        {
+            return Err(format!("MMC1 needs 16K to 512K of PRG ROM, not {}K", rom.prg.len() / 1024));
+        }
+
+        let (chr, chr_ram) = chr_or_ram(rom.chr);
+
+        Ok(Mmc1 {
+            prg: rom.prg,
+            prg_ram: prg_ram(rom.prg_ram_size, PRG_RAM_SIZE),
+            chr,
+            chr_ram,
+            battery: rom.battery,
+            shift: 0,
+            shift_count: 0,
+            control: CONTROL_RESET,
+            chr_banks: [0, 0],
+            prg_bank: 0,
+            cycle: 0,
+            last_write: None,
+        })
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8)
+    {
+        // The serial port ignores a write on the cycle following another one,
+        // only read-modify-write instructions do that (the unmodified value is taken)
+        let consecutive = self.last_write == Some(self.cycle);
+        self.last_write = Some(self.cycle);
+
+        if consecutive { return }
+
+        if value & 0b1000_0000 != 0
+        {
+            self.shift = 0;
+            self.shift_count = 0;
+            self.control |= CONTROL_RESET;
+            return;
+        }
+
+        // LSB first, the 5th write copies the value to the register selected by the address
+        self.shift |= (value & 1) << self.shift_count;
+        self.shift_count += 1;
+
+        if self.shift_count < 5 { return }
+
+        match addr & 0x6000
+        {
+            0x0000 => self.control = self.shift,
+            0x2000 => self.chr_banks[0] = self.shift,
+            0x4000 => self.chr_banks[1] = self.shift,
+            _      => self.prg_bank = self.shift,
+        }
+
+        self.shift = 0;
+        self.shift_count = 0;
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize
+    {
+        let bank = (self.prg_bank & 0x0F) as usize;
+
+        let (low, high) = match (self.control >> 2) & 0b11
+        {
+            0 | 1 => (bank & !1, bank | 1), // 32K, the low bit is ignored
+            2     => (0, bank),
+            _     => (bank, 0x0F),
+        };
+
+        let bank = if addr < 0xC000 { low } else { high };
+        let offset = (addr - PRG_ROM_START) as usize % PRG_BANK_SIZE;
+
+        (self.outer_prg_bank() + bank * PRG_BANK_SIZE + offset) % self.prg.len()
+    }
+
+    // SUROM wires bit 4 of the CHR bank to the PRG ROM, to select a 256K half.
+    // Which CHR register drives it follows the PPU in 4K mode, games set both the same
+    fn outer_prg_bank(&self) -> usize
+    {
+        if self.prg.len() <= OUTER_PRG_BANK_SIZE { return 0 }
+
+        ((self.chr_banks[0] >> 4) & 1) as usize * OUTER_PRG_BANK_SIZE
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize
+    {
+        let half = (addr as usize >> 12) & 1;
+
+        let bank = if self.control & 0b1_0000 == 0
+        {
+            (self.chr_banks[0] & !1) as usize | half // 8K
+        }
+        else
+        {
+            self.chr_banks[half] as usize
+        };
+
+        (bank * CHR_BANK_SIZE + (addr as usize % CHR_BANK_SIZE)) % self.chr.len()
+    }
+
+    // SNROM uses bit 4 of the CHR bank as an extra PRG RAM disable, its 8K of CHR RAM do not need it
+    fn prg_ram_enabled(&self) -> bool
+    {
+        let snrom = self.chr_ram && self.prg.len() <= OUTER_PRG_BANK_SIZE;
+
+        self.prg_bank & 0b1_0000 == 0 && !(snrom && self.chr_banks[0] & 0b1_0000 != 0)
+    }
+}
+
+impl Mapper for Mmc1
+{
+    fn cpu_peek(&self, addr: u16) -> Option<u8>
+    {
+        match addr
+        {
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_enabled() => Some(self.prg_ram[(addr - PRG_RAM_START) as usize]),
+            PRG_ROM_START..=0xFFFF => Some(self.prg[self.prg_addr(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8)
+    {
+        match addr
+        {
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_enabled() => self.prg_ram[(addr - PRG_RAM_START) as usize] = value,
+            PRG_ROM_START..=0xFFFF => self.write_register(addr, value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8
+    {
+        self.chr[self.chr_addr(addr)]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8)
+    {
+        if self.chr_ram
+        {
+            let addr = self.chr_addr(addr);
+            self.chr[addr] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        match self.control & 0b11
+        {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn set_cycle(&mut self, cycle: u64)
+    {
+        self.cycle = cycle;
+    }
+
+    fn battery_ram(&self) -> Option<&[u8]>
+    {
+        super::battery_ram(&self.prg_ram, self.battery)
+    }
+
+    fn load_battery_ram(&mut self, data: &[u8])
+    {
+        super::load_battery_ram(&mut self.prg_ram, data);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::cpu::Cpu;
+    use crate::mapper::numbered_rom;
+    use super::*;
+
+    fn mmc1(prg_banks: usize, chr_banks: usize) -> Mmc1
+    {
+        Mmc1::new(Rom { battery: true, ..numbered_rom(1, prg_banks, PRG_BANK_SIZE, chr_banks, CHR_BANK_SIZE) }).unwrap()
+    }
+
+    // The 5 serial writes, on separate instructions
+    fn write(mapper: &mut Mmc1, addr: u16, value: u8)
+    {
+        for bit in 0..5
+        {
+            mapper.set_cycle(mapper.cycle + 4);
+            mapper.cpu_write(addr, value >> bit);
+        }
+    }
+
+    #[test]
+    fn power_on_state()
+    {
+        let mapper = mmc1(8, 0);
+
+        assert_eq!(Some(0), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(7), mapper.cpu_peek(0xC000));
+        assert_eq!(Some(7), mapper.cpu_peek(0xFFFF));
+    }
+
+    #[test]
+    fn prg_bank_modes()
+    {
+        let mut mapper = mmc1(8, 0);
+
+        write(&mut mapper, 0xE000, 5);
+        assert_eq!(Some(5), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(7), mapper.cpu_peek(0xC000));
+
+        // First bank fixed at $8000
+        write(&mut mapper, 0x8000, 0b0_1000);
+        assert_eq!(Some(0), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(5), mapper.cpu_peek(0xC000));
+
+        // 32K
+        write(&mut mapper, 0x8000, 0b0_0000);
+        assert_eq!(Some(4), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(5), mapper.cpu_peek(0xC000));
+    }
+
+    #[test]
+    fn reset_write()
+    {
+        let mut mapper = mmc1(8, 0);
+
+        write(&mut mapper, 0x8000, 0b0_1000);
+        write(&mut mapper, 0xE000, 3);
+
+        // A reset in the middle of a sequence drops the bits written and restores PRG mode 3
+        mapper.set_cycle(100);
+        mapper.cpu_write(0xE000, 1);
+        mapper.set_cycle(104);
+        mapper.cpu_write(0x8000, 0x80);
+
+        assert_eq!(Some(3), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(7), mapper.cpu_peek(0xC000));
+
+        write(&mut mapper, 0xE000, 2);
+        assert_eq!(Some(2), mapper.cpu_peek(0x8000));
+    }
+
+    #[test]
+    fn consecutive_writes_are_ignored()
+    {
+        let mut mapper = mmc1(8, 0);
+
+        mapper.set_cycle(10);
+        mapper.cpu_write(0xE000, 1);
+        mapper.cpu_write(0xE000, 0); // Ignored
+
+        for cycle in [20, 30, 40, 50]
+        {
+            mapper.set_cycle(cycle);
+            mapper.cpu_write(0xE000, 0);
+        }
+
+        assert_eq!(Some(1), mapper.cpu_peek(0x8000));
+    }
+
+    #[test]
+    fn chr_bank_modes()
+    {
+        let mut mapper = mmc1(2, 8);
+
+        // 8K, the low bit is ignored
+        write(&mut mapper, 0xA000, 3);
+        assert_eq!(0x82, mapper.ppu_read(0x0000));
+        assert_eq!(0x83, mapper.ppu_read(0x1000));
+
+        // 4K
+        write(&mut mapper, 0x8000, 0b1_1100);
+        write(&mut mapper, 0xC000, 6);
+        assert_eq!(0x83, mapper.ppu_read(0x0FFF));
+        assert_eq!(0x86, mapper.ppu_read(0x1000));
+
+        // CHR ROM is not writable
+        mapper.ppu_write(0x1000, 0);
+        assert_eq!(0x86, mapper.ppu_read(0x1000));
+    }
+
+    #[test]
+    fn mirroring_control()
+    {
+        let mut mapper = mmc1(2, 2);
+
+        let modes = [
+            Mirroring::SingleScreenLower,
+            Mirroring::SingleScreenUpper,
+            Mirroring::Vertical,
+            Mirroring::Horizontal,
+        ];
+
+        for (value, mirroring) in modes.into_iter().enumerate()
+        {
+            write(&mut mapper, 0x9FFF, CONTROL_RESET | value as u8);
+            assert_eq!(mirroring, mapper.mirroring());
+        }
+    }
+
+    #[test]
+    fn surom_outer_bank()
+    {
+        let mut mapper = mmc1(32, 0);
+
+        assert_eq!(Some(15), mapper.cpu_peek(0xC000));
+
+        write(&mut mapper, 0xA000, 0b1_0000);
+        write(&mut mapper, 0xE000, 2);
+
+        assert_eq!(Some(18), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(31), mapper.cpu_peek(0xC000));
+    }
+
+    #[test]
+    fn snrom_prg_ram()
+    {
+        let mut mapper = mmc1(16, 0);
+
+        mapper.cpu_write(0x6000, 0x42);
+        assert_eq!(Some(0x42), mapper.cpu_peek(0x6000));
+        assert_eq!(Some(&0x42), mapper.battery_ram().and_then(|ram| ram.first()));
+
+        // Disabled by the CHR bank, then by the PRG bank
+        write(&mut mapper, 0xA000, 0b1_0000);
+        assert_eq!(None, mapper.cpu_peek(0x6000));
+
+        write(&mut mapper, 0xA000, 0);
+        write(&mut mapper, 0xE000, 0b1_0000);
+        mapper.cpu_write(0x6000, 0);
+        assert_eq!(None, mapper.cpu_peek(0x6000));
+
+        write(&mut mapper, 0xE000, 0);
+        assert_eq!(Some(0x42), mapper.cpu_peek(0x6000));
+
+        mapper.load_battery_ram(&[0x24; PRG_RAM_SIZE]);
+        assert_eq!(Some(0x24), mapper.cpu_peek(0x7FFF));
+    }
+
+    #[test]
+    fn read_modify_write()
+    {
+        let mut prg = vec![0; 4 * PRG_BANK_SIZE];
+        prg[PRG_BANK_SIZE] = 1;
+        prg[0] = 0xFF;
+
+        // INC $8000 ; LDA #1 ; STA $E000 ; 4 * (LSR A ; STA $E000)
+        let program = [0xEE, 0x00, 0x80, 0xA9, 0x01, 0x8D, 0x00, 0xE0];
+        let last = 3 * PRG_BANK_SIZE;
+        prg[last..last + program.len()].copy_from_slice(&program);
+
+        for i in 0..4
+        {
+            let at = last + program.len() + i * 4;
+            prg[at..at + 4].copy_from_slice(&[0x4A, 0x8D, 0x00, 0xE0]);
+        }
+
+        prg[last + 0x3FFD] = 0xC0;
+
+        let mut cpu = Cpu::new();
+        cpu.load_rom(Rom { prg, mapper: 1, ..Rom::empty() }).unwrap();
+        cpu.reset();
+
+        // Only the dummy write of $FF is seen, a reset, the written $00 would have shifted a bit in
+        for _ in 0..11
+        {
+            cpu.step().unwrap();
+        }
+
+        assert_eq!(1, cpu.memory.peek(0x8000));
+    }
+}
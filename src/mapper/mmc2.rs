@@ -0,0 +1,283 @@
+use crate::rom::{Rom, Mirroring};
+
+use super::{Mapper, chr_or_ram, prg_ram, PRG_RAM_START, PRG_RAM_END, PRG_ROM_START, PRG_RAM_SIZE};
+
+const CHR_BANK_SIZE: usize = 0x1000;
+
+const LATCH_FD: u16 = 0x0FD8; // High plane of tile $FD
+const LATCH_FE: u16 = 0x0FE8;
+
+/// Mappers 9 (MMC2, PxROM) and 10 (MMC4, FxROM).
+/// Each pattern table has 2 CHR banks, the latch of the table selects one of them
+/// when the PPU fetches tile $FD or $FE, so the cartridge follows what is rendered
+pub struct Mmc2
+{
+    mmc4:      bool, // 16K PRG banks and PRG RAM, the latch of $0000 triggers on the whole tile row like $1000
+    prg:       Vec<u8>,
+    prg_ram:   Vec<u8>, // Empty on MMC2
+    battery:   bool,
+    chr:       Vec<u8>,
+    chr_ram:   bool,
+    prg_bank:  u8,
+    chr_banks: [[u8; 2]; 2], // For each pattern table, the banks used after tile $FD and $FE
+    latches:   [usize; 2], // 0 after $FD, 1 after $FE
+    mirroring: Mirroring,
+}
+
+impl Mmc2
+{
+    /// The board depends on the mapper number of the ROM
+    pub fn new(rom: Rom) -> Result<Mmc2, String>
+    {
+        let mmc4 = rom.mapper == 10;
+        let bank_size = if mmc4 { 0x4000 } else { 0x2000 };
+
+        if rom.prg.len() < 4 * 0x2000 || !rom.prg.len().is_multiple_of(bank_size)
+        {
+            return Err(format!("MMC{} needs at least 32K of PRG ROM in {}K banks, not {}K",
+                if mmc4 { 4 } else { 2 }, bank_size / 1024, rom.prg.len() / 1024));
+        }
+
+        let (chr, chr_ram) = chr_or_ram(rom.chr);
+
+        Ok(Mmc2 {
+            mmc4,
+            prg: rom.prg,
+            prg_ram: if mmc4 { prg_ram(rom.prg_ram_size, PRG_RAM_SIZE) } else { Vec::new() },
+            battery: mmc4 && rom.battery,
+            chr,
+            chr_ram,
+            prg_bank: 0,
+            chr_banks: [[0, 0], [0, 0]],
+            latches: [1, 1],
+            mirroring: rom.mirroring,
+        })
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize
+    {
+        let bank_size = if self.mmc4 { 0x4000 } else { 0x2000 };
+        let offset = (addr - PRG_ROM_START) as usize;
+
+        // Only the first bank switches, the last ones are fixed
+        let addr = if offset < bank_size
+        {
+            self.prg_bank as usize * bank_size + offset
+        }
+        else
+        {
+            self.prg.len() - (0x8000 - offset)
+        };
+
+        addr % self.prg.len()
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize
+    {
+        let table = (addr as usize >> 12) & 1;
+        let bank = self.chr_banks[table][self.latches[table]] as usize;
+
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    // The fetch which sets a latch still uses the previous bank
+    fn update_latches(&mut self, addr: u16)
+    {
+        let table = (addr as usize >> 12) & 1;
+
+        // MMC2 only triggers on the first row in the first pattern table
+        let addr = if table == 0 && !self.mmc4 { addr } else { addr & 0x0FF8 };
+
+        self.latches[table] = match addr
+        {
+            LATCH_FD => 0,
+            LATCH_FE => 1,
+            _ => return,
+        };
+    }
+}
+
+impl Mapper for Mmc2
+{
+    fn cpu_peek(&self, addr: u16) -> Option<u8>
+    {
+        match addr
+        {
+            PRG_RAM_START..=PRG_RAM_END if self.mmc4 => Some(self.prg_ram[(addr - PRG_RAM_START) as usize]),
+            PRG_ROM_START..=0xFFFF => Some(self.prg[self.prg_addr(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8)
+    {
+        match addr
+        {
+            PRG_RAM_START..=PRG_RAM_END if self.mmc4 => self.prg_ram[(addr - PRG_RAM_START) as usize] = value,
+            0xA000..=0xAFFF => self.prg_bank = value & 0x0F,
+            0xB000..=0xEFFF => {
+                let register = ((addr - 0xB000) >> 12) as usize;
+                self.chr_banks[register / 2][register % 2] = value & 0x1F;
+            },
+            0xF000..=0xFFFF => self.mirroring = if value & 1 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal },
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8
+    {
+        let value = self.chr[self.chr_addr(addr)];
+        self.update_latches(addr);
+
+        value
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8)
+    {
+        if self.chr_ram
+        {
+            let addr = self.chr_addr(addr);
+            self.chr[addr] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        self.mirroring
+    }
+
+    fn battery_ram(&self) -> Option<&[u8]>
+    {
+        super::battery_ram(&self.prg_ram, self.battery)
+    }
+
+    fn load_battery_ram(&mut self, data: &[u8])
+    {
+        super::load_battery_ram(&mut self.prg_ram, data);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::mapper::numbered_rom;
+    use super::*;
+
+    // Numbered in 8K PRG banks and 4K CHR banks
+    fn mmc2(mapper: u8) -> Mmc2
+    {
+        let mut mapper = Mmc2::new(Rom { battery: true, ..numbered_rom(mapper, 16, 0x2000, 32, CHR_BANK_SIZE) }).unwrap();
+
+        for (register, bank) in [0xB000, 0xC000, 0xD000, 0xE000].into_iter().zip([1, 2, 3, 4])
+        {
+            mapper.cpu_write(register, bank);
+        }
+
+        mapper
+    }
+
+    // The PPU fetches both planes of a row
+    fn fetch(mapper: &mut Mmc2, table: u16, tile: u16, row: u16) -> u8
+    {
+        let addr = table | tile << 4 | row;
+        let value = mapper.ppu_read(addr);
+        mapper.ppu_read(addr + 8);
+
+        value
+    }
+
+    #[test]
+    fn prg_banks()
+    {
+        let mut mapper = mmc2(9);
+
+        mapper.cpu_write(0xA000, 5);
+
+        assert_eq!(Some(5), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(13), mapper.cpu_peek(0xA000));
+        assert_eq!(Some(14), mapper.cpu_peek(0xC000));
+        assert_eq!(Some(15), mapper.cpu_peek(0xE000));
+        assert_eq!(None, mapper.cpu_peek(0x6000));
+
+        let mut mapper = mmc2(10);
+
+        mapper.cpu_write(0xA000, 3);
+        mapper.cpu_write(0x6000, 0x42);
+
+        assert_eq!(Some(6), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(7), mapper.cpu_peek(0xA000));
+        assert_eq!(Some(14), mapper.cpu_peek(0xC000));
+        assert_eq!(Some(0x42), mapper.cpu_peek(0x6000));
+    }
+
+    #[test]
+    fn battery_ram_only_on_mmc4()
+    {
+        let mut mapper = mmc2(9);
+
+        mapper.load_battery_ram(&[0x24; PRG_RAM_SIZE]);
+        assert_eq!(None, mapper.battery_ram());
+        assert_eq!(None, mapper.cpu_peek(0x6000));
+
+        let mut mapper = mmc2(10);
+
+        mapper.cpu_write(0x6000, 0x42);
+        assert_eq!(Some(&0x42), mapper.battery_ram().and_then(|ram| ram.first()));
+
+        mapper.load_battery_ram(&[0x24; PRG_RAM_SIZE]);
+        assert_eq!(Some(0x24), mapper.cpu_peek(0x7FFF));
+    }
+
+    #[test]
+    fn latches_follow_pattern_fetches()
+    {
+        let mut mapper = mmc2(9);
+
+        // Power on with the $FE banks
+        assert_eq!(0x82, fetch(&mut mapper, 0x0000, 0x00, 0));
+        assert_eq!(0x84, fetch(&mut mapper, 0x1000, 0x00, 0));
+
+        // The fetch of tile $FD itself still uses the previous bank
+        assert_eq!(0x82, fetch(&mut mapper, 0x0000, 0xFD, 0));
+        assert_eq!(0x81, fetch(&mut mapper, 0x0000, 0x20, 0));
+        assert_eq!(0x84, fetch(&mut mapper, 0x1000, 0x20, 0));
+
+        // Each pattern table has its own latch
+        fetch(&mut mapper, 0x1000, 0xFD, 3);
+        assert_eq!(0x83, fetch(&mut mapper, 0x1000, 0x20, 0));
+        assert_eq!(0x81, fetch(&mut mapper, 0x0000, 0x20, 0));
+
+        fetch(&mut mapper, 0x0000, 0xFE, 0);
+        fetch(&mut mapper, 0x1000, 0xFE, 7);
+        assert_eq!(0x82, fetch(&mut mapper, 0x0000, 0x20, 0));
+        assert_eq!(0x84, fetch(&mut mapper, 0x1000, 0x20, 0));
+
+        // Only the first row of tile $FD in the first pattern table
+        fetch(&mut mapper, 0x0000, 0xFD, 3);
+        assert_eq!(0x82, fetch(&mut mapper, 0x0000, 0x20, 0));
+    }
+
+    #[test]
+    fn mmc4_latches_on_every_row()
+    {
+        let mut mapper = mmc2(10);
+
+        fetch(&mut mapper, 0x0000, 0xFD, 5);
+        assert_eq!(0x81, fetch(&mut mapper, 0x0000, 0x20, 0));
+
+        fetch(&mut mapper, 0x0000, 0xFE, 2);
+        assert_eq!(0x82, fetch(&mut mapper, 0x0000, 0x20, 0));
+    }
+
+    #[test]
+    fn bank_registers_apply_to_the_active_latch()
+    {
+        let mut mapper = mmc2(9);
+
+        mapper.cpu_write(0xC000, 9);
+        assert_eq!(0x89, fetch(&mut mapper, 0x0000, 0x00, 0));
+
+        mapper.cpu_write(0xF000, 1);
+        assert_eq!(Mirroring::Horizontal, mapper.mirroring());
+    }
+}
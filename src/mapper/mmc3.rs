@@ -0,0 +1,410 @@
+use crate::rom::{Rom, Mirroring};
+
+use super::{Mapper, chr_or_ram, prg_ram, PRG_RAM_START, PRG_RAM_END, PRG_ROM_START, PRG_RAM_SIZE};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+const PPU_A12: u16 = 0x1000;
+
+/// Mapper 4, the TxROM boards: 8K PRG banks, 1K and 2K CHR banks and a scanline counter.
+/// The counter is clocked by the rising edges of A12 in the pattern table addresses the PPU reads,
+/// so it follows what is actually rendered (status bars, sprites in the right table)
+pub struct Mmc3
+{
+    prg:               Vec<u8>,
+    prg_ram:           Vec<u8>,
+    chr:               Vec<u8>,
+    chr_ram:           bool,
+    battery:           bool,
+    four_screen:       bool,
+    mirroring:         Mirroring,
+    bank_select:       u8, // Register to update in bits 0-2, PRG mode in bit 6, CHR A12 inversion in bit 7
+    banks:             [u8; 8], // R0-R1 2K CHR, R2-R5 1K CHR, R6-R7 8K PRG
+    prg_ram_enabled:   bool,
+    prg_ram_protected: bool,
+    irq_latch:         u8,
+    irq_counter:       u8,
+    irq_reload:        bool,
+    irq_enabled:       bool,
+    irq:               bool,
+    a12:               bool, // Last level seen on the PPU address bus
+}
+
+impl Mmc3
+{
+    pub fn new(rom: Rom) -> Result<Mmc3, String>
+    {
+        if rom.prg.len() < 2 * PRG_BANK_SIZE || !rom.prg.len().is_multiple_of(PRG_BANK_SIZE)
+        {
+            return Err(format!("MMC3 needs PRG ROM in 8K banks, at least 2 of them, not {}K", rom.prg.len() / 1024));
+        }
+
+        let (chr, chr_ram) = chr_or_ram(rom.chr);
+
+        Ok(Mmc3 {
+            prg: rom.prg,
+            prg_ram: prg_ram(rom.prg_ram_size, PRG_RAM_SIZE),
+            chr,
+            chr_ram,
+            battery: rom.battery,
+            four_screen: matches!(rom.mirroring, Mirroring::FourScreen),
+            mirroring: rom.mirroring,
+            bank_select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+            prg_ram_enabled: true,
+            prg_ram_protected: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq: false,
+            a12: false,
+        })
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8)
+    {
+        let even = addr & 1 == 0;
+
+        match (addr & 0xE000, even)
+        {
+            (0x8000, true)  => self.bank_select = value,
+            (0x8000, false) => self.banks[(self.bank_select & 0b111) as usize] = value,
+            (0xA000, true) if self.four_screen => {},
+            (0xA000, true)  => self.mirroring = if value & 1 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal },
+            (0xA000, false) => {
+                self.prg_ram_enabled = value & 0b1000_0000 != 0;
+                self.prg_ram_protected = value & 0b0100_0000 != 0;
+            },
+            (0xC000, true)  => self.irq_latch = value,
+            (0xC000, false) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            },
+            // Disabling also acknowledges a pending IRQ
+            (_, true)       => {
+                self.irq_enabled = false;
+                self.irq = false;
+            },
+            (_, false)      => self.irq_enabled = true,
+        }
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize
+    {
+        let count = self.prg.len() / PRG_BANK_SIZE;
+        let second_last = count - 2;
+        let swapped = self.bank_select & 0b0100_0000 != 0;
+
+        let bank = match ((addr - PRG_ROM_START) as usize / PRG_BANK_SIZE, swapped)
+        {
+            (0, false) | (2, true) => self.banks[6] as usize,
+            (0, true) | (2, false) => second_last,
+            (1, _) => self.banks[7] as usize,
+            _ => count - 1,
+        };
+
+        (bank % count) * PRG_BANK_SIZE + addr as usize % PRG_BANK_SIZE
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize
+    {
+        // The inversion swaps the 2K and 1K halves
+        let addr = if self.bank_select & 0b1000_0000 != 0 { addr ^ PPU_A12 } else { addr } as usize;
+        let slot = addr / CHR_BANK_SIZE;
+
+        let bank = match slot
+        {
+            0..=3 => (self.banks[slot / 2] & !1) as usize + slot % 2,
+            _     => self.banks[slot - 2] as usize,
+        };
+
+        (bank * CHR_BANK_SIZE + addr % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    // Nametable fetches between pattern fetches keep A12 low in hardware, a filter on M2 ignores them.
+    // They do not reach the mapper here, so each rising edge seen is a clock
+    fn ppu_address(&mut self, addr: u16)
+    {
+        let a12 = addr & PPU_A12 != 0;
+
+        if a12 && !self.a12
+        {
+            self.clock_irq_counter();
+        }
+
+        self.a12 = a12;
+    }
+
+    fn clock_irq_counter(&mut self)
+    {
+        if self.irq_counter == 0 || self.irq_reload
+        {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        }
+        else
+        {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.irq_enabled
+        {
+            self.irq = true;
+        }
+    }
+}
+
+impl Mapper for Mmc3
+{
+    fn cpu_peek(&self, addr: u16) -> Option<u8>
+    {
+        match addr
+        {
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_enabled => Some(self.prg_ram[(addr - PRG_RAM_START) as usize]),
+            PRG_ROM_START..=0xFFFF => Some(self.prg[self.prg_addr(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8)
+    {
+        match addr
+        {
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_enabled && !self.prg_ram_protected => {
+                self.prg_ram[(addr - PRG_RAM_START) as usize] = value;
+            },
+            PRG_ROM_START..=0xFFFF => self.write_register(addr, value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8
+    {
+        self.ppu_address(addr);
+        self.chr[self.chr_addr(addr)]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8)
+    {
+        self.ppu_address(addr);
+
+        if self.chr_ram
+        {
+            let addr = self.chr_addr(addr);
+            self.chr[addr] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        self.mirroring
+    }
+
+    fn irq(&self) -> bool
+    {
+        self.irq
+    }
+
+    fn battery_ram(&self) -> Option<&[u8]>
+    {
+        super::battery_ram(&self.prg_ram, self.battery)
+    }
+
+    fn load_battery_ram(&mut self, data: &[u8])
+    {
+        super::load_battery_ram(&mut self.prg_ram, data);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::cpu::{Cpu, Interrupt};
+    use crate::mapper::numbered_rom;
+    use super::*;
+
+    fn mmc3(prg_banks: usize, chr_banks: usize) -> Mmc3
+    {
+        Mmc3::new(numbered_rom(4, prg_banks, PRG_BANK_SIZE, chr_banks, CHR_BANK_SIZE)).unwrap()
+    }
+
+    fn set_bank(mapper: &mut Mmc3, mode: u8, register: u8, bank: u8)
+    {
+        mapper.cpu_write(0x8000, mode | register);
+        mapper.cpu_write(0x8001, bank);
+    }
+
+    // Background from $0000 then sprites from $1000, as the PPU fetches them on a visible scanline
+    fn scanline(mapper: &mut Mmc3)
+    {
+        for tile in 0..34
+        {
+            mapper.ppu_read(tile * 16);
+            mapper.ppu_read(tile * 16 + 8);
+        }
+
+        for sprite in 0..8
+        {
+            mapper.ppu_read(0x1000 + sprite * 16);
+            mapper.ppu_read(0x1000 + sprite * 16 + 8);
+        }
+    }
+
+    #[test]
+    fn prg_modes()
+    {
+        let mut mapper = mmc3(16, 8);
+
+        set_bank(&mut mapper, 0, 6, 3);
+        set_bank(&mut mapper, 0, 7, 4);
+
+        assert_eq!(Some(3), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(4), mapper.cpu_peek(0xA000));
+        assert_eq!(Some(14), mapper.cpu_peek(0xC000));
+        assert_eq!(Some(15), mapper.cpu_peek(0xE000));
+
+        // $8000 and $C000 are swapped
+        mapper.cpu_write(0x8000, 0b0100_0000);
+
+        assert_eq!(Some(14), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(4), mapper.cpu_peek(0xA000));
+        assert_eq!(Some(3), mapper.cpu_peek(0xC000));
+        assert_eq!(Some(15), mapper.cpu_peek(0xFFFF));
+    }
+
+    #[test]
+    fn chr_modes()
+    {
+        let mut mapper = mmc3(4, 16);
+
+        set_bank(&mut mapper, 0, 0, 3); // Low bit ignored for 2K banks
+        set_bank(&mut mapper, 0, 1, 8);
+        set_bank(&mut mapper, 0, 2, 12);
+        set_bank(&mut mapper, 0, 5, 15);
+
+        assert_eq!(0x82, mapper.ppu_read(0x0000));
+        assert_eq!(0x83, mapper.ppu_read(0x0400));
+        assert_eq!(0x88, mapper.ppu_read(0x0800));
+        assert_eq!(0x8C, mapper.ppu_read(0x1000));
+        assert_eq!(0x8F, mapper.ppu_read(0x1FFF));
+
+        // The 2K banks move to $1000
+        mapper.cpu_write(0x8000, 0b1000_0000);
+
+        assert_eq!(0x8C, mapper.ppu_read(0x0000));
+        assert_eq!(0x82, mapper.ppu_read(0x1000));
+        assert_eq!(0x89, mapper.ppu_read(0x1C00));
+    }
+
+    #[test]
+    fn mirroring_and_prg_ram()
+    {
+        let mut mapper = mmc3(4, 8);
+
+        mapper.cpu_write(0xA000, 1);
+        assert_eq!(Mirroring::Horizontal, mapper.mirroring());
+
+        mapper.cpu_write(0x6000, 0x42);
+        mapper.cpu_write(0xA001, 0b1100_0000); // Write protected
+        mapper.cpu_write(0x6000, 0x24);
+        assert_eq!(Some(0x42), mapper.cpu_peek(0x6000));
+
+        mapper.cpu_write(0xA001, 0);
+        assert_eq!(None, mapper.cpu_peek(0x6000));
+
+        let rom = Rom { prg: vec![0; 0x8000], mapper: 4, mirroring: Mirroring::FourScreen, ..Rom::empty() };
+        let mut mapper = Mmc3::new(rom).unwrap();
+
+        mapper.cpu_write(0xA000, 1);
+        assert_eq!(Mirroring::FourScreen, mapper.mirroring());
+    }
+
+    #[test]
+    fn scanline_counter()
+    {
+        let mut mapper = mmc3(4, 8);
+
+        mapper.cpu_write(0xC000, 2);
+        mapper.cpu_write(0xC001, 0);
+        mapper.cpu_write(0xE001, 0);
+
+        // Reloaded, then counted down to 0
+        scanline(&mut mapper);
+        scanline(&mut mapper);
+        assert!(!mapper.irq());
+
+        scanline(&mut mapper);
+        assert!(mapper.irq());
+
+        // Acknowledged, then reloaded on the next clock
+        mapper.cpu_write(0xE000, 0);
+        mapper.cpu_write(0xE001, 0);
+        assert!(!mapper.irq());
+
+        for _ in 0..3
+        {
+            scanline(&mut mapper);
+        }
+
+        assert!(mapper.irq());
+    }
+
+    #[test]
+    fn counter_follows_a12()
+    {
+        let mut mapper = mmc3(4, 8);
+
+        mapper.cpu_write(0xC000, 5);
+        mapper.cpu_write(0xC001, 0);
+
+        // Reads staying in $1000-$1FFF are a single rising edge
+        for addr in 0x1000..0x1100
+        {
+            mapper.ppu_read(addr);
+        }
+
+        assert_eq!(5, mapper.irq_counter);
+
+        // Without sprites from $1000, nothing clocks the counter
+        for tile in 0..34
+        {
+            mapper.ppu_read(tile * 16);
+        }
+
+        assert_eq!(5, mapper.irq_counter);
+
+        mapper.ppu_write(0x1000, 0);
+        assert_eq!(4, mapper.irq_counter);
+    }
+
+    #[test]
+    fn cpu_irq()
+    {
+        // CLI ; JMP $E001, the IRQ handler is at $E010
+        let mut prg = vec![0; 4 * PRG_BANK_SIZE];
+        let last = 3 * PRG_BANK_SIZE;
+        prg[last..last + 4].copy_from_slice(&[0x58, 0x4C, 0x01, 0xE0]);
+        prg[last + 0x1FFD] = 0xE0;
+        prg[last + 0x1FFE] = 0x10;
+        prg[last + 0x1FFF] = 0xE0;
+
+        let mut cpu = Cpu::new();
+        cpu.load_rom(Rom { prg, mapper: 4, ..Rom::empty() }).unwrap();
+        cpu.reset();
+
+        cpu.memory.write(0xC000, 0);
+        cpu.memory.write(0xC001, 0);
+        cpu.memory.write(0xE001, 0);
+
+        cpu.step().unwrap();
+        assert_eq!(None, cpu.step().unwrap().interrupt);
+
+        cpu.memory.ppu_read(0x0000);
+        cpu.memory.ppu_read(0x1000);
+
+        assert_eq!(Some(Interrupt::Irq), cpu.step().unwrap().interrupt);
+        assert_eq!(0xE010, cpu.registers().pc);
+    }
+}
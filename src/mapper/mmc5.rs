@@ -0,0 +1,728 @@
+use crate::rom::{Rom, Mirroring};
+
+use super::{Mapper, chr_or_ram, prg_ram, PRG_RAM_START, PRG_RAM_END, PRG_ROM_START};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+const PRG_RAM_SIZE:  usize = 8 * PRG_BANK_SIZE; // The largest boards, smaller ones see it mirrored
+const EXRAM_START:   u16 = 0x5C00;
+const EXRAM_END:     u16 = 0x5FFF;
+const ATTRIBUTES:    usize = 0x3C0; // Offset of the attribute table in a nametable
+
+const PRG_ROM: u8 = 0b1000_0000; // In a PRG bank register, RAM otherwise
+
+// Order of the PPU reads on a rendered scanline, counted from the first nametable fetch
+const BACKGROUND_FETCHES: usize = 32 * 4;
+const SPRITE_FETCHES:     usize = 8 * 4;
+
+/// Mapper 5, the ExROM boards.
+/// MMC5 follows the rendering from the PPU reads, without knowing the PPU timing: a scanline starts
+/// when the same nametable address is read 3 times in a row (the dummy fetches at the end of the previous line),
+/// then counting the reads tells background and sprite fetches apart.
+/// The frame ends on the NMI vector read or when rendering is disabled.
+/// Vertical split and sound are not emulated
+pub struct Mmc5
+{
+    prg:            Vec<u8>,
+    prg_ram:        Vec<u8>,
+    chr:            Vec<u8>,
+    chr_ram:        bool,
+    battery:        bool,
+    exram:          [u8; 0x400],
+    prg_mode:       u8,
+    chr_mode:       u8,
+    prg_protect:    [u8; 2], // PRG RAM is writable with 2 then 1
+    exram_mode:     u8, // 0 nametable, 1 extended attributes, 2 RAM, 3 read-only RAM
+    nametables:     u8, // 2 bits per nametable: VRAM page 0 or 1, ExRAM, or fill mode
+    fill_tile:      u8,
+    fill_attribute: u8,
+    prg_banks:      [u8; 5], // $5113 for PRG RAM at $6000, then $5114-$5117
+    sprite_banks:   [u16; 8], // Set A, $5120-$5127, with the upper bits of $5130
+    bg_banks:       [u16; 4], // Set B, $5128-$512B, for the background with 8x16 sprites
+    last_set_bg:    bool, // With 8x8 sprites, the last written set is used for everything
+    chr_upper:      u8,
+    multiplicands:  [u8; 2],
+    irq_compare:    u8,
+    irq_enabled:    bool,
+    irq_pending:    bool,
+    sprites_8x16:   bool, // From PPUCTRL
+    rendering:      bool, // From PPUMASK
+    in_frame:       bool,
+    scanline:       u8,
+    last_nametable: Option<u16>, // Last PPU read if it was a nametable, with the number of repeats
+    repeats:        u8,
+    fetch:          usize, // Number of PPU reads since the start of the scanline
+    ext_tile:       Option<u8>, // ExRAM byte of the background tile being fetched, with extended attributes
+}
+
+impl Mmc5
+{
+    pub fn new(rom: Rom) -> Result<Mmc5, String>
+    {
+        if rom.prg.is_empty() || !rom.prg.len().is_multiple_of(PRG_BANK_SIZE)
+        {
+            return Err(format!("MMC5 needs PRG ROM in 8K banks, not {}K", rom.prg.len() / 1024));
+        }
+
+        let (chr, chr_ram) = chr_or_ram(rom.chr);
+
+        Ok(Mmc5 {
+            prg: rom.prg,
+            prg_ram: prg_ram(rom.prg_ram_size, PRG_RAM_SIZE),
+            chr,
+            chr_ram,
+            battery: rom.battery,
+            exram: [0; 0x400],
+            prg_mode: 3,
+            chr_mode: 0,
+            prg_protect: [0, 0],
+            exram_mode: 0,
+            nametables: 0,
+            fill_tile: 0,
+            fill_attribute: 0,
+            prg_banks: [0, 0, 0, 0, 0xFF],
+            sprite_banks: [0; 8],
+            bg_banks: [0; 4],
+            last_set_bg: false,
+            chr_upper: 0,
+            multiplicands: [0xFF, 0xFF],
+            irq_compare: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            sprites_8x16: false,
+            rendering: false,
+            in_frame: false,
+            scanline: 0,
+            last_nametable: None,
+            repeats: 0,
+            fetch: 0,
+            ext_tile: None,
+        })
+    }
+
+    // Bank register for an 8K slot of $8000-$FFFF, bit 7 set for ROM
+    fn prg_bank(&self, addr: u16) -> u8
+    {
+        let slot = ((addr - PRG_ROM_START) as usize / PRG_BANK_SIZE) as u8;
+        let register = |slot: u8| self.prg_banks[slot as usize + 1];
+
+        // Larger banks ignore the low bits of the register
+        match (self.prg_mode, slot)
+        {
+            (0, _)          => (register(3) & 0xFC) | slot | PRG_ROM,
+            (1, 0..=1)      => (register(1) & 0xFE) | (slot & 1),
+            (1, _)          => (register(3) & 0xFE) | (slot & 1) | PRG_ROM,
+            (2, 0..=1)      => (register(1) & 0xFE) | (slot & 1),
+            (2, 2) | (3, 0..=2) => register(slot),
+            _               => register(3) | PRG_ROM,
+        }
+    }
+
+    fn prg_ram_addr(&self, bank: u8, addr: u16) -> usize
+    {
+        ((bank & 0x07) as usize * PRG_BANK_SIZE + addr as usize % PRG_BANK_SIZE) % self.prg_ram.len()
+    }
+
+    fn prg_ram_writable(&self) -> bool
+    {
+        self.prg_protect == [0b10, 0b01]
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize
+    {
+        // Extended attributes select a 4K bank for each background tile
+        if let Some(ext) = self.ext_tile.filter(|_| self.background_fetch())
+        {
+            let bank = (ext & 0x3F) as usize | (self.chr_upper as usize) << 6;
+
+            return (bank * 4 * CHR_BANK_SIZE + addr as usize % (4 * CHR_BANK_SIZE)) % self.chr.len();
+        }
+
+        let slot = addr as usize / CHR_BANK_SIZE;
+        let size = 8 >> self.chr_mode; // In 1K
+
+        // The last register of the bank is used, set B only has 4K which are repeated
+        let bank = if self.background_set()
+        {
+            let size_bg = size.min(4);
+            self.bg_banks[((slot % 4) / size_bg + 1) * size_bg - 1]
+        }
+        else
+        {
+            self.sprite_banks[(slot / size + 1) * size - 1]
+        } as usize;
+
+        let size = size * CHR_BANK_SIZE;
+
+        (bank * size + addr as usize % size) % self.chr.len()
+    }
+
+    fn background_set(&self) -> bool
+    {
+        if self.in_frame && self.sprites_8x16
+        {
+            !self.sprite_fetch()
+        }
+        else
+        {
+            self.last_set_bg
+        }
+    }
+
+    fn sprite_fetch(&self) -> bool
+    {
+        (BACKGROUND_FETCHES..BACKGROUND_FETCHES + SPRITE_FETCHES).contains(&self.fetch)
+    }
+
+    fn background_fetch(&self) -> bool
+    {
+        self.in_frame && self.exram_mode == 1 && !self.sprite_fetch()
+    }
+
+    fn nametable_value(&self, addr: u16) -> Option<u8>
+    {
+        let offset = addr as usize & 0x3FF;
+        let nametable = (addr >> 10) & 0b11;
+
+        match (self.nametables >> (2 * nametable)) & 0b11
+        {
+            0 | 1 => None,
+            2 if self.exram_mode <= 1 => Some(self.exram[offset]),
+            2 => Some(0),
+            _ if offset < ATTRIBUTES => Some(self.fill_tile),
+            _ => Some(self.fill_attribute * 0b0101_0101),
+        }
+    }
+
+    fn detect_scanline(&mut self, addr: u16)
+    {
+        if self.last_nametable == Some(addr)
+        {
+            self.repeats = self.repeats.saturating_add(1);
+        }
+        else
+        {
+            self.last_nametable = Some(addr);
+            self.repeats = 0;
+        }
+
+        if self.repeats != 2 { return }
+
+        if self.in_frame
+        {
+            self.scanline = self.scanline.wrapping_add(1);
+
+            if self.scanline == self.irq_compare
+            {
+                self.irq_pending = true;
+            }
+        }
+        else
+        {
+            self.in_frame = true;
+            self.scanline = 0;
+        }
+
+        self.fetch = 0;
+    }
+
+    fn end_frame(&mut self)
+    {
+        self.in_frame = false;
+        self.last_nametable = None;
+        self.ext_tile = None;
+    }
+}
+
+impl Mapper for Mmc5
+{
+    fn cpu_read(&mut self, addr: u16) -> Option<u8>
+    {
+        let value = self.cpu_peek(addr);
+
+        match addr
+        {
+            0x5204 => self.irq_pending = false,
+            0xFFFA | 0xFFFB => self.end_frame(),
+            _ => {}
+        }
+
+        value
+    }
+
+    fn cpu_peek(&self, addr: u16) -> Option<u8>
+    {
+        let product = self.multiplicands[0] as u16 * self.multiplicands[1] as u16;
+
+        match addr
+        {
+            0x5204 => Some((self.irq_pending as u8) << 7 | (self.in_frame as u8) << 6),
+            0x5205 => Some(product as u8),
+            0x5206 => Some((product >> 8) as u8),
+            EXRAM_START..=EXRAM_END if self.exram_mode >= 2 => Some(self.exram[(addr - EXRAM_START) as usize]),
+            PRG_RAM_START..=PRG_RAM_END => Some(self.prg_ram[self.prg_ram_addr(self.prg_banks[0], addr)]),
+            PRG_ROM_START..=0xFFFF => {
+                let bank = self.prg_bank(addr);
+
+                if bank & PRG_ROM == 0
+                {
+                    return Some(self.prg_ram[self.prg_ram_addr(bank, addr)]);
+                }
+
+                let index = (bank & !PRG_ROM) as usize * PRG_BANK_SIZE + addr as usize % PRG_BANK_SIZE;
+                Some(self.prg[index % self.prg.len()])
+            },
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8)
+    {
+        match addr
+        {
+            0x5100 => self.prg_mode = value & 0b11,
+            0x5101 => self.chr_mode = value & 0b11,
+            0x5102 | 0x5103 => self.prg_protect[(addr - 0x5102) as usize] = value & 0b11,
+            0x5104 => self.exram_mode = value & 0b11,
+            0x5105 => self.nametables = value,
+            0x5106 => self.fill_tile = value,
+            0x5107 => self.fill_attribute = value & 0b11,
+            0x5113..=0x5117 => self.prg_banks[(addr - 0x5113) as usize] = value,
+            0x5120..=0x5127 => {
+                self.sprite_banks[(addr - 0x5120) as usize] = value as u16 | (self.chr_upper as u16) << 8;
+                self.last_set_bg = false;
+            },
+            0x5128..=0x512B => {
+                self.bg_banks[(addr - 0x5128) as usize] = value as u16 | (self.chr_upper as u16) << 8;
+                self.last_set_bg = true;
+            },
+            0x5130 => self.chr_upper = value & 0b11,
+            0x5203 => self.irq_compare = value,
+            0x5204 => self.irq_enabled = value & 0b1000_0000 != 0,
+            0x5205 | 0x5206 => self.multiplicands[(addr - 0x5205) as usize] = value,
+            EXRAM_START..=EXRAM_END if self.exram_mode != 3 => self.exram[(addr - EXRAM_START) as usize] = value,
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_writable() => {
+                let index = self.prg_ram_addr(self.prg_banks[0], addr);
+                self.prg_ram[index] = value;
+            },
+            PRG_ROM_START..=0xFFFF if self.prg_ram_writable() => {
+                let bank = self.prg_bank(addr);
+
+                if bank & PRG_ROM == 0
+                {
+                    let index = self.prg_ram_addr(bank, addr);
+                    self.prg_ram[index] = value;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8
+    {
+        self.last_nametable = None;
+
+        let value = self.chr[self.chr_addr(addr)];
+        self.fetch += 1;
+
+        value
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8)
+    {
+        if self.chr_ram
+        {
+            let addr = self.chr_addr(addr);
+            self.chr[addr] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        let page = |nametable: u8| (self.nametables >> (2 * nametable)) & 1;
+
+        Mirroring::Custom([page(0), page(1), page(2), page(3)])
+    }
+
+    fn nametable_read(&mut self, addr: u16) -> Option<u8>
+    {
+        if self.rendering
+        {
+            self.detect_scanline(addr);
+        }
+
+        let mut value = self.nametable_value(addr);
+
+        if self.background_fetch()
+        {
+            if addr as usize & 0x3FF < ATTRIBUTES
+            {
+                self.ext_tile = Some(self.exram[addr as usize & 0x3FF]);
+            }
+            else if let Some(ext) = self.ext_tile
+            {
+                value = Some((ext >> 6) * 0b0101_0101);
+            }
+        }
+
+        self.fetch += 1;
+
+        value
+    }
+
+    fn nametable_write(&mut self, addr: u16, value: u8) -> bool
+    {
+        let nametable = (addr >> 10) & 0b11;
+
+        match (self.nametables >> (2 * nametable)) & 0b11
+        {
+            0 | 1 => false,
+            2 => {
+                if self.exram_mode <= 1
+                {
+                    self.exram[addr as usize & 0x3FF] = value;
+                }
+
+                true
+            },
+            _ => true,
+        }
+    }
+
+    fn ppu_register_write(&mut self, addr: u16, value: u8)
+    {
+        match addr
+        {
+            0x2000 => self.sprites_8x16 = value & 0b0010_0000 != 0,
+            0x2001 => {
+                self.rendering = value & 0b0001_1000 != 0;
+
+                if !self.rendering
+                {
+                    self.end_frame();
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn irq(&self) -> bool
+    {
+        self.irq_pending && self.irq_enabled
+    }
+
+    fn battery_ram(&self) -> Option<&[u8]>
+    {
+        super::battery_ram(&self.prg_ram, self.battery)
+    }
+
+    fn load_battery_ram(&mut self, data: &[u8])
+    {
+        super::load_battery_ram(&mut self.prg_ram, data);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::mapper::numbered_rom;
+    use super::*;
+
+    // Numbered in 8K PRG banks and 1K CHR banks
+    fn mmc5() -> Mmc5
+    {
+        Mmc5::new(numbered_rom(5, 32, PRG_BANK_SIZE, 512, CHR_BANK_SIZE)).unwrap()
+    }
+
+    // The fetches of a tile: nametable, attribute, then both planes of the pattern
+    fn tile(mapper: &mut Mmc5, column: u16) -> (Option<u8>, Option<u8>, u8)
+    {
+        let name = mapper.nametable_read(0x2000 + column);
+        let attribute = mapper.nametable_read(0x23C0 + column / 4);
+        let pattern = mapper.ppu_read(0x0000);
+        mapper.ppu_read(0x0008);
+
+        (name, attribute, pattern)
+    }
+
+    // A rendered scanline as the PPU reads it, returns what the first tile and the first sprite fetched
+    fn scanline(mapper: &mut Mmc5) -> ((Option<u8>, Option<u8>, u8), u8)
+    {
+        // The first 2 tiles were fetched at the end of the previous line
+        let first = tile(mapper, 2);
+
+        for column in 3..34
+        {
+            tile(mapper, column % 32);
+        }
+
+        // 2 garbage nametable reads for each sprite
+        let mut sprite = 0;
+
+        for index in 0..8
+        {
+            mapper.nametable_read(0x2000);
+            mapper.nametable_read(0x2000);
+
+            let pattern = mapper.ppu_read(0x1000);
+            mapper.ppu_read(0x1008);
+
+            if index == 0 { sprite = pattern }
+        }
+
+        tile(mapper, 0);
+        tile(mapper, 1);
+
+        // Dummy reads, the next line starts with the same address
+        mapper.nametable_read(0x2002);
+        mapper.nametable_read(0x2002);
+
+        (first, sprite)
+    }
+
+    fn start_rendering(mapper: &mut Mmc5, sprites_8x16: bool)
+    {
+        mapper.ppu_register_write(0x2000, if sprites_8x16 { 0b0010_0000 } else { 0 });
+        mapper.ppu_register_write(0x2001, 0b0001_1000);
+
+        // Pre-render line
+        scanline(mapper);
+    }
+
+    #[test]
+    fn prg_modes()
+    {
+        let mut mapper = mmc5();
+
+        // Mode 3 at power on, the last bank at $E000
+        assert_eq!(Some(31), mapper.cpu_peek(0xE000));
+
+        for (register, bank) in (0x5114..=0x5117).zip([0x81, 0x83, 0x85, 0x87])
+        {
+            mapper.cpu_write(register, bank);
+        }
+
+        let banks = |mapper: &Mmc5| [0x8000, 0xA000, 0xC000, 0xE000].map(|addr| mapper.cpu_peek(addr).unwrap());
+
+        assert_eq!([1, 3, 5, 7], banks(&mapper));
+
+        mapper.cpu_write(0x5100, 2);
+        assert_eq!([2, 3, 5, 7], banks(&mapper));
+
+        mapper.cpu_write(0x5100, 1);
+        assert_eq!([2, 3, 6, 7], banks(&mapper));
+
+        mapper.cpu_write(0x5100, 0);
+        assert_eq!([4, 5, 6, 7], banks(&mapper));
+    }
+
+    #[test]
+    fn prg_ram()
+    {
+        let mut mapper = mmc5();
+
+        // Protected until $5102 and $5103 are set
+        mapper.cpu_write(0x6000, 0x42);
+        assert_eq!(Some(0), mapper.cpu_peek(0x6000));
+
+        mapper.cpu_write(0x5102, 2);
+        mapper.cpu_write(0x5103, 1);
+        mapper.cpu_write(0x5113, 3);
+        mapper.cpu_write(0x6000, 0x42);
+
+        // The same RAM bank mapped at $8000
+        mapper.cpu_write(0x5114, 3);
+        assert_eq!(Some(0x42), mapper.cpu_peek(0x8000));
+
+        mapper.cpu_write(0x8001, 0x24);
+        assert_eq!(Some(0x24), mapper.cpu_peek(0x6001));
+
+        // Not $E000, always ROM
+        mapper.cpu_write(0x5117, 3);
+        assert_eq!(Some(3), mapper.cpu_peek(0xE000));
+    }
+
+    #[test]
+    fn battery_ram()
+    {
+        let mut mapper = Mmc5::new(Rom { battery: true, prg_ram_size: 0x2000, ..numbered_rom(5, 32, PRG_BANK_SIZE, 512, CHR_BANK_SIZE) }).unwrap();
+
+        mapper.load_battery_ram(&[0x24; 0x2000]);
+        assert_eq!(Some(&[0x24; 0x2000][..]), mapper.battery_ram());
+
+        // An 8K board sees its only bank in every slot
+        mapper.cpu_write(0x5113, 5);
+        assert_eq!(Some(0x24), mapper.cpu_peek(0x7FFF));
+
+        assert_eq!(None, mmc5().battery_ram());
+    }
+
+    #[test]
+    fn chr_modes()
+    {
+        let mut mapper = mmc5();
+
+        for (register, bank) in (0x5120..=0x5127).zip(0x10..)
+        {
+            mapper.cpu_write(register, bank);
+        }
+
+        let banks = |mapper: &mut Mmc5| (0..8).map(|slot| mapper.ppu_read(slot * 0x400)).collect::<Vec<_>>();
+
+        // 8K from $5127
+        assert_eq!(vec![0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E, 0x3F], banks(&mut mapper));
+
+        mapper.cpu_write(0x5101, 1);
+        assert_eq!(vec![0xCC, 0xCD, 0xCE, 0xCF, 0xDC, 0xDD, 0xDE, 0xDF], banks(&mut mapper));
+
+        mapper.cpu_write(0x5101, 2);
+        assert_eq!(vec![0xA2, 0xA3, 0xA6, 0xA7, 0xAA, 0xAB, 0xAE, 0xAF], banks(&mut mapper));
+
+        mapper.cpu_write(0x5101, 3);
+        assert_eq!(vec![0x90, 0x91, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97], banks(&mut mapper));
+
+        // Upper bits, taken when the register is written
+        mapper.cpu_write(0x5130, 1);
+        mapper.cpu_write(0x5120, 0x10);
+        mapper.cpu_write(0x5130, 0);
+        assert_eq!(0x110, mapper.sprite_banks[0]);
+    }
+
+    #[test]
+    fn sprite_and_background_sets()
+    {
+        let mut mapper = mmc5();
+
+        mapper.cpu_write(0x5101, 1);
+        mapper.cpu_write(0x5127, 2); // Sprites at $1000, 4K bank 2
+        mapper.cpu_write(0x512B, 5); // Background at $0000, 4K bank 5
+
+        // With 8x8 sprites, the last written set
+        start_rendering(&mut mapper, false);
+        let ((_, _, background), sprite) = scanline(&mut mapper);
+
+        assert_eq!((0x94, 0x94), (background, sprite));
+
+        start_rendering(&mut mapper, true);
+        let ((_, _, background), sprite) = scanline(&mut mapper);
+
+        assert_eq!((0x94, 0x88), (background, sprite));
+
+        // Outside of rendering, as with PPUDATA
+        mapper.cpu_read(0xFFFA);
+        assert_eq!(0x94, mapper.ppu_read(0x1000));
+    }
+
+    #[test]
+    fn exram_modes()
+    {
+        let mut mapper = mmc5();
+
+        // As a nametable, the CPU does not read it back
+        mapper.cpu_write(0x5105, 0b10_00_10_00);
+        mapper.cpu_write(0x5C05, 0x42);
+
+        assert_eq!(None, mapper.cpu_peek(0x5C05));
+        assert_eq!(Some(0x42), mapper.nametable_read(0x2405));
+        assert_eq!(None, mapper.nametable_read(0x2005));
+        assert!(mapper.nametable_write(0x2C06, 0x24));
+        assert!(!mapper.nametable_write(0x2806, 0x24));
+        assert_eq!(Some(0x24), mapper.nametable_read(0x2406));
+        assert_eq!(Mirroring::Custom([0, 0, 0, 0]), mapper.mirroring());
+
+        mapper.cpu_write(0x5104, 2);
+        mapper.cpu_write(0x5C05, 0x11);
+        assert_eq!(Some(0x11), mapper.cpu_peek(0x5C05));
+        assert_eq!(Some(0), mapper.nametable_read(0x2405));
+
+        // Read-only
+        mapper.cpu_write(0x5104, 3);
+        mapper.cpu_write(0x5C05, 0x22);
+        assert_eq!(Some(0x11), mapper.cpu_peek(0x5C05));
+    }
+
+    #[test]
+    fn fill_mode()
+    {
+        let mut mapper = mmc5();
+
+        mapper.cpu_write(0x5105, 0b11_01_00_11);
+        mapper.cpu_write(0x5106, 0x42);
+        mapper.cpu_write(0x5107, 0b10);
+
+        assert_eq!(Some(0x42), mapper.nametable_read(0x2000));
+        assert_eq!(Some(0b1010_1010), mapper.nametable_read(0x23C0));
+        assert_eq!(Some(0x42), mapper.nametable_read(0x2C10));
+        assert_eq!(Mirroring::Custom([1, 0, 1, 1]), mapper.mirroring());
+    }
+
+    #[test]
+    fn extended_attributes()
+    {
+        let mut mapper = mmc5();
+
+        mapper.cpu_write(0x5104, 1);
+        mapper.cpu_write(0x5C02, 0b1100_0111); // Palette 3, 4K bank 7
+        mapper.cpu_write(0x5C03, 0b0100_0001);
+
+        start_rendering(&mut mapper, false);
+        let ((name, attribute, pattern), _) = scanline(&mut mapper);
+
+        assert_eq!(None, name);
+        assert_eq!(Some(0xFF), attribute);
+        assert_eq!(0x9C, pattern);
+
+        let (_, attribute, pattern) = tile(&mut mapper, 3);
+
+        assert_eq!(Some(0b0101_0101), attribute);
+        assert_eq!(0x84, pattern);
+    }
+
+    #[test]
+    fn multiplier()
+    {
+        let mut mapper = mmc5();
+
+        assert_eq!(Some(0x01), mapper.cpu_peek(0x5205));
+        assert_eq!(Some(0xFE), mapper.cpu_peek(0x5206));
+
+        mapper.cpu_write(0x5205, 200);
+        mapper.cpu_write(0x5206, 123);
+
+        assert_eq!(Some((24600 & 0xFF) as u8), mapper.cpu_peek(0x5205));
+        assert_eq!(Some((24600 >> 8) as u8), mapper.cpu_peek(0x5206));
+    }
+
+    #[test]
+    fn scanline_irq()
+    {
+        let mut mapper = mmc5();
+
+        mapper.cpu_write(0x5203, 3);
+        mapper.cpu_write(0x5204, 0x80);
+
+        start_rendering(&mut mapper, false);
+        assert_eq!(Some(0), mapper.cpu_peek(0x5204));
+
+        // Line 0 is when the frame starts
+        for _ in 0..3
+        {
+            scanline(&mut mapper);
+        }
+
+        assert_eq!(Some(0x40), mapper.cpu_peek(0x5204));
+        assert!(!mapper.irq());
+
+        // Raised when line 3 starts
+        mapper.nametable_read(0x2002);
+        assert!(mapper.irq());
+
+        // Reading the status acknowledges it
+        assert_eq!(Some(0xC0), mapper.cpu_read(0x5204));
+        assert!(!mapper.irq());
+
+        // The NMI vector read ends the frame
+        mapper.cpu_read(0xFFFA);
+        assert_eq!(Some(0), mapper.cpu_peek(0x5204));
+    }
+}
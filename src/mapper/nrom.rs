@@ -0,0 +1,132 @@
+use crate::rom::{Rom, Mirroring, PRG_BANK_SIZE};
+
+use super::{Mapper, chr_or_ram, prg_ram, PRG_RAM_START, PRG_RAM_END, PRG_ROM_START, PRG_RAM_SIZE};
+
+/// Mapper 0, 16K or 32K of PRG ROM without any bank switching
+pub struct Nrom
+{
+    prg:       Vec<u8>,
+    prg_ram:   Vec<u8>, // Family Basic has some, others simply ignore it
+    chr:       Vec<u8>,
+    chr_ram:   bool,
+    mirroring: Mirroring,
+}
+
+impl Nrom
+{
+    pub fn new(rom: Rom) -> Result<Nrom, String>
+    {
+        if rom.prg.len() != PRG_BANK_SIZE && rom.prg.len() != 2 * PRG_BANK_SIZE
+        {
+            return Err(format!("NROM needs 16K or 32K of PRG ROM, not {}K", rom.prg.len() / 1024));
+        }
+
+        let (chr, chr_ram) = chr_or_ram(rom.chr);
+
+        Ok(Nrom {
+            prg: rom.prg,
+            prg_ram: prg_ram(rom.prg_ram_size, PRG_RAM_SIZE),
+            chr,
+            chr_ram,
+            mirroring: rom.mirroring,
+        })
+    }
+}
+
+impl Mapper for Nrom
+{
+    fn cpu_peek(&self, addr: u16) -> Option<u8>
+    {
+        match addr
+        {
+            PRG_RAM_START..=PRG_RAM_END => Some(self.prg_ram[(addr - PRG_RAM_START) as usize]),
+            // A single 16K bank is mirrored at $C000
+            PRG_ROM_START..=0xFFFF => Some(self.prg[(addr - PRG_ROM_START) as usize % self.prg.len()]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8)
+    {
+        if let PRG_RAM_START..=PRG_RAM_END = addr
+        {
+            self.prg_ram[(addr - PRG_RAM_START) as usize] = value;
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8
+    {
+        self.chr[addr as usize & 0x1FFF]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8)
+    {
+        if self.chr_ram
+        {
+            self.chr[addr as usize & 0x1FFF] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::mapper::numbered_rom;
+    use crate::rom::CHR_BANK_SIZE;
+    use super::*;
+
+    fn nrom(prg_banks: usize, chr_banks: usize) -> Nrom
+    {
+        Nrom::new(numbered_rom(0, prg_banks, PRG_BANK_SIZE, chr_banks, CHR_BANK_SIZE)).unwrap()
+    }
+
+    #[test]
+    fn prg_mirroring()
+    {
+        let mapper = nrom(1, 0);
+
+        assert_eq!(Some(0), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(0), mapper.cpu_peek(0xC000));
+
+        let mapper = nrom(2, 0);
+
+        assert_eq!(Some(0), mapper.cpu_peek(0xBFFF));
+        assert_eq!(Some(1), mapper.cpu_peek(0xC000));
+        assert_eq!(None, mapper.cpu_peek(0x5000));
+    }
+
+    #[test]
+    fn rom_writes_are_ignored()
+    {
+        let mut mapper = nrom(1, 0);
+
+        mapper.cpu_write(0x8000, 0xFF);
+        mapper.cpu_write(0x6000, 0x42);
+
+        assert_eq!(Some(0), mapper.cpu_peek(0x8000));
+        assert_eq!(Some(0x42), mapper.cpu_peek(0x6000));
+    }
+
+    #[test]
+    fn chr_rom_and_ram()
+    {
+        let mut mapper = nrom(1, 1);
+        mapper.ppu_write(0x0000, 0xFF);
+        assert_eq!(0x80, mapper.ppu_read(0x0000));
+
+        let mut mapper = nrom(1, 0);
+        mapper.ppu_write(0x1FFF, 0xFF);
+        assert_eq!(0xFF, mapper.ppu_read(0x1FFF));
+    }
+
+    #[test]
+    fn invalid_prg_size()
+    {
+        assert!(Nrom::new(Rom { prg: vec![0; 0x1000], ..Rom::empty() }).is_err());
+    }
+}
@@ -3,12 +3,15 @@ pub const PRG_BANK_SIZE: usize = 16384;
 pub const CHR_BANK_SIZE: usize = 8192;
 pub const PRG_RAM_UNIT:  usize = 8192;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Mirroring
 {
     Horizontal,
     Vertical,
-    FourScreen
+    FourScreen,
+    SingleScreenLower, // Controlled by the mapper, every nametable shows the same 1K
+    SingleScreenUpper,
+    Custom([u8; 4]) // Page of the console VRAM for each nametable, set by the mapper (MMC5)
 }
 
 struct RomHeader
@@ -18,7 +21,7 @@ struct RomHeader
     prg_ram_size: usize,
     mapper_number: u8,
     mirroring: Mirroring,
-    contains_prg_ram: bool,
+    battery: bool,
     contains_trainer: bool
 }
 
@@ -27,7 +30,9 @@ pub struct Rom
     pub prg: Vec<u8>,
     pub chr: Vec<u8>,
     pub mapper: u8,
-    pub mirroring: Mirroring
+    pub mirroring: Mirroring,
+    pub prg_ram_size: usize, // In bytes, 0 when the header leaves it to the mapper
+    pub battery: bool // The PRG RAM keeps its content, to be saved by the host
 }
 
 impl Rom
@@ -39,7 +44,9 @@ impl Rom
             prg: vec![],
             chr: vec![],
             mapper: 0,
-            mirroring: Mirroring::Horizontal
+            mirroring: Mirroring::Horizontal,
+            prg_ram_size: 0,
+            battery: false
         }
     }
 
@@ -57,8 +64,6 @@ impl Rom
     pub fn from(data: Vec<u8>) -> Result<Rom, String>
     {
         let header = Self::parse_header(&data[0..16])?;
-        let mapper = header.mapper_number;
-        let mirroring = header.mirroring;
 
         // Skip header and trainer if present
         let prg_rom_start = 16 + if header.contains_trainer { 512 } else { 0 };
@@ -67,12 +72,19 @@ impl Rom
         let chr_rom_start = prg_rom_end;
         let chr_rom_end = chr_rom_start + header.chr_banks * CHR_BANK_SIZE;
 
+        if data.len() < chr_rom_end
+        {
+            return Err(String::from("Truncated ROM file"));
+        }
+
         Ok(Rom
         {
             prg: data[prg_rom_start..prg_rom_end].to_vec(),
             chr: data[chr_rom_start..chr_rom_end].to_vec(),
-            mapper: mapper,
-            mirroring: mirroring
+            mapper: header.mapper_number,
+            mirroring: header.mirroring,
+            prg_ram_size: header.prg_ram_size,
+            battery: header.battery
         })
     }
 
@@ -94,8 +106,8 @@ impl Rom
         }
 
         // Control bits
-        let mapper_number = (header[6] & 0b1111_0000) >> 4 | (header[7] & 0b1111) << 4;
-        let contains_prg_ram = header[6] & 0b0000_0010 != 0;
+        let mapper_number = (header[6] & 0b1111_0000) >> 4 | (header[7] & 0b1111_0000);
+        let battery = header[6] & 0b0000_0010 != 0;
         let contains_trainer = header[6] & 0b0000_0100 != 0;
 
         let mirroring = if header[6] & 0b0000_1000 != 0
@@ -115,11 +127,11 @@ impl Rom
         {
             prg_banks: header[4] as usize,
             chr_banks: header[5] as usize,
-            prg_ram_size: header[8] as usize,
-            mapper_number: mapper_number,
-            mirroring: mirroring,
-            contains_prg_ram: contains_prg_ram,
-            contains_trainer: contains_trainer
+            prg_ram_size: header[8] as usize * PRG_RAM_UNIT,
+            mapper_number,
+            mirroring,
+            battery,
+            contains_trainer
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+use rust_nes::cpu::{Cpu, trace::RingBufferTracer};
+
+// Klaus Dormann's 6502_functional_test, the published 64K image from bin_files/ in
+// https://github.com/Klaus2m5/6502_65C02_functional_tests
+const IMAGE_PATH: &str = "resources/6502_functional_test.bin";
+
+const START: u16 = 0x0400;
+
+// Success trap of that image
+const SUCCESS: u16 = 0x3469;
+
+// The whole test runs about 30 million instructions
+const MAX_INSTRUCTIONS: u64 = 100_000_000;
+
+// Instructions reported on failure
+const TRACE_LENGTH: usize = 32;
+
+// The image is not redistributed with the sources
+#[test]
+#[ignore = "needs resources/6502_functional_test.bin"]
+fn functional_test()
+{
+    let image = fs::read(IMAGE_PATH).unwrap();
+    assert_eq!(0x10000, image.len(), "{} is not a 64K image", IMAGE_PATH);
+
+    let tracer = Rc::new(RefCell::new(RingBufferTracer::new(TRACE_LENGTH)));
+
+    let mut cpu = Cpu::flat();
+    cpu.memory.write_slice(0x0000, &image).unwrap();
+    cpu.memory.set_decimal_mode(true); // Tested unlike on the NES
+    cpu.set_tracer(Box::new(tracer.clone()));
+
+    let mut registers = cpu.registers();
+    registers.pc = START;
+    cpu.set_registers(registers);
+
+    let trace = || tracer.borrow().lines().collect::<Vec<&str>>().join("\n");
+
+    for _ in 0..MAX_INSTRUCTIONS
+    {
+        let pc = cpu.registers().pc;
+
+        if let Err(err) = cpu.step()
+        {
+            panic!("CPU failed at ${:04X}: {}\n{}", pc, err, trace());
+        }
+
+        // Every outcome is a jump or a branch to itself
+        if cpu.registers().pc == pc
+        {
+            assert_eq!(SUCCESS, pc, "Trapped at ${:04X} instead of ${:04X}, last instructions:\n{}", pc, SUCCESS, trace());
+            return;
+        }
+    }
+
+    panic!("No trap after {} instructions, last instructions:\n{}", MAX_INSTRUCTIONS, trace());
+}
@@ -0,0 +1,46 @@
+use std::fs;
+
+use rust_nes::cpu::{Cpu, trace};
+use rust_nes::rom::Rom;
+
+const ROM_PATH: &str = "resources/nestest.nes";
+const LOG_PATH: &str = "resources/nestest.log";
+
+// Entry point of the automated mode, which runs without a PPU
+const AUTOMATION_START: u16 = 0xC000;
+
+// The ROM stores the result of the official and unofficial tests there, 0 when all passed
+const OFFICIAL_RESULT: u16   = 0x0002;
+const UNOFFICIAL_RESULT: u16 = 0x0003;
+
+// The ROM and the reference log are not redistributed with the sources
+#[test]
+#[ignore = "needs resources/nestest.nes and resources/nestest.log"]
+fn nestest()
+{
+    let rom = Rom::from_file(ROM_PATH).unwrap();
+    let log = fs::read_to_string(LOG_PATH).unwrap();
+
+    let mut cpu = Cpu::new();
+    cpu.load_rom(rom).unwrap();
+    cpu.reset();
+
+    let mut registers = cpu.registers();
+    registers.pc = AUTOMATION_START;
+    cpu.set_registers(registers);
+
+    for (index, expected) in log.lines().enumerate()
+    {
+        let line = trace::nestest_line(&cpu);
+
+        assert_eq!(expected.trim_end(), line, "Divergence at line {} of {}", index + 1, LOG_PATH);
+
+        if let Err(err) = cpu.step()
+        {
+            panic!("CPU failed after line {}: {}", index + 1, err);
+        }
+    }
+
+    assert_eq!(0x00, cpu.memory.read(OFFICIAL_RESULT), "Official opcodes test failed");
+    assert_eq!(0x00, cpu.memory.read(UNOFFICIAL_RESULT), "Unofficial opcodes test failed");
+}
@@ -0,0 +1,243 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use rust_nes::cpu::{Bus, Cpu, ErrorKind, RegisterSnapshot, disasm};
+
+// Tom Harte's per-opcode vectors, one `xx.json` file per opcode, from
+// https://github.com/SingleStepTests/65x02 (the nes6502 set, which has no decimal mode)
+const TESTS_PATH: &str = "resources/single_step";
+
+// Bits 4 and 5 of P only exist once pushed on the stack
+const STATUS_MASK: u8 = 0b1100_1111;
+
+// Failures shown for each opcode, the others are only counted
+const FAILURES_SHOWN: usize = 3;
+
+// Opcodes whose failures are reported without failing the test, while passing them fails it so the list
+// stays accurate. The unstable ones depend on the chip and its temperature (SHA, SHX, SHY, TAS), and JAM
+// stops the CPU where the vectors keep reading
+const EXPECTED_FAILURES: &[u8] = &[
+    0x93, 0x9F, 0x9E, 0x9C, 0x9B,
+    0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2,
+];
+
+/// 64K of RAM logging every access the way the vectors list them
+struct LoggingBus
+{
+    ram: Vec<u8>,
+    log: RefCell<Vec<(u16, u8, &'static str)>>,
+}
+
+impl Bus for LoggingBus
+{
+    fn read(&self, addr: u16) -> u8
+    {
+        let value = self.ram[addr as usize];
+        self.log.borrow_mut().push((addr, value, "read"));
+
+        value
+    }
+
+    fn write(&mut self, addr: u16, value: u8) -> Result<(), ErrorKind>
+    {
+        self.log.borrow_mut().push((addr, value, "write"));
+        self.ram[addr as usize] = value;
+
+        Ok(())
+    }
+
+    fn peek(&self, addr: u16) -> u8
+    {
+        self.ram[addr as usize]
+    }
+}
+
+/// Failures of an opcode, grouped with its mnemonic and addressing mode
+struct Group
+{
+    name:     String,
+    count:    usize,
+    total:    usize,
+    failures: Vec<String>,
+}
+
+// The vectors are not redistributed with the sources, SINGLE_STEP_TESTS can point elsewhere
+#[test]
+#[ignore = "needs the vectors in resources/single_step or SINGLE_STEP_TESTS"]
+fn single_step()
+{
+    let path = env::var("SINGLE_STEP_TESTS").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from(TESTS_PATH));
+    assert!(path.is_dir(), "{} is not a directory", path.display());
+
+    let mut groups: BTreeMap<u8, Group> = BTreeMap::new();
+
+    for file in test_files(&path)
+    {
+        let tests: Value = serde_json::from_slice(&fs::read(&file).unwrap())
+            .unwrap_or_else(|err| panic!("Invalid JSON in {}: {}", file.display(), err));
+
+        for test in tests.as_array().into_iter().flatten()
+        {
+            let mut cpu = Cpu::with_bus(LoggingBus { ram: vec![0; 0x10000], log: RefCell::new(vec![]) });
+            load_state(&mut cpu, &test["initial"]);
+
+            let pc = cpu.registers().pc;
+            let opcode = cpu.memory.peek(pc);
+
+            let group = groups.entry(opcode).or_insert_with(|| Group {
+                name: match disasm::decode(|addr| cpu.memory.peek(addr), pc)
+                {
+                    Some(instruction) => format!("{:02X} {} {:?}", opcode, instruction.mnemonic(), instruction.mode),
+                    None => format!("{:02X} ???", opcode),
+                },
+                count: 0,
+                total: 0,
+                failures: vec![],
+            });
+
+            group.total += 1;
+
+            if let Err(error) = run(&mut cpu, test)
+            {
+                group.count += 1;
+
+                if group.failures.len() < FAILURES_SHOWN
+                {
+                    group.failures.push(format!("    {}: {}", test["name"].as_str().unwrap_or("?"), error));
+                }
+            }
+        }
+    }
+
+    let (expected, unexpected): (Vec<_>, Vec<_>) = groups.iter()
+        .partition(|(opcode, _)| EXPECTED_FAILURES.contains(opcode));
+
+    let failed: Vec<&Group> = unexpected.iter().map(|(_, group)| *group).filter(|group| group.count > 0).collect();
+    let passed: Vec<&Group> = expected.iter().map(|(_, group)| *group).filter(|group| group.count == 0).collect();
+
+    for (_, group) in expected.iter().filter(|(_, group)| group.count > 0)
+    {
+        eprintln!("Expected failure, {}: {} of {} failed", group.name, group.count, group.total);
+    }
+
+    if !failed.is_empty() || !passed.is_empty()
+    {
+        let mut report = format!("{} of {} opcodes failed, {} expected failures passed\n",
+            failed.len(), groups.len(), passed.len());
+
+        for group in failed
+        {
+            report += &format!("  {}: {} of {} failed\n{}\n", group.name, group.count, group.total, group.failures.join("\n"));
+        }
+
+        for group in passed
+        {
+            report += &format!("  {}: passed, remove it from EXPECTED_FAILURES\n", group.name);
+        }
+
+        panic!("{}", report);
+    }
+}
+
+// Sorted, so the report is the same from one run to the next
+fn test_files(path: &Path) -> Vec<PathBuf>
+{
+    let mut files: Vec<PathBuf> = fs::read_dir(path).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|file| file.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+
+    files.sort();
+    files
+}
+
+fn load_state(cpu: &mut Cpu<LoggingBus>, state: &Value)
+{
+    cpu.set_registers(registers(state));
+
+    for (addr, value) in ram(state)
+    {
+        cpu.memory.ram[addr as usize] = value;
+    }
+}
+
+fn run(cpu: &mut Cpu<LoggingBus>, test: &Value) -> Result<(), String>
+{
+    let step = cpu.step().map_err(|err| err.to_string())?;
+
+    let expected = registers(&test["final"]);
+    let mut actual = cpu.registers();
+
+    actual.p &= STATUS_MASK;
+
+    if actual != expected
+    {
+        return Err(format!("expected {}, got {}", expected, actual));
+    }
+
+    for (addr, value) in ram(&test["final"])
+    {
+        let actual = cpu.memory.peek(addr);
+
+        if actual != value
+        {
+            return Err(format!("expected ${:02X} at ${:04X}, got ${:02X}", value, addr, actual));
+        }
+    }
+
+    // `[[addr, value, "read" | "write"], ...]`, one access per cycle
+    let accesses: Vec<(u16, u8, &str)> = test["cycles"].as_array().into_iter().flatten()
+        .filter_map(|cycle| Some((cycle[0].as_u64()? as u16, cycle[1].as_u64()? as u8, cycle[2].as_str()?)))
+        .collect();
+
+    let log = cpu.memory.log.borrow();
+
+    for (cycle, expected) in accesses.iter().enumerate()
+    {
+        match log.get(cycle)
+        {
+            Some(actual) if actual == expected => {},
+            actual => return Err(format!("cycle {}: expected {:?}, got {:?}", cycle, expected, actual)),
+        }
+    }
+
+    if log.len() > accesses.len()
+    {
+        return Err(format!("{} bus accesses, expected {}, first extra {:?}", log.len(), accesses.len(), log[accesses.len()]));
+    }
+
+    // The count the CPU reports is computed separately from the accesses it makes
+    if accesses.len() != step.cycles as usize
+    {
+        return Err(format!("expected {} cycles, got {}", accesses.len(), step.cycles));
+    }
+
+    Ok(())
+}
+
+fn registers(state: &Value) -> RegisterSnapshot
+{
+    let field = |name: &str| state[name].as_u64().unwrap_or_else(|| panic!("Missing '{}' in {}", name, state));
+
+    RegisterSnapshot {
+        a:  field("a") as u8,
+        x:  field("x") as u8,
+        y:  field("y") as u8,
+        sp: field("s") as u8,
+        pc: field("pc") as u16,
+        p:  field("p") as u8 & STATUS_MASK,
+    }
+}
+
+// `[[addr, value], ...]`
+fn ram(state: &Value) -> Vec<(u16, u8)>
+{
+    state["ram"].as_array().into_iter().flatten()
+        .filter_map(|entry| Some((entry[0].as_u64()? as u16, entry[1].as_u64()? as u8)))
+        .collect()
+}